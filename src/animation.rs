@@ -0,0 +1,192 @@
+//! Charts whose wedges move from one frame to the next, as SMIL animations of the wedge paths.
+//! The angles of the wedges are interpolated rather than their paths, so that the wedges turn
+//! and grow instead of morphing, and the title of each frame is shown in turn.
+
+use crate::{PieChartTool, RenderData, FULL_PERCENTAGE};
+use svg::node::{element, Value};
+
+/// The paths sampled in each move from one frame to the next
+const STEPS: usize = 12;
+
+/// The timing of an animation and the path of each wedge over it
+#[derive(Debug)]
+pub(crate) struct Animation {
+    /// The seconds the frames take before they repeat
+    duration: f64,
+    /// The time of each path as a fraction of the duration, separated by `;`
+    key_times: String,
+    /// The paths of each wedge at the key times, separated by `;`
+    paths: Vec<String>,
+    /// The time of each sample and the percentage of each wedge at it
+    samples: Vec<(f64, Vec<f64>)>,
+    /// The title of each frame on one line, or none if the frames share their title
+    pub titles: Vec<String>,
+}
+
+/// Animate the wedges of the chart through the percentages of each frame, which are in the order
+/// of the wedges. Each frame is held for the first half of its duration and moves to the next
+/// for the second half, with the last moving back to the first.
+pub(crate) fn animate(
+    rd: &RenderData,
+    frames: &[Vec<f64>],
+    titles: &[String],
+    frame_duration: f64,
+) -> Animation {
+    let count = frames.len();
+    let duration = count as f64 * frame_duration;
+    let mut samples: Vec<(f64, Vec<f64>)> = vec![];
+
+    for (index, frame) in frames.iter().enumerate() {
+        let next = &frames[(index + 1) % count];
+        let start = index as f64 * frame_duration;
+        let hold = frame_duration / 2.0;
+
+        samples.push((start, frame.clone()));
+        samples.push((start + hold, frame.clone()));
+
+        for step in 1..STEPS {
+            let t = step as f64 / STEPS as f64;
+
+            samples.push((
+                start + hold + t * hold,
+                frame
+                    .iter()
+                    .zip(next)
+                    .map(|(from, to)| from + (to - from) * t)
+                    .collect(),
+            ));
+        }
+    }
+
+    samples.push((duration, frames[0].clone()));
+
+    let key_times = samples
+        .iter()
+        .map(|(time, _)| format!("{:.4}", time / duration))
+        .collect::<Vec<_>>()
+        .join(";");
+    let (start, sweep) = rd.angles();
+    let (x_center, y_center) = rd.center();
+    let (inner_radius, radius) = rd.ring_radii(0);
+    let paths = (0..rd.wedges.len())
+        .map(|index| {
+            samples
+                .iter()
+                .map(|(_, percentages)| {
+                    let a = start + sweep * percentages[..index].iter().sum::<f64>();
+                    let b = a + sweep * percentages[index];
+                    let whole = !rd.half && percentages[index] >= FULL_PERCENTAGE;
+
+                    Value::from(PieChartTool::arc_path(
+                        x_center,
+                        y_center,
+                        inner_radius,
+                        radius,
+                        whole,
+                        a,
+                        b,
+                    ))
+                    .to_string()
+                })
+                .collect::<Vec<_>>()
+                .join(";")
+        })
+        .collect();
+    let titles = if titles.iter().all(|title| *title == titles[0]) {
+        vec![]
+    } else {
+        titles
+            .iter()
+            .map(|title| title.replace('\n', " "))
+            .collect()
+    };
+
+    Animation {
+        duration,
+        key_times,
+        paths,
+        samples,
+        titles,
+    }
+}
+
+impl Animation {
+    /// The percentages of the wedges at each sample, with the seconds until the next, for formats
+    /// that draw the animation as a sequence of images
+    pub(crate) fn frames(self: &Self) -> impl Iterator<Item = (&[f64], f64)> {
+        self.samples
+            .windows(2)
+            .map(|pair| (pair[0].1.as_slice(), pair[1].0 - pair[0].0))
+    }
+
+    /// The animation of the path of a wedge
+    pub(crate) fn path(self: &Self, index: usize) -> element::Animate {
+        element::Animate::new()
+            .set("attributeName", "d")
+            .set("values", self.paths[index].as_str())
+            .set("keyTimes", self.key_times.as_str())
+            .set("dur", format!("{}s", self.duration))
+            .set("repeatCount", "indefinite")
+    }
+
+    /// The animation that shows the title of a frame for as long as the wedges are at that frame
+    pub(crate) fn title(self: &Self, index: usize) -> element::Animate {
+        let count = self.titles.len() as f64;
+        let (values, key_times) = if index == 0 {
+            ("1;0".to_string(), format!("0;{:.4}", 1.0 / count))
+        } else {
+            (
+                "0;1;0".to_string(),
+                format!(
+                    "0;{:.4};{:.4}",
+                    index as f64 / count,
+                    (index + 1) as f64 / count
+                ),
+            )
+        };
+
+        element::Animate::new()
+            .set("attributeName", "opacity")
+            .set("values", values)
+            .set("keyTimes", key_times)
+            .set("calcMode", "discrete")
+            .set("dur", format!("{}s", self.duration))
+            .set("repeatCount", "indefinite")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChartData, ChartOptions, SilentLog};
+
+    #[test]
+    fn interpolates_angles() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 1 }] }",
+        )
+        .unwrap();
+        let rd = PieChartTool::new(&SilentLog)
+            .process_chart_data(&chart_data, &ChartOptions::default())
+            .unwrap();
+        let frames = [vec![0.5, 0.5], vec![1.0, 0.0]];
+        let animation = animate(&rd, &frames, &["2020".to_string(), "2021".to_string()], 2.0);
+        let key_times: Vec<&str> = animation.key_times.split(';').collect();
+        let paths: Vec<&str> = animation.paths[1].split(';').collect();
+
+        assert_eq!(animation.duration, 4.0);
+        assert_eq!(key_times.len(), 2 * (STEPS + 1) + 1);
+        assert_eq!(key_times[1], "0.2500");
+        assert_eq!(key_times[STEPS + 1], "0.5000");
+        // The second wedge shrinks away and grows back, ending where it started
+        assert_eq!(paths[0], paths[1]);
+        assert_ne!(paths[0], paths[STEPS + 1]);
+        assert_eq!(paths[0], *paths.last().unwrap());
+        assert_eq!(animation.titles, ["2020", "2021"]);
+        assert_eq!(animation.frames().count(), 2 * (STEPS + 1));
+        assert_eq!(
+            animation.frames().next(),
+            Some(([0.5, 0.5].as_slice(), 1.0))
+        );
+    }
+}
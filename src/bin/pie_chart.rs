@@ -15,18 +15,25 @@ impl PieChartLog for PieChartLogger {
         println!("{}", args);
     }
     fn warning(self: &Self, args: Arguments) {
-        eprintln!("{}", format!("warning: {}", Paint::yellow(&args)));
+        eprintln!("warning: {}", Paint::yellow(&args));
     }
     fn error(self: &Self, args: Arguments) {
-        eprintln!("{}", format!("error: {}", Paint::red(&args)));
+        eprintln!("error: {}", Paint::red(&args));
+    }
+    fn debug(self: &Self, args: Arguments) {
+        eprintln!("debug: {}", Paint::blue(&args));
+    }
+    fn trace(self: &Self, args: Arguments) {
+        eprintln!("trace: {}", Paint::cyan(&args));
     }
 }
 
 fn main() {
     let logger = PieChartLogger::new();
+    let mut tool = PieChartTool::new(&logger);
 
-    if let Err(error) = PieChartTool::new(&logger).run(std::env::args_os()) {
-        error!(logger, "{}", error);
-        std::process::exit(1);
+    if let Err(error) = tool.run(std::env::args_os()) {
+        error!(tool.log(), "{}", error);
+        std::process::exit(error.exit_code());
     }
 }
@@ -0,0 +1,158 @@
+use crate::{ChartData, ChartOptions, ItemData, PieChartError, SeriesData};
+
+/// Builds a [`ChartData`] in code, validating it on [`ChartDataBuilder::build`]
+///
+/// ```
+/// use pie_chart::ChartData;
+///
+/// let chart_data = ChartData::builder()
+///     .title("Grades")
+///     .item("A", 2.0)
+///     .item("B", 30.0)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(chart_data.items.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ChartDataBuilder {
+    title: String,
+    subtitle: Option<String>,
+    caption: Option<String>,
+    items: Vec<ItemData>,
+    series: Vec<SeriesData>,
+    hole_ratio: Option<f64>,
+    seed: Option<u64>,
+    options: ChartOptions,
+    styles: Vec<String>,
+}
+
+impl ChartDataBuilder {
+    pub fn new() -> ChartDataBuilder {
+        ChartDataBuilder::default()
+    }
+
+    /// Set the chart title
+    pub fn title(mut self, title: impl Into<String>) -> ChartDataBuilder {
+        self.title = title.into();
+        self
+    }
+
+    /// Set the line drawn below the title
+    pub fn subtitle(mut self, subtitle: impl Into<String>) -> ChartDataBuilder {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    /// Set the line drawn below the legend, such as the source of the data
+    pub fn caption(mut self, caption: impl Into<String>) -> ChartDataBuilder {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    /// Add an item with the given key and value
+    pub fn item(mut self, key: impl Into<String>, value: f64) -> ChartDataBuilder {
+        self.items.push(ItemData::new(key, value));
+        self
+    }
+
+    /// Add an item with an explicit wedge color
+    pub fn item_with_color(
+        mut self,
+        key: impl Into<String>,
+        value: f64,
+        color: impl Into<String>,
+    ) -> ChartDataBuilder {
+        self.items.push(ItemData {
+            color: Some(color.into()),
+            ..ItemData::new(key, value)
+        });
+        self
+    }
+
+    /// Add several items at once
+    pub fn items(mut self, items: impl IntoIterator<Item = ItemData>) -> ChartDataBuilder {
+        self.items.extend(items);
+        self
+    }
+
+    /// Add a series of items, drawn as a ring outside those of the series before it
+    pub fn series(
+        mut self,
+        name: impl Into<String>,
+        items: impl IntoIterator<Item = ItemData>,
+    ) -> ChartDataBuilder {
+        self.series.push(SeriesData {
+            name: name.into(),
+            items: items.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Render the chart as a donut with the given inner radius ratio
+    pub fn hole_ratio(mut self, hole_ratio: f64) -> ChartDataBuilder {
+        self.hole_ratio = Some(hole_ratio);
+        self
+    }
+
+    /// Seed the random wedge colors
+    pub fn seed(mut self, seed: u64) -> ChartDataBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the chart options stored with the data
+    pub fn options(mut self, options: ChartOptions) -> ChartDataBuilder {
+        self.options = options;
+        self
+    }
+
+    /// Add a CSS rule to the generated styles
+    pub fn style(mut self, style: impl Into<String>) -> ChartDataBuilder {
+        self.styles.push(style.into());
+        self
+    }
+
+    /// Validate and return the chart data
+    pub fn build(self) -> Result<ChartData, PieChartError> {
+        let chart_data = ChartData {
+            title: self.title,
+            subtitle: self.subtitle,
+            caption: self.caption,
+            items: self.items,
+            series: self.series,
+            hole_ratio: self.hole_ratio,
+            seed: self.seed,
+            options: self.options,
+            styles: self.styles,
+        };
+
+        chart_data.validate()?;
+
+        Ok(chart_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_validates_items() {
+        assert!(ChartDataBuilder::new().title("Empty").build().is_err());
+        assert!(ChartDataBuilder::new().item("a", f64::NAN).build().is_err());
+        assert!(ChartDataBuilder::new()
+            .item_with_color("a", 1.0, "#12345")
+            .build()
+            .is_err());
+
+        let chart_data = ChartDataBuilder::new()
+            .title("Ok")
+            .item("a", 1.0)
+            .item_with_color("b", 0.0, "red")
+            .build()
+            .unwrap();
+
+        assert_eq!(chart_data.items[1].color.as_deref(), Some("red"));
+    }
+}
@@ -0,0 +1,209 @@
+//! Layout of labels drawn outside the pie. Each label sits in a column beside the pie, on the
+//! side its wedge is on, with a leader line that runs out from the wedge and then across to it.
+//! Labels that would overlap are spread apart vertically, with their leaders bending on the rim
+//! where the label is, and those nearest the top and bottom of a side that has no room for them
+//! are moved to the other side. Labels that still cannot all fit are spread evenly down their
+//! side.
+
+/// How far beyond the rim the leader bends
+pub(crate) const ELBOW_GAP: f64 = 12.0;
+/// The length of the leader from the bend to the label column, for a wedge at 3 or 9 o'clock
+pub(crate) const LEADER_LENGTH: f64 = 16.0;
+/// The space between the end of the leader and the label
+pub(crate) const TEXT_GAP: f64 = 4.0;
+
+#[derive(Debug)]
+pub(crate) struct Callout {
+    /// Where the leader meets the wedge
+    pub anchor: (f64, f64),
+    /// Where the leader bends to run across to the label
+    pub elbow: (f64, f64),
+    /// Where the leader ends, beside the middle of the label
+    pub end: (f64, f64),
+    /// Whether the label is on the left, so that it ends at the leader rather than starting there
+    pub left: bool,
+}
+
+/// Lay out a callout for each wedge, given as its middle angle and outer radius, around a pie of
+/// the given radius. Labels are kept `spacing` apart and between `min_y` and `max_y` where there
+/// is room.
+pub(crate) fn layout(
+    center: (f64, f64),
+    radius: f64,
+    wedges: &[(f64, f64)],
+    spacing: f64,
+    min_y: f64,
+    max_y: f64,
+) -> Vec<Callout> {
+    let (x_center, y_center) = center;
+    let elbow_radius = radius + ELBOW_GAP;
+    let mut callouts: Vec<Callout> = wedges
+        .iter()
+        .map(|&(angle, wedge_radius)| {
+            let left = angle.cos() < -1e-9;
+            let y = (y_center + elbow_radius * angle.sin()).clamp(min_y, max_y);
+            let end_x = if left {
+                x_center - elbow_radius - LEADER_LENGTH
+            } else {
+                x_center + elbow_radius + LEADER_LENGTH
+            };
+
+            Callout {
+                anchor: (
+                    x_center + wedge_radius * angle.cos(),
+                    y_center + wedge_radius * angle.sin(),
+                ),
+                elbow: (x_center + elbow_radius * angle.cos(), y),
+                end: (end_x, y),
+                left,
+            }
+        })
+        .collect();
+    let capacity = ((max_y - min_y) / spacing).floor().max(0.0) as usize + 1;
+
+    for left in [false, true] {
+        let count = |callouts: &[Callout], left| {
+            callouts
+                .iter()
+                .filter(|callout| callout.left == left)
+                .count()
+        };
+
+        while count(&callouts, left) > capacity && count(&callouts, !left) < capacity {
+            let Some(index) = (0..callouts.len())
+                .filter(|&index| callouts[index].left == left)
+                .min_by(|&a, &b| wedges[a].0.cos().abs().total_cmp(&wedges[b].0.cos().abs()))
+            else {
+                break;
+            };
+            let callout = &mut callouts[index];
+
+            callout.left = !left;
+            callout.end.0 = 2.0 * x_center - callout.end.0;
+        }
+    }
+
+    for left in [false, true] {
+        let mut side: Vec<usize> = (0..callouts.len())
+            .filter(|&index| callouts[index].left == left)
+            .collect();
+
+        side.sort_by(|&a, &b| callouts[a].end.1.total_cmp(&callouts[b].end.1));
+
+        // Push overlapping labels down, then back up from the bottom if they went too far
+        let mut ys: Vec<f64> = side.iter().map(|&index| callouts[index].end.1).collect();
+
+        for i in 1..ys.len() {
+            ys[i] = ys[i].max(ys[i - 1] + spacing);
+        }
+
+        if let Some(last) = ys.last_mut() {
+            *last = last.min(max_y);
+        }
+
+        for i in (0..ys.len().saturating_sub(1)).rev() {
+            ys[i] = ys[i].min(ys[i + 1] - spacing);
+        }
+
+        // Labels that cannot all fit are spread evenly down the side, closer than the spacing
+        if ys.first().is_some_and(|y| *y < min_y) {
+            let step = (max_y - min_y) / (ys.len() - 1).max(1) as f64;
+
+            for (i, y) in ys.iter_mut().enumerate() {
+                *y = min_y + i as f64 * step;
+            }
+        }
+
+        // Nudge each bend along the rim to the height of its label, so that leaders do not cross
+        for (&index, y) in side.iter().zip(ys) {
+            let dx = (elbow_radius.powi(2) - (y - y_center).powi(2))
+                .max(0.0)
+                .sqrt();
+
+            callouts[index].elbow = (if left { x_center - dx } else { x_center + dx }, y);
+            callouts[index].end.1 = y;
+        }
+    }
+
+    callouts
+}
+
+/// Whether any labels on the same side are closer than `spacing`, because there was no room to
+/// spread them apart
+pub(crate) fn crowded<'a>(callouts: impl Iterator<Item = &'a Callout>, spacing: f64) -> bool {
+    let mut ys: [Vec<f64>; 2] = [vec![], vec![]];
+
+    for callout in callouts {
+        ys[callout.left as usize].push(callout.end.1);
+    }
+
+    ys.iter_mut().any(|ys| {
+        ys.sort_by(f64::total_cmp);
+        ys.windows(2).any(|pair| pair[1] - pair[0] < spacing - 1e-6)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn spreads_out_crowded_labels() {
+        // Three thin wedges just right of 6 o'clock
+        let wedges = [
+            (FRAC_PI_2 - 0.03, 100.0),
+            (FRAC_PI_2 - 0.02, 100.0),
+            (FRAC_PI_2 - 0.01, 100.0),
+        ];
+        let callouts = layout((0.0, 0.0), 100.0, &wedges, 12.0, -100.0, 100.0);
+
+        assert!(callouts.iter().all(|callout| !callout.left));
+        assert_eq!(callouts[2].end.1, 100.0);
+        assert_eq!(callouts[1].end.1, 88.0);
+        assert_eq!(callouts[0].end.1, 76.0);
+        assert_eq!(callouts[0].end.0, 128.0);
+        // The bends move up the rim with their labels
+        assert!(callouts[0].elbow.0 > callouts[2].elbow.0);
+        assert!((callouts[0].elbow.0.hypot(callouts[0].elbow.1) - 112.0).abs() < 1e-9);
+
+        // With room for only five labels a side, the lowest wedges move to the left
+        let wedges: Vec<(f64, f64)> = (0..7)
+            .map(|i| (FRAC_PI_2 - 0.1 + i as f64 * 0.01, 100.0))
+            .collect();
+        let callouts = layout((0.0, 0.0), 100.0, &wedges, 12.0, 52.0, 100.0);
+        let left: Vec<bool> = callouts.iter().map(|callout| callout.left).collect();
+
+        assert_eq!(left, [false, false, false, false, false, true, true]);
+        assert_eq!(callouts[5].end.0, -128.0);
+        assert!(!crowded(callouts.iter(), 12.0));
+        assert!(crowded(
+            layout((0.0, 0.0), 100.0, &wedges, 12.0, 88.0, 100.0).iter(),
+            12.0
+        ));
+    }
+
+    #[test]
+    fn spreads_labels_evenly_when_full() {
+        // Room for two labels a side, so two move left and the other eight share the right
+        let wedges: Vec<(f64, f64)> = (0..10)
+            .map(|i| (FRAC_PI_2 - 0.1 + i as f64 * 0.01, 100.0))
+            .collect();
+        let callouts = layout((0.0, 0.0), 100.0, &wedges, 12.0, 88.0, 100.0);
+        let mut right: Vec<f64> = callouts
+            .iter()
+            .filter(|callout| !callout.left)
+            .map(|callout| callout.end.1)
+            .collect();
+
+        right.sort_by(f64::total_cmp);
+        assert_eq!(callouts.iter().filter(|callout| callout.left).count(), 2);
+        assert_eq!(right.len(), 8);
+        assert_eq!(right[0], 88.0);
+        assert_eq!(right[7], 100.0);
+        assert!(right
+            .windows(2)
+            .all(|pair| (pair[1] - pair[0] - 12.0 / 7.0).abs() < 1e-9));
+        assert!(crowded(callouts.iter(), 12.0));
+    }
+}
@@ -0,0 +1,401 @@
+//! Colors as they are written in chart files and options, which are `#rgb`, `#rrggbb`, `rgb()`,
+//! `hsl()` and the CSS named colors. Colors are written to SVG as they are given, so they are
+//! parsed to check them and for the formats and calculations that need RGB values.
+
+use crate::PieChartError;
+use std::f64::consts::TAU;
+
+/// The CSS named colors, in alphabetical order so that they can be searched
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+const EXAMPLES: &str = "'#ff0000', 'rgb(255, 0, 0)', 'hsl(0, 100%, 50%)' or 'red'";
+
+/// A color parsed to a 24 bit RGB value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Color(pub u32);
+
+impl Color {
+    /// Parse a color, or say what is wrong with it
+    pub(crate) fn parse(text: &str) -> Result<Color, String> {
+        let text = text.trim().to_ascii_lowercase();
+
+        if let Some(hex) = text.strip_prefix('#') {
+            let rgb = u32::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.chars().all(|c| c.is_ascii_hexdigit()));
+
+            return match (hex.len(), rgb) {
+                (3, Some(rgb)) => Ok(Color(
+                    ((rgb >> 8) & 0xf) * 0x110000
+                        + ((rgb >> 4) & 0xf) * 0x1100
+                        + (rgb & 0xf) * 0x11,
+                )),
+                (6, Some(rgb)) => Ok(Color(rgb)),
+                _ => Err("hex colors have 3 or 6 hex digits".to_string()),
+            };
+        }
+
+        if let Some(args) = function_args(&text, "rgb") {
+            return match args.as_slice() {
+                [r, g, b] => [r, g, b]
+                    .iter()
+                    .try_fold(0, |rgb, arg| {
+                        channel(arg).map(|c| rgb << 8 | c).ok_or_else(|| {
+                            format!(
+                                "rgb() takes numbers from 0 to 255 or percentages, not '{}'",
+                                arg
+                            )
+                        })
+                    })
+                    .map(Color),
+                _ => Err("rgb() takes a red, a green and a blue".to_string()),
+            };
+        }
+
+        if let Some(args) = function_args(&text, "hsl") {
+            let hue = |arg: &str| arg.strip_suffix("deg").unwrap_or(arg).parse::<f64>().ok();
+
+            return match args.as_slice() {
+                [h, s, l] => match (hue(h), percentage(s), percentage(l)) {
+                    (Some(h), Some(s), Some(l)) => Ok(Color(hsl_to_rgb(h / 360.0, s, l))),
+                    _ => Err("hsl() takes a hue in degrees and two percentages".to_string()),
+                },
+                _ => Err("hsl() takes a hue, a saturation and a lightness".to_string()),
+            };
+        }
+
+        if !text.is_empty() && text.chars().all(|c| c.is_ascii_alphabetic()) {
+            return NAMED_COLORS
+                .binary_search_by(|(name, _)| name.cmp(&text.as_str()))
+                .map(|index| Color(NAMED_COLORS[index].1))
+                .map_err(|_| format!("'{}' is not a CSS color name", text));
+        }
+
+        Err(format!("colors are written like {}", EXAMPLES))
+    }
+}
+
+/// The RGB value of a color, or `None` if it is not one
+pub(crate) fn rgb(color: &str) -> Option<u32> {
+    Color::parse(color).ok().map(|color| color.0)
+}
+
+/// Check a color given for a part of the chart, such as the `background` color
+pub(crate) fn validate(name: &str, color: &str) -> Result<Color, PieChartError> {
+    Color::parse(color).map_err(|reason| {
+        PieChartError::Validation(format!("Invalid {} color '{}', as {}", name, color, reason))
+    })
+}
+
+/// The arguments of a CSS function like `rgb(1, 2, 3)`, separated by commas or spaces
+fn function_args<'a>(text: &'a str, name: &str) -> Option<Vec<&'a str>> {
+    let args = text
+        .strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+
+    Some(
+        args.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .collect(),
+    )
+}
+
+fn percentage(arg: &str) -> Option<f64> {
+    arg.strip_suffix('%')?
+        .parse::<f64>()
+        .ok()
+        .filter(|p| (0.0..=100.0).contains(p))
+        .map(|p| p / 100.0)
+}
+
+fn channel(arg: &str) -> Option<u32> {
+    let value = match percentage(arg) {
+        Some(fraction) => fraction * 255.0,
+        None => arg
+            .parse::<f64>()
+            .ok()
+            .filter(|c| (0.0..=255.0).contains(c))?,
+    };
+
+    Some(value.round() as u32)
+}
+
+/// Scale the channels, each from 0 to 1, to a 24 bit RGB value
+fn to_rgb(channels: [f64; 3]) -> u32 {
+    channels.iter().fold(0, |rgb, c| {
+        rgb << 8 | (c.clamp(0.0, 1.0) * 255.0).round() as u32
+    })
+}
+
+/// The color with the given hue, as a fraction of a turn, and saturation and value from 0 to 1
+pub(crate) fn hsv_to_rgb(h: f64, s: f64, v: f64) -> u32 {
+    let h_i = (h.rem_euclid(1.0) * 6.0) as usize;
+    let f = h.rem_euclid(1.0) * 6.0 - h_i as f64;
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - f * s);
+    let t = v * (1.0 - (1.0 - f) * s);
+
+    to_rgb(match h_i {
+        0 => [v, t, p],
+        1 => [q, v, p],
+        2 => [p, v, t],
+        3 => [p, q, v],
+        4 => [t, p, v],
+        _ => [v, p, q],
+    })
+}
+
+/// The color with the given hue, as a fraction of a turn, and saturation and lightness from 0
+/// to 1
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> u32 {
+    let v = l + s * l.min(1.0 - l);
+    let s = if v == 0.0 { 0.0 } else { 2.0 * (1.0 - l / v) };
+
+    hsv_to_rgb(h, s, v)
+}
+
+/// The color with the given OKLCH lightness, chroma and hue, where the hue is a fraction of a
+/// turn. Colors outside sRGB have their chroma reduced until they fit, keeping their lightness.
+pub(crate) fn oklch_to_rgb(l: f64, c: f64, h: f64) -> u32 {
+    let to_linear_rgb = |c: f64| {
+        let (a, b) = (c * (h * TAU).cos(), c * (h * TAU).sin());
+        let cube = |x: f64| x * x * x;
+        let l_ = cube(l + 0.3963377774 * a + 0.2158037573 * b);
+        let m_ = cube(l - 0.1055613458 * a - 0.0638541728 * b);
+        let s_ = cube(l - 0.0894841775 * a - 1.2914855480 * b);
+
+        [
+            4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_,
+            -1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_,
+            -0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_,
+        ]
+    };
+    let in_gamut = |rgb: &[f64; 3]| rgb.iter().all(|c| (-1e-6..=1.0 + 1e-6).contains(c));
+    let mut rgb = to_linear_rgb(c);
+
+    if !in_gamut(&rgb) {
+        let (mut low, mut high) = (0.0, c);
+
+        for _ in 0..20 {
+            let middle = (low + high) / 2.0;
+
+            if in_gamut(&to_linear_rgb(middle)) {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+
+        rgb = to_linear_rgb(low);
+    }
+
+    to_rgb(rgb.map(|c| {
+        if c <= 0.0031308 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        let rgb = |text: &str| Color::parse(text).map(|color| color.0);
+
+        assert_eq!(rgb("#fff"), Ok(0xffffff));
+        assert_eq!(rgb("#A0B1C2"), Ok(0xa0b1c2));
+        assert_eq!(rgb("rgb(10, 20, 30)"), Ok(0x0a141e));
+        assert_eq!(rgb("rgb(100% 0% 50%)"), Ok(0xff0080));
+        assert_eq!(rgb("hsl(120deg, 100%, 25%)"), Ok(0x008000));
+        assert_eq!(rgb("SteelBlue"), Ok(0x4682b4));
+        assert!(rgb("#abcd").unwrap_err().contains("3 or 6"));
+        assert!(rgb("rgb(300,0,0)").unwrap_err().contains("'300'"));
+        assert!(rgb("stelblue")
+            .unwrap_err()
+            .contains("not a CSS color name"));
+        assert!(rgb("red;x").is_err());
+        assert!(matches!(
+            validate("background", "#12"),
+            Err(PieChartError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn converts_hsv_without_overflow() {
+        assert_eq!(hsv_to_rgb(0.0, 0.0, 1.0), 0xffffff);
+        assert_eq!(hsv_to_rgb(0.0, 1.0, 1.0), 0xff0000);
+        assert_eq!(hsv_to_rgb(0.5, 0.5, 0.5), 0x408080);
+    }
+
+    #[test]
+    fn oklch_colors_are_equally_light() {
+        // Relative luminance is the cube of Oklab lightness for grays, and near it for colors
+        let luminance = |rgb: u32| {
+            let linear = |shift: u32| (((rgb >> shift) & 0xff) as f64 / 255.0).powf(2.2);
+
+            0.2126 * linear(16) + 0.7152 * linear(8) + 0.0722 * linear(0)
+        };
+        let luminances: Vec<f64> = (0..6)
+            .map(|i| luminance(oklch_to_rgb(0.7, 0.12, i as f64 / 6.0)))
+            .collect();
+
+        assert_eq!(oklch_to_rgb(1.0, 0.0, 0.0), 0xffffff);
+        assert_eq!(oklch_to_rgb(0.0, 0.0, 0.0), 0x000000);
+        assert!(luminances.iter().all(|l| (l - 0.343).abs() < 0.05));
+    }
+}
@@ -0,0 +1,88 @@
+//! Simulation of the common color vision deficiencies, used to find wedge colors that would be
+//! hard to tell apart. The matrices are those of Machado et al. (2009) at full severity, applied
+//! to linear RGB, and colors are compared by their distance in CIELAB.
+
+/// The CIELAB distance below which two colors are hard to tell apart
+const MIN_DISTANCE: f64 = 12.0;
+
+const DEFICIENCIES: [(&str, [[f64; 3]; 3]); 3] = [
+    (
+        "protanopia",
+        [
+            [0.152286, 1.052583, -0.204868],
+            [0.114503, 0.786281, 0.099216],
+            [-0.003882, -0.048116, 1.051998],
+        ],
+    ),
+    (
+        "deuteranopia",
+        [
+            [0.367322, 0.860646, -0.227968],
+            [0.280085, 0.672501, 0.047413],
+            [-0.011820, 0.042940, 0.968881],
+        ],
+    ),
+    (
+        "tritanopia",
+        [
+            [1.255528, -0.076749, -0.178779],
+            [-0.078411, 0.930809, 0.147602],
+            [0.004733, 0.691367, 0.303900],
+        ],
+    ),
+];
+
+/// The first deficiency, if any, under which the two colors are hard to tell apart
+pub(crate) fn confused_by(a: u32, b: u32) -> Option<&'static str> {
+    DEFICIENCIES.iter().find_map(|(name, matrix)| {
+        let (a, b) = (lab(simulate(matrix, a)), lab(simulate(matrix, b)));
+        let distance =
+            ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt();
+
+        (distance < MIN_DISTANCE).then_some(*name)
+    })
+}
+
+fn simulate(matrix: &[[f64; 3]; 3], rgb: u32) -> [f64; 3] {
+    let linear = |shift: u32| {
+        let c = ((rgb >> shift) & 0xff) as f64 / 255.0;
+
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let rgb = [linear(16), linear(8), linear(0)];
+
+    matrix.map(|row| (row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]).clamp(0.0, 1.0))
+}
+
+fn lab(rgb: [f64; 3]) -> [f64; 3] {
+    let [r, g, b] = rgb;
+    // D65 white point
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_red_green_confusion() {
+        assert_eq!(confused_by(0xd62728, 0x6b8e23), Some("deuteranopia"));
+        assert_eq!(confused_by(0x000000, 0xffffff), None);
+    }
+}
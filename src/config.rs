@@ -0,0 +1,79 @@
+//! User defaults for the chart options, read from a TOML file holding the same keys as the
+//! `options` block of a chart file
+
+use crate::{input, ChartOptions, PieChartError};
+use std::{env, fs, path::Path, path::PathBuf};
+
+/// The default config file, under `$XDG_CONFIG_HOME` or `~/.config`
+pub(crate) fn default_path() -> Option<PathBuf> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?)
+            .join(".config"),
+    };
+
+    Some(config_home.join("pie_chart").join("config.toml"))
+}
+
+/// Read the options in the given config file, or in the default one if it exists
+pub(crate) fn load(path: Option<&Path>) -> Result<ChartOptions, PieChartError> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => match default_path() {
+            Some(path) if path.is_file() => path,
+            _ => return Ok(ChartOptions::default()),
+        },
+    };
+    let content = fs::read_to_string(&path).map_err(|e| PieChartError::Io {
+        message: format!("Unable to read config file '{}'", path.to_string_lossy()),
+        source: e,
+    })?;
+    let in_file = |message: String| format!("{} in '{}'", message, path.to_string_lossy());
+    let value = input::toml::parse(&content).map_err(|e| match e {
+        PieChartError::Parse {
+            message,
+            line,
+            column,
+        } => PieChartError::Parse {
+            message: in_file(message),
+            line,
+            column,
+        },
+        e => e,
+    })?;
+
+    serde_json::from_value(value).map_err(|e| PieChartError::Parse {
+        message: in_file(e.to_string()),
+        line: None,
+        column: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Palette, Theme};
+
+    #[test]
+    fn loads_options() {
+        let path = env::temp_dir().join(format!("pie_chart_config_{}.toml", std::process::id()));
+
+        fs::write(
+            &path,
+            "palette = \"tableau10\"\ntheme = \"dark\"\nsize = 300\n\n[title_style]\nfont_size = 20\n",
+        )
+        .unwrap();
+
+        let options = load(Some(&path)).unwrap();
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(options.palette, Some(Palette::Tableau10));
+        assert_eq!(options.theme, Some(Theme::Dark));
+        assert_eq!(options.size, Some(300.0));
+        assert_eq!(options.title_style.font_size, Some(20.0));
+        assert!(matches!(
+            load(Some(Path::new("/nonexistent/config.toml"))),
+            Err(PieChartError::Io { .. })
+        ));
+    }
+}
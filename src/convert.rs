@@ -0,0 +1,404 @@
+//! Chart files written in the formats they are read from, so that charts can be moved from one
+//! format to another. The charts are given as the value they serialize to, with the options that
+//! are not set left out. CSV files and Mermaid diagrams only hold the items of one chart, so the
+//! title and options are lost in them, and charts with series or children cannot be written.
+
+use crate::{ChartData, InputFormat, LegendValues, PieChartError};
+use serde_json::{Map, Value};
+use std::fmt::Write;
+
+/// The keys that are written first, in this order, as they are in the fields of the charts and
+/// items. Any others follow in alphabetical order.
+const KEY_ORDER: [&str; 13] = [
+    "title", "subtitle", "caption", "key", "value", "color", "href", "class", "children", "items",
+    "series", "options", "styles",
+];
+
+/// The charts as the text of a chart file in the format, given the value they serialize to
+pub(crate) fn write(
+    charts: &[ChartData],
+    value: &Value,
+    format: InputFormat,
+) -> Result<String, PieChartError> {
+    let mut s = String::new();
+    let mut value = value.clone();
+
+    // Charts without options are written without an empty table of them
+    for chart in match value {
+        Value::Array(ref mut charts) => charts.iter_mut().collect(),
+        ref mut chart => vec![chart],
+    } {
+        if let Value::Object(map) = chart {
+            map.retain(|key, value| {
+                key != "options" || value.as_object().is_none_or(|map| !map.is_empty())
+            });
+        }
+    }
+
+    let value = &value;
+
+    match format {
+        InputFormat::Json | InputFormat::Json5 => {
+            json(&mut s, value, 0, format == InputFormat::Json5);
+            s.push('\n');
+        }
+        InputFormat::Yaml => match value {
+            Value::Object(_) | Value::Array(_) => yaml(&mut s, value, 0),
+            _ => s = format!("{}\n", value),
+        },
+        InputFormat::Toml => match value {
+            Value::Array(charts) => {
+                for (index, chart) in charts.iter().enumerate() {
+                    let _ = writeln!(s, "{}[[charts]]", if index > 0 { "\n" } else { "" });
+                    toml_table(&mut s, chart);
+                }
+            }
+            chart => toml_table(&mut s, chart),
+        },
+        InputFormat::Csv | InputFormat::Mermaid => {
+            let chart_data = match charts {
+                [chart_data]
+                    if chart_data.series.is_empty()
+                        && chart_data.items.iter().all(|item| item.children.is_empty()) =>
+                {
+                    chart_data
+                }
+                _ => {
+                    return Err(PieChartError::Validation(format!(
+                        "{} files only hold the items of one chart, without series or children",
+                        if format == InputFormat::Csv {
+                            "CSV"
+                        } else {
+                            "Mermaid"
+                        }
+                    )))
+                }
+            };
+
+            if format == InputFormat::Csv {
+                csv(&mut s, chart_data);
+            } else {
+                mermaid(&mut s, chart_data);
+            }
+        }
+        InputFormat::Xlsx | InputFormat::Svg => {
+            return Err(PieChartError::Usage(
+                "Charts can only be converted to JSON5, JSON, CSV, YAML, TOML or Mermaid"
+                    .to_string(),
+            ))
+        }
+    }
+
+    Ok(s)
+}
+
+/// The entries of an object, in the order of `KEY_ORDER`
+fn entries(map: &Map<String, Value>) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+
+    entries.sort_by_key(|(key, _)| {
+        KEY_ORDER
+            .iter()
+            .position(|name| name == key)
+            .unwrap_or(KEY_ORDER.len())
+    });
+    entries
+}
+
+fn indent(s: &mut String, level: usize) {
+    s.extend(std::iter::repeat_n("  ", level));
+}
+
+/// A key as it is written in JSON5, which is without quotes if it is an identifier
+fn json5_key(key: &str) -> String {
+    let mut chars = key.chars();
+
+    if chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+    {
+        key.to_string()
+    } else {
+        Value::from(key).to_string()
+    }
+}
+
+/// JSON with a line for each value, or JSON5 with a comma after each and keys without quotes
+/// where they can be, as chart files are written by hand
+fn json(s: &mut String, value: &Value, level: usize, is_json5: bool) {
+    let comma =
+        |s: &mut String, is_last: bool| s.push_str(if is_last && !is_json5 { "\n" } else { ",\n" });
+
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            s.push_str("{\n");
+
+            for (index, (key, value)) in entries(map).into_iter().enumerate() {
+                indent(s, level + 1);
+
+                if is_json5 {
+                    let _ = write!(s, "{}: ", json5_key(key));
+                } else {
+                    let _ = write!(s, "{}: ", Value::from(key.as_str()));
+                }
+
+                json(s, value, level + 1, is_json5);
+                comma(s, index + 1 == map.len());
+            }
+
+            indent(s, level);
+            s.push('}');
+        }
+        Value::Array(values) if !values.is_empty() => {
+            s.push_str("[\n");
+
+            for (index, value) in values.iter().enumerate() {
+                indent(s, level + 1);
+                json(s, value, level + 1, is_json5);
+                comma(s, index + 1 == values.len());
+            }
+
+            indent(s, level);
+            s.push(']');
+        }
+        value => s.push_str(&value.to_string()),
+    }
+}
+
+/// Block YAML, with strings in double quotes so that none are read as numbers or booleans
+fn yaml(s: &mut String, value: &Value, level: usize) {
+    let scalar = |value: &Value| match value {
+        Value::Object(_) => "{}".to_string(),
+        Value::Array(_) => "[]".to_string(),
+        value => value.to_string(),
+    };
+    let is_block = |value: &Value| match value {
+        Value::Object(map) => !map.is_empty(),
+        Value::Array(values) => !values.is_empty(),
+        _ => false,
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (index, (key, value)) in entries(map).into_iter().enumerate() {
+                // The first key of a sequence entry follows its dash
+                if index > 0 || !s.ends_with("- ") {
+                    indent(s, level);
+                }
+
+                if is_block(value) {
+                    let _ = writeln!(s, "{}:", bare_key(key));
+                    yaml(s, value, level + 1);
+                } else {
+                    let _ = writeln!(s, "{}: {}", bare_key(key), scalar(value));
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                indent(s, level);
+
+                if is_block(value) {
+                    s.push_str("- ");
+                    yaml(s, value, level + 1);
+                } else {
+                    let _ = writeln!(s, "- {}", scalar(value));
+                }
+            }
+        }
+        value => {
+            let _ = writeln!(s, "{}", scalar(value));
+        }
+    }
+}
+
+/// A key as it is written in TOML and YAML, which is without quotes if it is bare
+fn bare_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        Value::from(key).to_string()
+    }
+}
+
+/// A TOML value on one line, with tables written inline
+fn toml_value(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let entries: Vec<String> = entries(map)
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| format!("{} = {}", bare_key(key), toml_value(value)))
+                .collect();
+
+            if entries.is_empty() {
+                "{}".to_string()
+            } else {
+                format!("{{ {} }}", entries.join(", "))
+            }
+        }
+        Value::Array(values) => format!(
+            "[{}]",
+            values.iter().map(toml_value).collect::<Vec<_>>().join(", ")
+        ),
+        value => value.to_string(),
+    }
+}
+
+/// The keys of a table, with arrays of tables such as the items given a line each
+fn toml_table(s: &mut String, value: &Value) {
+    let Value::Object(map) = value else {
+        return;
+    };
+
+    for (key, value) in entries(map)
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+    {
+        match value {
+            Value::Array(values) if values.iter().any(Value::is_object) => {
+                let _ = writeln!(s, "{} = [", bare_key(key));
+
+                for value in values {
+                    let _ = writeln!(s, "  {},", toml_value(value));
+                }
+
+                s.push_str("]\n");
+            }
+            value => {
+                let _ = writeln!(s, "{} = {}", bare_key(key), toml_value(value));
+            }
+        }
+    }
+}
+
+/// A CSV field, quoted if it has a comma, quote or line break in it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// `key,value` rows with a header, and the colors and links of the items if any have them
+fn csv(s: &mut String, chart_data: &ChartData) {
+    let has_color = chart_data.items.iter().any(|item| item.color.is_some());
+    let has_href = chart_data.items.iter().any(|item| item.href.is_some());
+
+    s.push_str("key,value");
+
+    if has_color {
+        s.push_str(",color");
+    }
+
+    if has_href {
+        s.push_str(",href");
+    }
+
+    s.push('\n');
+
+    for item in &chart_data.items {
+        let _ = write!(s, "{},{}", csv_field(&item.key), item.value);
+
+        if has_color {
+            let _ = write!(
+                s,
+                ",{}",
+                csv_field(item.color.as_deref().unwrap_or_default())
+            );
+        }
+
+        if has_href {
+            let _ = write!(
+                s,
+                ",{}",
+                csv_field(item.href.as_deref().unwrap_or_default())
+            );
+        }
+
+        s.push('\n');
+    }
+}
+
+/// A `pie` diagram, which shows the values in the legend if the chart does
+fn mermaid(s: &mut String, chart_data: &ChartData) {
+    s.push_str("pie");
+
+    if chart_data.options.legend_values == Some(LegendValues::Both) {
+        s.push_str(" showData");
+    }
+
+    s.push('\n');
+
+    if !chart_data.title.is_empty() {
+        let _ = writeln!(s, "    title {}", chart_data.title.replace('\n', " "));
+    }
+
+    for item in &chart_data.items {
+        let _ = writeln!(s, "    \"{}\" : {}", item.key.replace('"', "'"), item.value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input, PieChartTool};
+
+    #[test]
+    fn writes_each_format() {
+        let charts = input::parse(
+            InputFormat::Json5,
+            "[{ title: 'Costs', items: [{ key: 'a, b', value: 1.5, color: 'red' }, { key: 'c', value: 2, children: [{ key: 'd', value: 2 }] }], options: { donut: true } }, { title: 'Two', items: [{ key: 'e', value: 1 }], styles: [] }]",
+            "",
+            &Default::default(),
+        )
+        .unwrap();
+        let value = PieChartTool::chart_value(&charts);
+
+        // Each format reads back as the same charts
+        for format in [
+            InputFormat::Json5,
+            InputFormat::Json,
+            InputFormat::Yaml,
+            InputFormat::Toml,
+        ] {
+            let text = write(&charts, &value, format).unwrap();
+            let read = input::parse(format, &text, "", &Default::default()).unwrap();
+
+            assert_eq!(
+                PieChartTool::chart_value(&read),
+                value,
+                "{:?}\n{}",
+                format,
+                text
+            );
+        }
+
+        assert!(write(&charts, &value, InputFormat::Csv).is_err());
+        assert!(write(&charts, &value, InputFormat::Svg).is_err());
+
+        let mut flat = charts[..1].to_vec();
+
+        flat[0].items[1].children.clear();
+
+        let csv = write(&flat, &value, InputFormat::Csv).unwrap();
+        let mermaid = write(&flat, &value, InputFormat::Mermaid).unwrap();
+
+        assert_eq!(csv, "key,value,color\n\"a, b\",1.5,red\nc,2,\n");
+        assert_eq!(
+            mermaid,
+            "pie\n    title Costs\n    \"a, b\" : 1.5\n    \"c\" : 2\n"
+        );
+        assert_eq!(
+            input::parse(InputFormat::Csv, &csv, "Costs", &Default::default()).unwrap()[0].items[0]
+                .key,
+            "a, b"
+        );
+    }
+}
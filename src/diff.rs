@@ -0,0 +1,143 @@
+//! The change in each item from one chart to another, such as from one month to the next. The
+//! change is drawn as a pie of how far each item moved, in either direction, with a table below
+//! it of the values and their change as a percentage.
+
+use crate::{
+    number_format::NumberFormatter,
+    strings::{Message, Strings},
+    ChartData, ItemData,
+};
+
+/// An item of either chart, with its total in each, which is 0 in the chart it is missing from
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Change {
+    pub key: String,
+    pub before: f64,
+    pub after: f64,
+}
+
+impl Change {
+    pub fn delta(self: &Self) -> f64 {
+        self.after - self.before
+    }
+
+    /// The change as a percentage with its sign, such as `+25%`, or `new` for an item that is
+    /// only in the later chart
+    pub fn percent(self: &Self, formatter: &NumberFormatter, strings: Strings) -> String {
+        if self.before == 0.0 {
+            return if self.after == 0.0 {
+                formatter.percent(0.0)
+            } else {
+                strings.get(Message::New).to_string()
+            };
+        }
+
+        let fraction = self.delta() / self.before.abs();
+        let sign = if fraction > 0.0 { "+" } else { "" };
+
+        format!("{}{}", sign, formatter.percent(fraction))
+    }
+}
+
+/// The items of both charts, in the order they first appear, with the totals of any that repeat
+pub(crate) fn changes(before: &ChartData, after: &ChartData) -> Vec<Change> {
+    let mut changes: Vec<Change> = vec![];
+    let items = (before.items.iter().map(|item| (item, false)))
+        .chain(after.items.iter().map(|item| (item, true)));
+
+    for (item, is_after) in items {
+        let index = match changes.iter().position(|change| change.key == item.key) {
+            Some(index) => index,
+            None => {
+                changes.push(Change {
+                    key: item.key.clone(),
+                    before: 0.0,
+                    after: 0.0,
+                });
+                changes.len() - 1
+            }
+        };
+
+        if is_after {
+            changes[index].after += item.total();
+        } else {
+            changes[index].before += item.total();
+        }
+    }
+
+    changes
+}
+
+/// A chart with a wedge for how much each item changed, keeping the colors and links of the
+/// items and the options of the later chart
+pub(crate) fn chart(
+    before: &ChartData,
+    after: &ChartData,
+    changes: &[Change],
+    strings: Strings,
+) -> ChartData {
+    let items = changes
+        .iter()
+        .map(|change| {
+            let item = after
+                .items
+                .iter()
+                .chain(&before.items)
+                .find(|item| item.key == change.key);
+
+            ItemData {
+                key: change.key.clone(),
+                value: change.delta().abs(),
+                color: item.and_then(|item| item.color.clone()),
+                href: item.and_then(|item| item.href.clone()),
+                class: item.and_then(|item| item.class.clone()),
+                children: vec![],
+            }
+        })
+        .collect();
+
+    ChartData {
+        title: strings.format(
+            Message::ChangeFrom,
+            &[
+                &before.title.replace('\n', " "),
+                &after.title.replace('\n', " "),
+            ],
+        ),
+        subtitle: None,
+        caption: None,
+        items,
+        ..after.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_changes() {
+        let before: ChartData =
+            json5::from_str("{ title: 'May', items: [{ key: 'a', value: 4 }, { key: 'b', value: 2 }, { key: 'a', value: 4 }] }")
+                .unwrap();
+        let after: ChartData = json5::from_str(
+            "{ title: 'June', items: [{ key: 'c', value: 1, color: 'red' }, { key: 'a', value: 6 }] }",
+        )
+        .unwrap();
+        let changes = changes(&before, &after);
+        let formatter = NumberFormatter::default();
+        let percents: Vec<String> = changes
+            .iter()
+            .map(|change| change.percent(&formatter, Strings::default()))
+            .collect();
+
+        assert_eq!(percents, ["-25%", "-100%", "new"]);
+
+        let chart = chart(&before, &after, &changes, Strings::default());
+        let values: Vec<f64> = chart.items.iter().map(|item| item.value).collect();
+
+        assert_eq!(chart.title, "Change from May to June");
+        assert_eq!(values, [2.0, 2.0, 1.0]);
+        assert_eq!(chart.items[2].color.as_deref(), Some("red"));
+    }
+}
@@ -0,0 +1,102 @@
+use std::{error::Error, fmt, io};
+
+/// An error producing a chart
+#[derive(Debug)]
+pub enum PieChartError {
+    /// The command line arguments are invalid
+    Usage(String),
+    /// The chart data or options are invalid
+    Validation(String),
+    /// The chart has no items
+    NoItems,
+    /// An item value is below zero
+    NegativeValue { key: String, value: f64 },
+    /// An item value is NaN or infinite
+    NonFiniteValue { key: String, value: f64 },
+    /// An item color is not a hex, `rgb()`, `hsl()` or named color
+    InvalidColor { key: String, color: String },
+    /// The item values add up to zero, so there is nothing to divide
+    ZeroTotal,
+    /// The chart file could not be parsed
+    Parse {
+        message: String,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    /// A file could not be read or written
+    Io { message: String, source: io::Error },
+    /// The chart could not be rendered, such as with a broken template
+    Render(String),
+}
+
+impl PieChartError {
+    /// The exit code of the command line tool for the error, so that scripts can tell the kinds
+    /// of failure apart
+    pub fn exit_code(self: &Self) -> i32 {
+        match self {
+            PieChartError::Usage(_) => 2,
+            PieChartError::Parse { .. } => 3,
+            PieChartError::Io { .. } => 5,
+            PieChartError::Render(_) => 6,
+            _ => 4,
+        }
+    }
+
+    /// Make an IO error with a message saying what could not be done, for use with `map_err`
+    pub(crate) fn io(message: impl Into<String>) -> impl FnOnce(io::Error) -> PieChartError {
+        let message = message.into();
+
+        move |source| PieChartError::Io { message, source }
+    }
+}
+
+impl fmt::Display for PieChartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PieChartError::Usage(message) => write!(f, "{}", message),
+            PieChartError::Validation(message) => write!(f, "{}", message),
+            PieChartError::NoItems => write!(f, "Chart must have at least one item"),
+            PieChartError::NegativeValue { key, value } => write!(
+                f,
+                "Item '{}' has a negative value {}, but values must be at least 0",
+                key, value
+            ),
+            PieChartError::NonFiniteValue { key, value } => write!(
+                f,
+                "Item '{}' has the value {}, but values must be finite numbers",
+                key, value
+            ),
+            PieChartError::InvalidColor { key, color } => write!(
+                f,
+                "Item '{}' has an invalid color '{}', which should be like '#ff0000', 'rgb(255, 0, 0)', 'hsl(0, 100%, 50%)' or 'red'",
+                key, color
+            ),
+            PieChartError::ZeroTotal => write!(
+                f,
+                "Chart item values add up to zero, so at least one must be greater than 0"
+            ),
+            PieChartError::Parse {
+                message,
+                line,
+                column,
+            } => match (line, column) {
+                (Some(line), Some(column)) => {
+                    write!(f, "{} at line {}, column {}", message, line, column)
+                }
+                (Some(line), None) => write!(f, "{} at line {}", message, line),
+                _ => write!(f, "{}", message),
+            },
+            PieChartError::Io { message, source } => write!(f, "{}: {}", message, source),
+            PieChartError::Render(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for PieChartError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PieChartError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
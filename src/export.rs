@@ -0,0 +1,139 @@
+//! Chart definitions for other charting tools, converted from the laid out chart so that the
+//! wedges have the same values, colors and angles as they are drawn here.
+
+use crate::RenderData;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::f64::consts::FRAC_PI_2;
+
+const VEGA_LITE_SCHEMA: &str = "https://vega.github.io/schema/vega-lite/v5.json";
+
+/// The format charts are exported to
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    /// A Vega-Lite spec with an arc mark, concatenating several charts
+    VegaLite,
+}
+
+/// A Vega-Lite spec for the charts, with a view for each if there are several
+pub(crate) fn vega_lite(charts: &[RenderData]) -> Value {
+    let mut spec = match charts {
+        [rd] => vega_lite_view(rd),
+        charts => json!({ "concat": charts.iter().map(vega_lite_view).collect::<Vec<_>>() }),
+    };
+
+    spec.as_object_mut()
+        .unwrap()
+        .insert("$schema".to_string(), VEGA_LITE_SCHEMA.into());
+    spec
+}
+
+/// A view of one chart, with a layer for each series of a chart of several. Vega-Lite measures
+/// angles from the top rather than from the right, and keeps the wedges in the order of the data
+/// when the color is not sorted.
+fn vega_lite_view(rd: &RenderData) -> Value {
+    let (start, sweep) = rd.angles();
+    let start = start + FRAC_PI_2;
+    let mut arcs: Vec<Value> = rd
+        .rings()
+        .map(|(level, wedges)| {
+            let (inner_radius, radius) = rd.ring_radii(level);
+            let values: Vec<Value> = wedges
+                .iter()
+                .map(|wedge| json!({ "key": wedge.key, "value": wedge.value }))
+                .collect();
+
+            json!({
+                "data": { "values": values },
+                "mark": {
+                    "type": "arc",
+                    "innerRadius": inner_radius,
+                    "outerRadius": radius,
+                    "tooltip": true,
+                },
+            })
+        })
+        .collect();
+    let mut title = json!({ "text": rd.title.split('\n').collect::<Vec<_>>() });
+
+    if let Some(ref subtitle) = rd.subtitle {
+        title["subtitle"] = subtitle.split('\n').collect::<Vec<_>>().into();
+    }
+
+    let mut view = json!({
+        "title": title,
+        "width": rd.pie_diameter,
+        "height": rd.pie_diameter,
+        "encoding": {
+            "theta": {
+                "field": "value",
+                "type": "quantitative",
+                "stack": true,
+                "scale": { "range": [start, start + sweep] },
+            },
+            "color": {
+                "field": "key",
+                "type": "nominal",
+                "sort": null,
+                "scale": {
+                    "domain": rd.wedges.iter().map(|wedge| wedge.key.as_str()).collect::<Vec<_>>(),
+                    "range": rd.wedges.iter().map(|wedge| wedge.color.as_str()).collect::<Vec<_>>(),
+                },
+                "legend": if rd.legend { json!({}) } else { Value::Null },
+            },
+        },
+    });
+
+    match arcs.pop() {
+        Some(Value::Object(arc)) if arcs.is_empty() => view.as_object_mut().unwrap().extend(arc),
+        arc => {
+            arcs.extend(arc);
+            view["layer"] = arcs.into();
+        }
+    }
+
+    view
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChartData, ChartOptions, PieChartTool, SilentLog};
+
+    #[test]
+    fn exports_vega_lite() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: 'Costs', items: [{ key: 'a', value: 1, color: '#ff0000' }, { key: 'b', value: 3, color: '#0000ff' }] }",
+        )
+        .unwrap();
+        let options = ChartOptions {
+            hole_ratio: Some(0.5),
+            ..Default::default()
+        };
+        let tool = PieChartTool::new(&SilentLog);
+        let rd = tool.process_chart_data(&chart_data, &options).unwrap();
+        let spec = vega_lite(&[rd]);
+
+        assert_eq!(spec["$schema"], VEGA_LITE_SCHEMA);
+        assert_eq!(spec["title"]["text"], json!(["Costs"]));
+        assert_eq!(spec["mark"]["type"], "arc");
+        assert_eq!(spec["mark"]["innerRadius"], 100.0);
+        assert_eq!(
+            spec["data"]["values"][1],
+            json!({ "key": "b", "value": 3.0 })
+        );
+        assert_eq!(
+            spec["encoding"]["color"]["scale"]["range"],
+            json!(["#ff0000", "#0000ff"])
+        );
+
+        let rds = [
+            tool.process_chart_data(&chart_data, &options).unwrap(),
+            tool.process_chart_data(&chart_data, &options).unwrap(),
+        ];
+
+        assert_eq!(vega_lite(&rds)["concat"].as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,80 @@
+//! C functions for linking the renderer into programs not written in Rust, built with the `ffi`
+//! feature. Strings returned by these functions belong to the caller, who frees them with
+//! `pie_chart_free`.
+
+use crate::{svg_string, ChartData, ChartOptions};
+use std::{
+    ffi::{c_char, CString},
+    slice,
+};
+
+/// Render a chart from the JSON5 chart file in `json`, which is `len` bytes of UTF-8 that need
+/// not end with a nul. Returns 0 and sets `*out` to the SVG, or returns 1 and sets `*out` to the
+/// error message.
+///
+/// # Safety
+///
+/// `json` must point to `len` readable bytes and `out` must point to writable memory for a
+/// pointer.
+#[no_mangle]
+pub unsafe extern "C" fn pie_chart_render(
+    json: *const u8,
+    len: usize,
+    out: *mut *mut c_char,
+) -> i32 {
+    let result = std::str::from_utf8(slice::from_raw_parts(json, len))
+        .map_err(|_| "Chart file is not UTF-8 text".to_string())
+        .and_then(|json| json5::from_str::<ChartData>(json).map_err(|e| e.to_string()))
+        .and_then(|chart_data| {
+            svg_string(&chart_data, &ChartOptions::default()).map_err(|e| e.to_string())
+        });
+    let (status, text) = match result {
+        Ok(svg) => (0, svg),
+        Err(message) => (1, message),
+    };
+
+    // Neither SVG nor the error messages hold nuls, but the chart text could
+    *out = CString::new(text.replace('\0', ""))
+        .unwrap_or_default()
+        .into_raw();
+    status
+}
+
+/// Free a string returned by `pie_chart_render`. Does nothing if `text` is null.
+///
+/// # Safety
+///
+/// `text` must be null or a string returned by this library that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn pie_chart_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{ffi::CStr, ptr};
+
+    #[test]
+    fn renders_and_reports_errors() {
+        let render = |json: &str| unsafe {
+            let mut out = ptr::null_mut();
+            let status = pie_chart_render(json.as_ptr(), json.len(), &mut out);
+            let text = CStr::from_ptr(out).to_string_lossy().to_string();
+
+            pie_chart_free(out);
+            (status, text)
+        };
+
+        let (status, svg) = render("{title: 'a', items: [{key: 'b', value: 1}]}");
+
+        assert_eq!(status, 0);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(
+            render("{title: 'a', items: []}"),
+            (1, "Chart must have at least one item".to_string())
+        );
+    }
+}
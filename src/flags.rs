@@ -0,0 +1,186 @@
+//! The command line flags that turn off the on/off chart options, so that the command line can
+//! override a chart file or config file that turns one on. Each option has a `--no-` flag, such
+//! as `--no-donut` for `--donut`, except `--no-legend`, which is turned off with `--legend`.
+//! Whichever of the two is given last wins.
+
+use crate::ChartOptions;
+use clap::{ArgMatches, Args, Command, FromArgMatches};
+use std::ops::Deref;
+
+/// Chart options given on the command line, with the options turned off by the flags in
+/// `Negations` set to false
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OptionArgs(pub ChartOptions);
+
+impl Deref for OptionArgs {
+    type Target = ChartOptions;
+
+    fn deref(self: &Self) -> &ChartOptions {
+        &self.0
+    }
+}
+
+impl FromArgMatches for OptionArgs {
+    fn from_arg_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+        Self::from_arg_matches_mut(&mut matches.clone())
+    }
+
+    fn from_arg_matches_mut(matches: &mut ArgMatches) -> Result<Self, clap::Error> {
+        let mut options = ChartOptions::from_arg_matches_mut(matches)?;
+
+        Negations::from_arg_matches_mut(matches)?.apply(&mut options);
+        Ok(OptionArgs(options))
+    }
+
+    fn update_from_arg_matches(self: &mut Self, matches: &ArgMatches) -> Result<(), clap::Error> {
+        *self = Self::from_arg_matches(matches)?;
+        Ok(())
+    }
+}
+
+impl Args for OptionArgs {
+    fn augment_args(command: Command) -> Command {
+        Negations::augment_args(ChartOptions::augment_args(command))
+    }
+
+    fn augment_args_for_update(command: Command) -> Command {
+        Self::augment_args(command)
+    }
+}
+
+#[derive(Args, Debug)]
+struct Negations {
+    /// Turn off `--donut`
+    #[arg(long = "no-donut", overrides_with = "donut")]
+    no_donut: bool,
+
+    /// Turn off `--half`
+    #[arg(long = "no-half", overrides_with = "half")]
+    no_half: bool,
+
+    /// Turn off `--counterclockwise`
+    #[arg(long = "no-counterclockwise", overrides_with = "counterclockwise")]
+    no_counterclockwise: bool,
+
+    /// Turn off `--sunburst`
+    #[arg(long = "no-sunburst", overrides_with = "sunburst")]
+    no_sunburst: bool,
+
+    /// Turn off `--rose`
+    #[arg(long = "no-rose", overrides_with = "rose")]
+    no_rose: bool,
+
+    /// Turn off `--waffle`
+    #[arg(long = "no-waffle", overrides_with = "waffle")]
+    no_waffle: bool,
+
+    /// Turn off `--as-bars`
+    #[arg(long = "no-as-bars", overrides_with = "as_bars")]
+    no_as_bars: bool,
+
+    /// Turn off `--treemap`
+    #[arg(long = "no-treemap", overrides_with = "treemap")]
+    no_treemap: bool,
+
+    /// Turn off `--deterministic`
+    #[arg(long = "no-deterministic", overrides_with = "deterministic")]
+    no_deterministic: bool,
+
+    /// Turn off `--background-rect`
+    #[arg(long = "no-background-rect", overrides_with = "background_rect")]
+    no_background_rect: bool,
+
+    /// Turn off `--percent-input`
+    #[arg(long = "no-percent-input", overrides_with = "percentages")]
+    no_percentages: bool,
+
+    /// Turn off `--top-other`
+    #[arg(long = "no-top-other", overrides_with = "top_other")]
+    no_top_other: bool,
+
+    /// Turn off `--merge-duplicates`
+    #[arg(long = "no-merge-duplicates", overrides_with = "merge_duplicates")]
+    no_merge_duplicates: bool,
+
+    /// Turn off `--thousands-separator`
+    #[arg(
+        long = "no-thousands-separator",
+        overrides_with = "thousands_separator"
+    )]
+    no_thousands_separator: bool,
+
+    /// Turn off `--si-prefix`
+    #[arg(long = "no-si-prefix", overrides_with = "si_prefix")]
+    no_si_prefix: bool,
+
+    /// Turn off `--replace-styles`
+    #[arg(long = "no-replace-styles", overrides_with = "replace_styles")]
+    no_replace_styles: bool,
+
+    /// Turn off `--animate`
+    #[arg(long = "no-animate", overrides_with = "animate")]
+    no_animate: bool,
+
+    /// Turn off `--check-colorblind`
+    #[arg(long = "no-check-colorblind", overrides_with = "check_colorblind")]
+    no_check_colorblind: bool,
+
+    /// Turn off `--no-legend`, drawing the legend
+    #[arg(long = "legend", overrides_with = "no_legend")]
+    legend: bool,
+
+    /// Turn off `--minify`
+    #[arg(long = "no-minify", overrides_with = "minify")]
+    no_minify: bool,
+
+    /// Turn off `--pretty`
+    #[arg(long = "no-pretty", overrides_with = "pretty")]
+    no_pretty: bool,
+
+    /// Turn off `--responsive`
+    #[arg(long = "no-responsive", overrides_with = "responsive")]
+    no_responsive: bool,
+
+    /// Turn off `--embed-data`
+    #[arg(long = "no-embed-data", overrides_with = "embed_data")]
+    no_embed_data: bool,
+}
+
+impl Negations {
+    fn apply(self: &Self, options: &mut ChartOptions) {
+        let negations = [
+            (self.no_donut, &mut options.donut),
+            (self.no_half, &mut options.half),
+            (self.no_counterclockwise, &mut options.counterclockwise),
+            (self.no_sunburst, &mut options.sunburst),
+            (self.no_rose, &mut options.rose),
+            (self.no_waffle, &mut options.waffle),
+            (self.no_as_bars, &mut options.as_bars),
+            (self.no_treemap, &mut options.treemap),
+            (self.no_deterministic, &mut options.deterministic),
+            (self.no_background_rect, &mut options.background_rect),
+            (self.no_percentages, &mut options.percentages),
+            (self.no_top_other, &mut options.top_other),
+            (self.no_merge_duplicates, &mut options.merge_duplicates),
+            (
+                self.no_thousands_separator,
+                &mut options.thousands_separator,
+            ),
+            (self.no_si_prefix, &mut options.si_prefix),
+            (self.no_replace_styles, &mut options.replace_styles),
+            (self.no_animate, &mut options.animate),
+            (self.no_check_colorblind, &mut options.check_colorblind),
+            (self.legend, &mut options.no_legend),
+            (self.no_minify, &mut options.minify),
+            (self.no_pretty, &mut options.pretty),
+            (self.no_responsive, &mut options.responsive),
+            (self.no_embed_data, &mut options.embed_data),
+        ];
+
+        for (negated, option) in negations {
+            if negated {
+                *option = Some(false);
+            }
+        }
+    }
+}
@@ -0,0 +1,371 @@
+mod csv;
+mod mermaid;
+pub(crate) mod sqlite;
+pub(crate) mod svg;
+pub(crate) mod toml;
+mod xlsx;
+mod xml;
+mod yaml;
+pub(crate) mod zip;
+
+use crate::{ChartData, PieChartError};
+use clap::ValueEnum;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::Path;
+
+/// The format of a chart file
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum InputFormat {
+    /// JSON5
+    Json5,
+    /// Strict JSON
+    Json,
+    /// Comma separated `key,value[,color]` rows with an optional header row
+    Csv,
+    /// YAML
+    Yaml,
+    /// TOML
+    Toml,
+    /// Excel workbook
+    Xlsx,
+    /// SVG rendered with `--embed-data`
+    Svg,
+    /// A Mermaid `pie` diagram
+    Mermaid,
+}
+
+/// Where the items are in a table, for CSV files and Excel workbooks
+#[derive(Default)]
+pub(crate) struct Table<'a> {
+    /// The worksheet of a workbook, instead of the first
+    pub sheet: Option<&'a str>,
+    /// The column of keys, as a letter like `B` or a header name
+    pub key_column: Option<&'a str>,
+    /// The column of values, as a letter like `B` or a header name
+    pub value_column: Option<&'a str>,
+}
+
+/// The index of a spreadsheet column letter, where `A` is 0 and `AA` is 26
+fn column_index(letters: &str) -> Option<usize> {
+    if letters.is_empty() || letters.len() > 3 || !letters.chars().all(|c| c.is_ascii_alphabetic())
+    {
+        return None;
+    }
+
+    letters
+        .bytes()
+        .try_fold(0, |index: usize, c| {
+            Some(index * 26 + (c.to_ascii_uppercase() - b'A') as usize + 1)
+        })
+        .map(|index| index - 1)
+}
+
+impl InputFormat {
+    /// Guess the format from the file extension, falling back to the content
+    pub fn detect(path: Option<&Path>, content: &str) -> InputFormat {
+        let extension = path
+            .and_then(|path| path.extension())
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("json5") => return InputFormat::Json5,
+            Some("json") => return InputFormat::Json,
+            Some("csv") => return InputFormat::Csv,
+            Some("yaml") | Some("yml") => return InputFormat::Yaml,
+            Some("toml") => return InputFormat::Toml,
+            Some("xlsx") => return InputFormat::Xlsx,
+            Some("svg") => return InputFormat::Svg,
+            Some("mmd") | Some("mermaid") => return InputFormat::Mermaid,
+            _ => (),
+        }
+
+        let first_line = content
+            .lines()
+            .map(str::trim)
+            .find(|line| {
+                !line.is_empty()
+                    && !line.starts_with('#')
+                    && !line.starts_with("//")
+                    && !line.starts_with("%%")
+            })
+            .unwrap_or_default();
+
+        if first_line.starts_with('<') {
+            InputFormat::Svg
+        } else if first_line.starts_with("```mermaid")
+            || first_line.split_whitespace().next() == Some("pie")
+        {
+            InputFormat::Mermaid
+        } else if first_line.starts_with('{')
+            || first_line.starts_with('[') && !Self::is_toml_table(first_line)
+        {
+            InputFormat::Json5
+        } else if first_line == "---" || Self::is_yaml_key(first_line) {
+            InputFormat::Yaml
+        } else if Self::is_toml_table(first_line) || first_line.contains('=') {
+            InputFormat::Toml
+        } else if first_line.contains(',') {
+            InputFormat::Csv
+        } else {
+            InputFormat::Json5
+        }
+    }
+
+    fn is_toml_table(line: &str) -> bool {
+        let name = line.trim_start_matches('[').trim_end_matches(']');
+
+        line.starts_with('[')
+            && line.ends_with(']')
+            && !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    }
+
+    fn is_yaml_key(line: &str) -> bool {
+        match line.split_once(':') {
+            Some((key, rest)) => {
+                !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    && (rest.is_empty() || rest.starts_with(' '))
+            }
+            None => false,
+        }
+    }
+}
+
+/// Read a chart file in the given format, or the one detected from the path and content
+pub(crate) fn read(
+    content: &[u8],
+    path: Option<&Path>,
+    format: Option<InputFormat>,
+    title: &str,
+    table: &Table,
+) -> Result<Vec<ChartData>, PieChartError> {
+    // Workbooks are zip archives, so they are binary and are known by the zip file signature
+    if format == Some(InputFormat::Xlsx) || format.is_none() && content.starts_with(b"PK\x03\x04") {
+        return xlsx::parse(content, title, table).map(|chart| vec![chart]);
+    }
+
+    let content = std::str::from_utf8(content).map_err(|e| PieChartError::Parse {
+        message: "Chart file is not UTF-8 text".to_string(),
+        line: Some(content[..e.valid_up_to()].split(|b| *b == b'\n').count()),
+        column: None,
+    })?;
+
+    parse(
+        format.unwrap_or_else(|| InputFormat::detect(path, content)),
+        content,
+        title,
+        table,
+    )
+}
+
+/// Read a chart file as it is written, before it is converted to charts, so that it can be
+/// checked against the schema. CSV files and workbooks are read as the charts they make.
+pub(crate) fn read_value(
+    content: &[u8],
+    path: Option<&Path>,
+    format: Option<InputFormat>,
+    table: &Table,
+) -> Result<serde_json::Value, PieChartError> {
+    let text = std::str::from_utf8(content).unwrap_or_default();
+    let format = match format {
+        Some(format) => format,
+        None if content.starts_with(b"PK\x03\x04") => InputFormat::Xlsx,
+        None => InputFormat::detect(path, text),
+    };
+    let charts_value = |charts: Vec<ChartData>| {
+        serde_json::to_value(&charts[0]).map_err(|e| PieChartError::Parse {
+            message: e.to_string(),
+            line: None,
+            column: None,
+        })
+    };
+
+    match format {
+        InputFormat::Json5 => from_json5(text),
+        InputFormat::Json => from_json(text),
+        InputFormat::Yaml => yaml::parse(text),
+        InputFormat::Toml => toml::parse(text),
+        InputFormat::Svg => svg::parse(text),
+        InputFormat::Csv | InputFormat::Xlsx | InputFormat::Mermaid => {
+            charts_value(read(content, path, Some(format), "", table)?)
+        }
+    }
+}
+
+/// Parse a chart file in the given format, which may hold a single chart or an array of charts.
+/// The title is used for formats that cannot hold one.
+pub(crate) fn parse(
+    format: InputFormat,
+    content: &str,
+    title: &str,
+    table: &Table,
+) -> Result<Vec<ChartData>, PieChartError> {
+    let is_array = content.trim_start().starts_with('[');
+
+    match format {
+        InputFormat::Json5 if is_array => from_json5(content),
+        InputFormat::Json5 => from_json5(content).map(|chart| vec![chart]),
+        InputFormat::Json if is_array => from_json(content),
+        InputFormat::Json => from_json(content).map(|chart| vec![chart]),
+        InputFormat::Csv => csv::parse(content, title, table).map(|chart| vec![chart]),
+        InputFormat::Yaml => from_value(yaml::parse(content)?),
+        InputFormat::Toml => from_value(toml::parse(content)?),
+        InputFormat::Svg => from_value(svg::parse(content)?),
+        InputFormat::Xlsx => xlsx::parse(content.as_bytes(), title, table).map(|chart| vec![chart]),
+        InputFormat::Mermaid => mermaid::parse(content, title).map(|chart| vec![chart]),
+    }
+}
+
+fn from_json5<T: DeserializeOwned>(content: &str) -> Result<T, PieChartError> {
+    json5::from_str(content).map_err(|e| {
+        let json5::Error::Message { msg, location } = e;
+
+        PieChartError::Parse {
+            message: msg,
+            line: location.as_ref().map(|l| l.line),
+            column: location.as_ref().map(|l| l.column),
+        }
+    })
+}
+
+fn from_json<T: DeserializeOwned>(content: &str) -> Result<T, PieChartError> {
+    serde_json::from_str(content).map_err(|e| {
+        // The message ends with the location, which is reported separately
+        let message = e.to_string();
+        let message = message
+            .rsplit_once(" at line ")
+            .map_or(message.as_str(), |(message, _)| message);
+
+        PieChartError::Parse {
+            message: message.to_string(),
+            line: Some(e.line()),
+            column: Some(e.column()),
+        }
+    })
+}
+
+/// Convert a parsed value to charts. As TOML cannot have an array at the top level, an array of
+/// charts can also be given as a `charts` key holding the only value.
+fn from_value(value: serde_json::Value) -> Result<Vec<ChartData>, PieChartError> {
+    let value = match value {
+        serde_json::Value::Object(mut map) if map.len() == 1 && map.contains_key("charts") => {
+            map.remove("charts").unwrap()
+        }
+        value => value,
+    };
+    let result = if value.is_array() {
+        serde_json::from_value(value)
+    } else {
+        serde_json::from_value(value).map(|chart| vec![chart])
+    };
+
+    result.map_err(|e| PieChartError::Parse {
+        message: e.to_string(),
+        line: None,
+        column: None,
+    })
+}
+
+fn parse_error(message: impl Into<String>, line: usize) -> PieChartError {
+    PieChartError::Parse {
+        message: message.into(),
+        line: Some(line),
+        column: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats() {
+        let detect =
+            |path: &str, content: &str| InputFormat::detect(Some(Path::new(path)), content);
+
+        assert_eq!(detect("a.yml", ""), InputFormat::Yaml);
+        assert_eq!(detect("a.JSON", ""), InputFormat::Json);
+        assert_eq!(detect("-", "{ title: 'x' }"), InputFormat::Json5);
+        assert_eq!(detect("-", "# Chart\ntitle: Grades\n"), InputFormat::Yaml);
+        assert_eq!(detect("-", "title = \"Grades\"\n"), InputFormat::Toml);
+        assert_eq!(detect("-", "[[items]]\nkey = \"a\"\n"), InputFormat::Toml);
+        assert_eq!(detect("-", "key,value\na,1\n"), InputFormat::Csv);
+        assert_eq!(detect("-", "pie title Pets\n"), InputFormat::Mermaid);
+    }
+
+    #[test]
+    fn parses_every_format_to_the_same_chart() {
+        let charts = [
+            parse(
+                InputFormat::Json5,
+                "{title: 'Grades', items: [{key: 'A', value: 2}, {key: 'B', value: 30}]}",
+                "", &Table::default()
+            ),
+            parse(
+                InputFormat::Json,
+                r#"{"title": "Grades", "items": [{"key": "A", "value": 2}, {"key": "B", "value": 30}]}"#,
+                "", &Table::default()
+            ),
+            parse(InputFormat::Csv, "key,value\nA,2\nB,30\n", "Grades", &Table::default()),
+            parse(
+                InputFormat::Yaml,
+                "title: Grades\nitems:\n  - key: A\n    value: 2\n  - {key: B, value: 30}\n",
+                "", &Table::default()
+            ),
+            parse(
+                InputFormat::Toml,
+                "title = \"Grades\"\n\n[[items]]\nkey = \"A\"\nvalue = 2\n\n[[items]]\nkey = \"B\"\nvalue = 30\n",
+                "", &Table::default()
+            ),
+        ];
+
+        for charts in charts {
+            let chart = &charts.unwrap()[0];
+
+            assert_eq!(chart.title, "Grades");
+            assert_eq!(chart.items.len(), 2);
+            assert_eq!(chart.items[1].key, "B");
+            assert_eq!(chart.items[1].value, 30.0);
+        }
+    }
+
+    #[test]
+    fn parses_arrays_of_charts() {
+        let json5 = parse(
+            InputFormat::Json5,
+            "[{title: 'a', items: []}, {title: 'b', items: []}]",
+            "",
+            &Table::default(),
+        )
+        .unwrap();
+        let toml = parse(
+            InputFormat::Toml,
+            "[[charts]]\ntitle = \"a\"\nitems = []\n\n[[charts]]\ntitle = \"b\"\nitems = []\n",
+            "",
+            &Table::default(),
+        )
+        .unwrap();
+
+        assert_eq!(json5.len(), 2);
+        assert_eq!(toml[1].title, "b");
+    }
+
+    #[test]
+    fn reports_parse_locations() {
+        match parse(
+            InputFormat::Json,
+            "{\n  \"title\": }",
+            "",
+            &Table::default(),
+        ) {
+            Err(PieChartError::Parse { line, .. }) => assert_eq!(line, Some(2)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use super::{column_index, parse_error, Table};
+use crate::{ChartData, ItemData, PieChartError};
+
+/// Parse `key,value[,color]` rows
+pub(crate) fn parse(content: &str, title: &str, table: &Table) -> Result<ChartData, PieChartError> {
+    from_records(&records(content)?, title, table)
+}
+
+/// Make a chart of `key,value[,color]` records, each with its line number. A first record whose
+/// value column is not a number is taken as a header naming the `key`, `value`, `color` and `href`
+/// columns, in any order. Other key and value columns can be chosen by letter or header name.
+pub(crate) fn from_records(
+    records: &[(usize, Vec<String>)],
+    title: &str,
+    table: &Table,
+) -> Result<ChartData, PieChartError> {
+    let mut rows = records.iter().peekable();
+    let is_named = |spec: &Option<&str>| spec.is_some_and(|spec| column_index(spec).is_none());
+    let mut key_column = table.key_column.and_then(column_index).unwrap_or(0);
+    let mut value_column = table.value_column.and_then(column_index).unwrap_or(1);
+    let mut color_column =
+        (table.key_column.is_none() && table.value_column.is_none()).then_some(2);
+    let mut href_column = None;
+
+    if let Some((_, header)) = rows.peek() {
+        if is_named(&table.key_column)
+            || is_named(&table.value_column)
+            || header
+                .get(value_column)
+                .is_none_or(|v| v.trim().parse::<f64>().is_err())
+        {
+            let find = |name: &str| {
+                header
+                    .iter()
+                    .position(|column| column.trim().eq_ignore_ascii_case(name))
+            };
+            let column = |spec: Option<&str>, name: &str, default: usize| match spec {
+                Some(spec) => find(spec).or_else(|| column_index(spec)).ok_or_else(|| {
+                    PieChartError::Validation(format!("There is no column named '{}'", spec))
+                }),
+                None => Ok(find(name).unwrap_or(default)),
+            };
+
+            key_column = column(table.key_column, "key", 0)?;
+            value_column = column(table.value_column, "value", 1)?;
+            color_column = find("color");
+            href_column = find("href");
+            rows.next();
+        }
+    }
+
+    let mut items = vec![];
+
+    for (line, row) in rows {
+        let field = |column: usize| row.get(column).map(|field| field.trim());
+        let key = field(key_column)
+            .ok_or_else(|| parse_error("Row is missing a key", *line))?
+            .to_string();
+        let value = field(value_column)
+            .ok_or_else(|| parse_error(format!("Row for '{}' is missing a value", key), *line))?;
+        let value = value.parse::<f64>().map_err(|_| {
+            parse_error(
+                format!("Row for '{}' has an invalid value '{}'", key, value),
+                *line,
+            )
+        })?;
+        let color = color_column
+            .and_then(field)
+            .filter(|color| !color.is_empty())
+            .map(str::to_string);
+
+        let href = href_column
+            .and_then(field)
+            .filter(|href| !href.is_empty())
+            .map(str::to_string);
+
+        items.push(ItemData {
+            color,
+            href,
+            ..ItemData::new(key, value)
+        });
+    }
+
+    Ok(ChartData {
+        title: title.to_string(),
+        items,
+        ..Default::default()
+    })
+}
+
+/// Split the content into records of fields, handling quoted fields. Blank lines are skipped.
+fn records(content: &str) -> Result<Vec<(usize, Vec<String>)>, PieChartError> {
+    let mut records = vec![];
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+
+                if fields.iter().any(|field| !field.trim().is_empty()) {
+                    records.push((record_line, std::mem::take(&mut fields)));
+                } else {
+                    fields.clear();
+                }
+
+                line += 1;
+                record_line = line;
+            }
+            '\r' if !quoted => (),
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+
+                field.push(c);
+            }
+        }
+    }
+
+    if quoted {
+        return Err(parse_error("Unterminated quoted field", record_line));
+    }
+
+    fields.push(field);
+
+    if fields.iter().any(|field| !field.trim().is_empty()) {
+        records.push((record_line, fields));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_headers_and_quotes() {
+        let chart = parse(
+            "Color,Value,Key\n#f00,1.5,\"Big, red\"\n\n,2,\"Say \"\"hi\"\"\"\n",
+            "Chart",
+            &Table::default(),
+        )
+        .unwrap();
+
+        assert_eq!(chart.items[0].key, "Big, red");
+        assert_eq!(chart.items[0].color.as_deref(), Some("#f00"));
+        assert_eq!(chart.items[1].key, "Say \"hi\"");
+        assert_eq!(chart.items[1].color, None);
+        assert!(parse("a,1\nb,x\n", "", &Table::default()).is_err());
+    }
+
+    #[test]
+    fn selects_columns() {
+        let content = "Region,Units,Revenue\nNorth,3,1200.5\nSouth,5,900\n";
+        let by_name = Table {
+            key_column: Some("region"),
+            value_column: Some("Revenue"),
+            ..Default::default()
+        };
+        let by_letter = Table {
+            value_column: Some("C"),
+            ..Default::default()
+        };
+
+        for table in [by_name, by_letter] {
+            let chart = parse(content, "", &table).unwrap();
+
+            assert_eq!(chart.items[0].key, "North");
+            assert_eq!(chart.items[0].value, 1200.5);
+        }
+    }
+}
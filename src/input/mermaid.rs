@@ -0,0 +1,117 @@
+//! Mermaid `pie` diagrams, as written in Markdown documents:
+//!
+//! ```text
+//! pie showData
+//!     title Pets adopted
+//!     "Dogs" : 386
+//!     "Cats" : 85
+//! ```
+//!
+//! The diagram may be inside a fenced code block. Diagrams with `showData` show the values in the
+//! legend as well as the percentages, as Mermaid does.
+
+use super::parse_error;
+use crate::{ChartData, ChartOptions, ItemData, LegendValues, PieChartError};
+
+/// Parse a `pie` diagram, using the title given if the diagram has none
+pub(crate) fn parse(content: &str, title: &str) -> Result<ChartData, PieChartError> {
+    let mut chart_data = ChartData {
+        title: title.to_string(),
+        ..Default::default()
+    };
+    let mut is_pie = false;
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let n = index + 1;
+
+        // Comments, directives, code fences and accessibility text are not drawn
+        if line.is_empty()
+            || line.starts_with("%%")
+            || line.starts_with("```")
+            || line.starts_with("acc")
+        {
+            continue;
+        }
+
+        if !is_pie {
+            let mut header = line
+                .strip_prefix("pie")
+                .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+                .ok_or_else(|| parse_error("Mermaid diagram is not a 'pie'", n))?
+                .trim();
+
+            if let Some(rest) = header.strip_prefix("showData") {
+                chart_data.options = ChartOptions {
+                    legend_values: Some(LegendValues::Both),
+                    ..Default::default()
+                };
+                header = rest.trim();
+            }
+
+            if let Some(rest) = header.strip_prefix("title") {
+                chart_data.title = rest.trim().to_string();
+            }
+
+            is_pie = true;
+        } else if let Some(rest) = line.strip_prefix("title") {
+            chart_data.title = rest.trim().to_string();
+        } else {
+            chart_data.items.push(item(line, n)?);
+        }
+    }
+
+    if !is_pie {
+        return Err(parse_error("Mermaid diagram is empty", 1));
+    }
+
+    Ok(chart_data)
+}
+
+/// An item line, which is a quoted label, a colon and a value
+fn item(line: &str, n: usize) -> Result<ItemData, PieChartError> {
+    let (key, rest) = line
+        .strip_prefix('"')
+        .and_then(|rest| rest.split_once('"'))
+        .ok_or_else(|| parse_error(format!("Expected a quoted label in '{}'", line), n))?;
+    let value = rest
+        .trim()
+        .strip_prefix(':')
+        .map(str::trim)
+        .ok_or_else(|| parse_error(format!("Label '{}' is missing a value", key), n))?;
+    let value = value.parse::<f64>().map_err(|_| {
+        parse_error(
+            format!("Label '{}' has an invalid value '{}'", key, value),
+            n,
+        )
+    })?;
+
+    Ok(ItemData::new(key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_pie_diagrams() {
+        let chart_data = parse(
+            "```mermaid\n%% Adoptions in 2024\npie showData\n    title Pets adopted\n    \"Dogs\" : 386\n    \"Cats\":85.5\n```\n",
+            "pets",
+        )
+        .unwrap();
+
+        assert_eq!(chart_data.title, "Pets adopted");
+        assert_eq!(chart_data.options.legend_values, Some(LegendValues::Both));
+        assert_eq!(chart_data.items.len(), 2);
+        assert_eq!(chart_data.items[1].key, "Cats");
+        assert_eq!(chart_data.items[1].value, 85.5);
+        assert_eq!(parse("pie title Pets\n", "").unwrap().title, "Pets");
+        assert_eq!(parse("pie\n\"Dogs\": 1\n", "pets").unwrap().title, "pets");
+
+        match parse("pie\n  \"Dogs\" : many\n", "") {
+            Err(PieChartError::Parse { line, .. }) => assert_eq!(line, Some(2)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{ChartData, PieChartError};
+use std::{path::Path, process::Command};
+
+/// Run a query against a SQLite database with the `sqlite3` command. The first column of each
+/// row is the key and the second the value, unless the columns are named `key` and `value`, in
+/// which case `color` and `href` columns are used too.
+pub(crate) fn query(database: &Path, sql: &str, title: &str) -> Result<ChartData, PieChartError> {
+    // Read only, so that a mistyped path is reported rather than created as an empty database
+    let output = Command::new("sqlite3")
+        .args(["-readonly", "-bail", "-batch", "-csv", "-header"])
+        .arg(database)
+        .arg(sql)
+        .output()
+        .map_err(|e| PieChartError::Io {
+            message: "Unable to run 'sqlite3'".to_string(),
+            source: e,
+        })?;
+
+    if !output.status.success() {
+        return Err(PieChartError::Validation(format!(
+            "Query of '{}' failed: {}",
+            database.to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    super::csv::parse(
+        &String::from_utf8_lossy(&output.stdout),
+        title,
+        &Default::default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    #[test]
+    fn queries_rows() {
+        let path = env::temp_dir().join(format!("pie_chart_{}.db", std::process::id()));
+        let created = Command::new("sqlite3")
+            .arg(&path)
+            .arg("create table usage (service text, cost real); insert into usage values ('api', 2.5), ('db', 4);")
+            .status();
+
+        // The sqlite3 command is not installed everywhere
+        if !created.is_ok_and(|status| status.success()) {
+            return;
+        }
+
+        let chart = query(
+            &path,
+            "select service, cost from usage order by cost desc",
+            "Usage",
+        );
+        let missing = query(&path.with_extension("missing"), "select 1, 2", "");
+
+        fs::remove_file(&path).unwrap();
+
+        let chart = chart.unwrap();
+
+        assert_eq!(chart.title, "Usage");
+        assert_eq!(chart.items[0].key, "db");
+        assert_eq!(chart.items[1].value, 2.5);
+        assert!(matches!(missing, Err(PieChartError::Validation(_))));
+    }
+}
@@ -0,0 +1,53 @@
+//! SVG files rendered with `--embed-data`, which hold the charts they were rendered from as JSON
+//! in a `<metadata>` element
+
+use super::{
+    from_json,
+    xml::{self, Event},
+};
+use crate::PieChartError;
+use serde_json::Value;
+
+/// The class of the `<metadata>` element holding the charts
+pub(crate) const METADATA_CLASS: &str = "pie-chart-data";
+
+/// The chart or charts embedded in an SVG file
+pub(crate) fn parse(content: &str) -> Result<Value, PieChartError> {
+    let mut json = None;
+
+    for event in xml::events(content) {
+        match (&event, json.as_mut()) {
+            (
+                Event::Start {
+                    name: "metadata", ..
+                },
+                None,
+            ) if event.attribute("class") == Some(METADATA_CLASS) => json = Some(String::new()),
+            (Event::Text(text), Some(json)) => json.push_str(text),
+            (Event::End("metadata"), Some(_)) => break,
+            _ => (),
+        }
+    }
+
+    from_json(
+        &json.ok_or_else(|| PieChartError::Parse {
+            message: "The SVG file has no chart data, as it was not rendered with '--embed-data'"
+                .to_string(),
+            line: None,
+            column: None,
+        })?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_embedded_charts() {
+        let svg = r#"<svg><title>a</title><metadata class="pie-chart-data">{"title":"a &amp; b","items":[]}</metadata></svg>"#;
+
+        assert_eq!(parse(svg).unwrap()["title"], "a & b");
+        assert!(parse("<svg></svg>").is_err());
+    }
+}
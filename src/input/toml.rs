@@ -0,0 +1,433 @@
+//! A parser for the subset of TOML used by chart and configuration files: tables, arrays of
+//! tables, dotted keys, strings, numbers, booleans, arrays and inline tables. Dates are not
+//! supported.
+
+use super::parse_error;
+use crate::PieChartError;
+use serde_json::{Map, Value};
+
+pub(crate) fn parse(content: &str) -> Result<Value, PieChartError> {
+    let mut root = Map::new();
+    // The path of the table that keys are currently added to
+    let mut current: Vec<String> = vec![];
+    let mut parser = Parser {
+        chars: content.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+
+    loop {
+        parser.skip_blank();
+
+        let Some(c) = parser.peek() else {
+            break;
+        };
+
+        if c == '[' {
+            let is_array = parser.chars.get(parser.pos + 1) == Some(&'[');
+
+            parser.pos += if is_array { 2 } else { 1 };
+
+            let path = parser.key_path()?;
+
+            parser.expect(']')?;
+
+            if is_array {
+                parser.expect(']')?;
+            }
+
+            let line = parser.line;
+            let (last, parents) = path.split_last().unwrap();
+            let parent = table_at(&mut root, parents, line)?;
+
+            if is_array {
+                match parent
+                    .entry(last.to_string())
+                    .or_insert_with(|| Value::Array(vec![]))
+                {
+                    Value::Array(tables) => tables.push(Value::Object(Map::new())),
+                    _ => return Err(parse_error(format!("'{}' is not an array", last), line)),
+                }
+            } else if parent
+                .insert(last.to_string(), Value::Object(Map::new()))
+                .is_some()
+            {
+                return Err(parse_error(
+                    format!("Table '{}' is defined twice", last),
+                    line,
+                ));
+            }
+
+            current = path;
+        } else {
+            let path = parser.key_path()?;
+
+            parser.skip_spaces();
+            parser.expect('=')?;
+
+            let value = parser.value()?;
+            let line = parser.line;
+            let (last, parents) = path.split_last().unwrap();
+            let mut full_path = current.clone();
+
+            full_path.extend(parents.iter().cloned());
+
+            if table_at(&mut root, &full_path, line)?
+                .insert(last.to_string(), value)
+                .is_some()
+            {
+                return Err(parse_error(
+                    format!("Key '{}' is defined twice", last),
+                    line,
+                ));
+            }
+        }
+
+        parser.end_of_line()?;
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// The table at `path`, creating tables as needed. Arrays of tables resolve to their last entry.
+fn table_at<'a>(
+    root: &'a mut Map<String, Value>,
+    path: &[String],
+    line: usize,
+) -> Result<&'a mut Map<String, Value>, PieChartError> {
+    let mut table = root;
+
+    for key in path {
+        let value = table
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+
+        table = match value {
+            Value::Object(map) => map,
+            Value::Array(tables) => match tables.last_mut() {
+                Some(Value::Object(map)) => map,
+                _ => return Err(parse_error(format!("'{}' is not a table", key), line)),
+            },
+            _ => return Err(parse_error(format!("'{}' is not a table", key), line)),
+        };
+    }
+
+    Ok(table)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+
+        self.pos += 1;
+
+        if c == '\n' {
+            self.line += 1;
+        }
+
+        Some(c)
+    }
+
+    fn error(&self, message: impl Into<String>) -> PieChartError {
+        parse_error(message, self.line)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), PieChartError> {
+        self.skip_spaces();
+
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(format!("Expected '{}' but found '{}'", expected, c))),
+            None => Err(self.error(format!("Expected '{}'", expected))),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), Some('\n') | None) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skip whitespace, newlines and comments
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+
+            match self.peek() {
+                Some('\n') | Some('\r') => {
+                    self.next();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), PieChartError> {
+        self.skip_spaces();
+        self.skip_comment();
+
+        if self.peek() == Some('\r') {
+            self.pos += 1;
+        }
+
+        match self.next() {
+            Some('\n') | None => Ok(()),
+            Some(c) => Err(self.error(format!("Unexpected '{}' at end of line", c))),
+        }
+    }
+
+    fn key_path(&mut self) -> Result<Vec<String>, PieChartError> {
+        let mut path = vec![];
+
+        loop {
+            self.skip_spaces();
+
+            let key = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+
+                    if start == self.pos {
+                        return Err(self.error("Expected a key"));
+                    }
+
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+
+            path.push(key);
+            self.skip_spaces();
+
+            if self.peek() == Some('.') {
+                self.pos += 1;
+            } else {
+                return Ok(path);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, PieChartError> {
+        self.skip_spaces();
+
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => {
+                self.pos += 1;
+
+                let mut values = vec![];
+
+                loop {
+                    self.skip_blank();
+
+                    if self.peek() == Some(']') {
+                        self.pos += 1;
+                        return Ok(Value::Array(values));
+                    }
+
+                    values.push(self.value()?);
+                    self.skip_blank();
+
+                    match self.next() {
+                        Some(',') => (),
+                        Some(']') => return Ok(Value::Array(values)),
+                        _ => return Err(self.error("Expected ',' or ']' in array")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+
+                let mut map = Map::new();
+
+                self.skip_spaces();
+
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                    return Ok(Value::Object(map));
+                }
+
+                loop {
+                    let path = self.key_path()?;
+
+                    self.expect('=')?;
+
+                    let value = self.value()?;
+                    let (last, parents) = path.split_last().unwrap();
+                    let line = self.line;
+
+                    table_at(&mut map, parents, line)?.insert(last.to_string(), value);
+                    self.skip_spaces();
+
+                    match self.next() {
+                        Some(',') => (),
+                        Some('}') => return Ok(Value::Object(map)),
+                        _ => return Err(self.error("Expected ',' or '}' in inline table")),
+                    }
+                }
+            }
+            _ => {
+                let start = self.pos;
+
+                while self
+                    .peek()
+                    .is_some_and(|c| !matches!(c, ',' | ']' | '}' | '#' | '\n' | '\r'))
+                {
+                    self.pos += 1;
+                }
+
+                let text: String = self.chars[start..self.pos].iter().collect();
+                let text = text.trim();
+
+                match text {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    _ => {
+                        let number = text.replace('_', "");
+
+                        if let Ok(integer) = number.parse::<i64>() {
+                            Ok(Value::from(integer))
+                        } else {
+                            number
+                                .parse::<f64>()
+                                .ok()
+                                .and_then(serde_json::Number::from_f64)
+                                .map(Value::Number)
+                                .ok_or_else(|| self.error(format!("Invalid value '{}'", text)))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, PieChartError> {
+        let mut text = String::new();
+
+        self.pos += 1;
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => match self.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.next()).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error("Invalid unicode escape"))?;
+
+                        text.push(c);
+                    }
+                    Some(c @ ('"' | '\\')) => text.push(c),
+                    _ => return Err(self.error("Invalid escape in string")),
+                },
+                Some('\n') | None => return Err(self.error("Unterminated string")),
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, PieChartError> {
+        let mut text = String::new();
+
+        self.pos += 1;
+
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(text),
+                Some('\n') | None => return Err(self.error("Unterminated string")),
+                Some(c) => text.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_tables_and_inline_values() {
+        let value = parse(
+            r#"
+# A chart
+title = "Grades" # trailing comment
+styles = [
+  ".a{fill:red}",
+  '.b{}',
+]
+
+[options]
+donut = true
+title_style.font_size = 1_000
+
+[[items]]
+key = "A"
+value = 2.5
+
+[[items]]
+key = "B \"quoted\""
+value = -3
+color = '#ff0000'
+
+[other]
+items = [{ key = "C", value = 1 }, {}]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            value,
+            json!({
+                "title": "Grades",
+                "styles": [".a{fill:red}", ".b{}"],
+                "options": {"donut": true, "title_style": {"font_size": 1000}},
+                "items": [
+                    {"key": "A", "value": 2.5},
+                    {"key": "B \"quoted\"", "value": -3, "color": "#ff0000"},
+                ],
+                "other": {"items": [{"key": "C", "value": 1}, {}]},
+            })
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_lines() {
+        match parse("title = \"x\"\nsize = = 1\n") {
+            Err(PieChartError::Parse { line, .. }) => assert_eq!(line, Some(2)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Excel workbooks, which are zip archives of XML files. Cells are read as the values they hold,
+//! so numbers keep their full precision, and formulas give the value they last calculated.
+
+use super::{
+    column_index, csv,
+    xml::{self, Event},
+    zip, Table,
+};
+use crate::{ChartData, PieChartError};
+
+fn invalid(message: &str) -> PieChartError {
+    PieChartError::Parse {
+        message: format!("Not a valid Excel workbook: {}", message),
+        line: None,
+        column: None,
+    }
+}
+
+fn read_xml(data: &[u8], name: &str) -> Result<Option<String>, PieChartError> {
+    zip::read(data, name)?
+        .map(|bytes| {
+            String::from_utf8(bytes).map_err(|_| invalid(&format!("'{}' is not UTF-8", name)))
+        })
+        .transpose()
+}
+
+/// Parse the rows of a worksheet, the first unless the table names another, like CSV rows with
+/// the spreadsheet row numbers as the line numbers
+pub(crate) fn parse(data: &[u8], title: &str, table: &Table) -> Result<ChartData, PieChartError> {
+    let workbook =
+        read_xml(data, "xl/workbook.xml")?.ok_or_else(|| invalid("there is no workbook part"))?;
+    let sheets: Vec<(String, String)> = xml::events(&workbook)
+        .iter()
+        .filter(|event| matches!(event, Event::Start { name: "sheet", .. }))
+        .filter_map(|event| {
+            Some((
+                event.attribute("name")?.to_string(),
+                event.attribute("id")?.to_string(),
+            ))
+        })
+        .collect();
+    let (_, id) = match table.sheet {
+        Some(sheet) => sheets
+            .iter()
+            .find(|(name, _)| name == sheet)
+            .ok_or_else(|| {
+                PieChartError::Validation(format!("The workbook has no sheet named '{}'", sheet))
+            })?,
+        None => sheets
+            .first()
+            .ok_or_else(|| invalid("there are no sheets"))?,
+    };
+    let relationships = read_xml(data, "xl/_rels/workbook.xml.rels")?.unwrap_or_default();
+    // Targets are relative to the workbook part, unless they start at the root
+    let path = xml::events(&relationships)
+        .iter()
+        .find(|event| event.attribute("Id") == Some(id))
+        .and_then(|event| event.attribute("Target"))
+        .map(|target| match target.strip_prefix('/') {
+            Some(target) => target.to_string(),
+            None => format!("xl/{}", target),
+        })
+        .ok_or_else(|| invalid("a sheet has no part"))?;
+    let shared_strings = read_xml(data, "xl/sharedStrings.xml")?
+        .map(|xml| shared_strings(&xml))
+        .unwrap_or_default();
+    let sheet = read_xml(data, &path)?.ok_or_else(|| invalid("a sheet part is missing"))?;
+
+    csv::from_records(&rows(&sheet, &shared_strings)?, title, table)
+}
+
+/// The strings that cells refer to by index, each of which may be split into formatted runs
+fn shared_strings(xml: &str) -> Vec<String> {
+    let mut strings = vec![];
+    let (mut in_text, mut in_phonetic) = (false, false);
+
+    for event in xml::events(xml) {
+        match event {
+            Event::Start { name: "si", .. } => strings.push(String::new()),
+            Event::Start { name: "t", .. } => in_text = true,
+            Event::End("t") => in_text = false,
+            // Pronunciation hints for East Asian text, which are not part of the string
+            Event::Start { name: "rPh", .. } => in_phonetic = true,
+            Event::End("rPh") => in_phonetic = false,
+            Event::Text(text) if in_text && !in_phonetic => {
+                if let Some(string) = strings.last_mut() {
+                    string.push_str(&text);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    strings
+}
+
+fn rows(xml: &str, shared_strings: &[String]) -> Result<Vec<(usize, Vec<String>)>, PieChartError> {
+    let mut rows: Vec<(usize, Vec<String>)> = vec![];
+    // The column and type of the cell being read, and its text so far
+    let mut cell = None;
+    let mut text = String::new();
+    let mut in_value = false;
+
+    for event in xml::events(xml) {
+        match event {
+            Event::Start { name: "row", .. } => {
+                let number = event
+                    .attribute("r")
+                    .and_then(|r| r.parse().ok())
+                    .unwrap_or(rows.len() + 1);
+
+                rows.push((number, vec![]));
+            }
+            Event::Start {
+                name: "c", empty, ..
+            } if !empty => {
+                let row_len = rows.last().map_or(0, |(_, row)| row.len());
+                let column = event
+                    .attribute("r")
+                    .map(|r| r.trim_end_matches(|c: char| c.is_ascii_digit()))
+                    .and_then(column_index)
+                    .unwrap_or(row_len);
+
+                cell = Some((column, event.attribute("t").unwrap_or("n").to_string()));
+                text.clear();
+            }
+            Event::Start { name, .. } if name == "v" || name == "t" => in_value = true,
+            Event::End(name) if name == "v" || name == "t" => in_value = false,
+            Event::Text(value) if in_value => text.push_str(&value),
+            Event::End("c") => {
+                let (Some((column, kind)), Some((_, row))) = (cell.take(), rows.last_mut()) else {
+                    continue;
+                };
+                let value = if kind == "s" {
+                    text.trim()
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| shared_strings.get(index))
+                        .ok_or_else(|| invalid("a cell refers to a missing shared string"))?
+                        .clone()
+                } else {
+                    std::mem::take(&mut text)
+                };
+
+                if row.len() <= column {
+                    row.resize(column + 1, String::new());
+                }
+
+                row[column] = value;
+            }
+            _ => (),
+        }
+    }
+
+    rows.retain(|(_, row)| row.iter().any(|cell| !cell.trim().is_empty()));
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A zip archive of uncompressed files, without the checksums that are not read
+    fn stored_zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let (mut data, mut directory) = (vec![], vec![]);
+
+        for (name, contents) in files {
+            let sizes = [(contents.len() as u32).to_le_bytes(); 2].concat();
+            let name_len = (name.len() as u16).to_le_bytes();
+
+            directory.extend(b"PK\x01\x02");
+            directory.extend([0; 16]);
+            directory.extend(&sizes);
+            directory.extend(name_len);
+            directory.extend([0; 12]);
+            directory.extend((data.len() as u32).to_le_bytes());
+            directory.extend(name.as_bytes());
+            data.extend(b"PK\x03\x04");
+            data.extend([0; 14]);
+            data.extend(&sizes);
+            data.extend(name_len);
+            data.extend([0; 2]);
+            data.extend(name.as_bytes());
+            data.extend(contents.as_bytes());
+        }
+
+        let offset = (data.len() as u32).to_le_bytes();
+
+        data.extend(&directory);
+        data.extend(b"PK\x05\x06");
+        data.extend([0; 4]);
+        data.extend([(files.len() as u16).to_le_bytes(); 2].concat());
+        data.extend((directory.len() as u32).to_le_bytes());
+        data.extend(offset);
+        data.extend([0; 2]);
+        data
+    }
+
+    #[test]
+    fn parses_worksheets() {
+        let workbook = stored_zip(&[
+            (
+                "xl/workbook.xml",
+                r#"<workbook xmlns:r="r"><sheets><sheet name="Notes" r:id="rId1"/><sheet name="Costs" r:id="rId2"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="/xl/worksheets/sheet2.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>Team</t></si><si><t>Cost</t></si><si><r><t>R&amp;</t></r><r><t>D</t></r></si></sst>"#,
+            ),
+            ("xl/worksheets/sheet1.xml", "<worksheet/>"),
+            (
+                "xl/worksheets/sheet2.xml",
+                r#"<worksheet><sheetData><row r="2"><c r="B2" t="s"><v>0</v></c><c r="D2" t="s"><v>1</v></c></row><row r="3"><c r="B3" t="s"><v>2</v></c><c r="D3"><f>SUM(E3:F3)</f><v>1234.5678</v></c></row><row r="4"><c r="B4" t="inlineStr"><is><t>Sales</t></is></c><c r="D4"><v>12</v></c></row></sheetData></worksheet>"#,
+            ),
+        ]);
+        let table = Table {
+            sheet: Some("Costs"),
+            key_column: Some("B"),
+            value_column: Some("cost"),
+        };
+        let chart = parse(&workbook, "Budget", &table).unwrap();
+
+        assert_eq!(chart.items.len(), 2);
+        assert_eq!(chart.items[0].key, "R&D");
+        assert_eq!(chart.items[0].value, 1234.5678);
+        assert_eq!(chart.items[1].key, "Sales");
+        assert!(matches!(
+            parse(
+                &workbook,
+                "",
+                &Table {
+                    sheet: Some("Missing"),
+                    ..Default::default()
+                }
+            ),
+            Err(PieChartError::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+//! Just enough of an XML reader for the parts of an Excel workbook and the data embedded in an
+//! SVG file: elements, attributes and text. Namespace prefixes are dropped, and declarations,
+//! comments and doctypes are skipped.
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Event<'a> {
+    /// An element start tag, or an empty element if `empty` is set
+    Start {
+        name: &'a str,
+        attributes: Vec<(&'a str, String)>,
+        empty: bool,
+    },
+    End(&'a str),
+    Text(String),
+}
+
+impl Event<'_> {
+    /// The value of an attribute of a start tag
+    pub(crate) fn attribute(self: &Self, name: &str) -> Option<&str> {
+        match self {
+            Event::Start { attributes, .. } => attributes
+                .iter()
+                .find(|(attribute, _)| *attribute == name)
+                .map(|(_, value)| value.as_str()),
+            _ => None,
+        }
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+pub(crate) fn events(xml: &str) -> Vec<Event<'_>> {
+    let mut events = vec![];
+    let mut rest = xml;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            events.push(Event::Text(unescape(rest)));
+            break;
+        };
+
+        if start > 0 {
+            events.push(Event::Text(unescape(&rest[..start])));
+        }
+
+        rest = &rest[start..];
+
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+
+            events.push(Event::Text(cdata[..end].to_string()));
+            rest = cdata.get(end + 3..).unwrap_or_default();
+            continue;
+        }
+
+        let terminator = if rest.starts_with("<!--") { "-->" } else { ">" };
+        let end = rest.find(terminator).unwrap_or(rest.len());
+        let tag = &rest[1..end];
+
+        rest = rest.get(end + terminator.len()..).unwrap_or_default();
+
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            events.push(Event::End(local_name(name.trim())));
+            continue;
+        }
+
+        let (tag, empty) = match tag.strip_suffix('/') {
+            Some(tag) => (tag, true),
+            None => (tag, false),
+        };
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+
+        events.push(Event::Start {
+            name: local_name(&tag[..name_end]),
+            attributes: attributes(&tag[name_end..]),
+            empty,
+        });
+    }
+
+    events
+}
+
+fn attributes(mut rest: &str) -> Vec<(&str, String)> {
+    let mut attributes = vec![];
+
+    while let Some(equals) = rest.find('=') {
+        let name = local_name(rest[..equals].trim());
+        let value = rest[equals + 1..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let end = value[1..].find(quote).map_or(value.len(), |end| end + 1);
+
+        attributes.push((name, unescape(&value[1..end])));
+        rest = value.get(end + 1..).unwrap_or_default();
+    }
+
+    attributes
+}
+
+/// Replace the predefined entities and character references
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+
+        match c {
+            Some(c) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_elements_and_text() {
+        let events = events(
+            "<?xml version=\"1.0\"?><x:si a='1 &amp; 2' r:id=\"rId1\"><t>R&amp;D &#8364;</t><br/></x:si>",
+        );
+
+        assert_eq!(events[0].attribute("a"), Some("1 & 2"));
+        assert_eq!(events[0].attribute("id"), Some("rId1"));
+        assert_eq!(events[2], Event::Text("R&D €".to_string()));
+        assert!(matches!(
+            events[4],
+            Event::Start {
+                name: "br",
+                empty: true,
+                ..
+            }
+        ));
+        assert_eq!(events[5], Event::End("si"));
+    }
+}
@@ -0,0 +1,468 @@
+//! A parser for the subset of YAML used by chart files: block mappings and sequences, flow
+//! collections, plain and quoted scalars, and comments. Anchors, tags and block scalars are not
+//! supported.
+
+use super::parse_error;
+use crate::PieChartError;
+use serde_json::{Map, Value};
+
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+pub(crate) fn parse(content: &str) -> Result<Value, PieChartError> {
+    let mut lines = vec![];
+
+    for (index, raw) in content.lines().enumerate() {
+        let text = strip_comment(raw).trim_end();
+        let trimmed = text.trim_start();
+
+        if trimmed.is_empty() || trimmed == "---" || trimmed == "..." {
+            continue;
+        }
+
+        if raw.starts_with('\t') {
+            return Err(parse_error(
+                "Tabs cannot be used for indentation",
+                index + 1,
+            ));
+        }
+
+        lines.push(Line {
+            number: index + 1,
+            indent: text.len() - trimmed.len(),
+            text: trimmed,
+        });
+    }
+
+    if lines.is_empty() {
+        return Ok(Value::Null);
+    }
+
+    let mut parser = Parser { lines, pos: 0 };
+    let indent = parser.lines[0].indent;
+    let value = parser.node(indent)?;
+
+    if let Some(line) = parser.lines.get(parser.pos) {
+        return Err(parse_error("Unexpected indentation", line.number));
+    }
+
+    Ok(value)
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn node(&mut self, indent: usize) -> Result<Value, PieChartError> {
+        if is_sequence_entry(self.lines[self.pos].text) {
+            self.sequence(indent)
+        } else {
+            self.mapping(indent)
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, PieChartError> {
+        let mut values = vec![];
+
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent != indent || !is_sequence_entry(line.text) {
+                break;
+            }
+
+            let rest = line.text[1..].trim_start();
+            let number = line.number;
+
+            if rest.is_empty() {
+                self.pos += 1;
+                values.push(self.child(indent)?);
+            } else if split_key(rest).is_some() {
+                // A mapping that starts on the same line as the dash
+                let offset = line.text.len() - rest.len();
+
+                self.lines[self.pos] = Line {
+                    number,
+                    indent: indent + offset,
+                    text: rest,
+                };
+                values.push(self.mapping(indent + offset)?);
+            } else {
+                self.pos += 1;
+                values.push(scalar(rest, number)?);
+            }
+        }
+
+        Ok(Value::Array(values))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, PieChartError> {
+        let mut map = Map::new();
+
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent != indent || is_sequence_entry(line.text) {
+                break;
+            }
+
+            let number = line.number;
+            let (key, rest) =
+                split_key(line.text).ok_or_else(|| parse_error("Expected a key", number))?;
+            let key = unquote(key, number)?;
+
+            self.pos += 1;
+
+            let value = if !rest.is_empty() {
+                scalar(rest, number)?
+            } else {
+                match self.lines.get(self.pos) {
+                    // Sequences under a key may be at the same indent as the key
+                    Some(next) if next.indent == indent && is_sequence_entry(next.text) => {
+                        self.sequence(indent)?
+                    }
+                    _ => self.child(indent)?,
+                }
+            };
+
+            if map.insert(key.clone(), value).is_some() {
+                return Err(parse_error(format!("Duplicate key '{}'", key), number));
+            }
+        }
+
+        Ok(Value::Object(map))
+    }
+
+    /// The node nested under the line at `indent`, or null if there is none
+    fn child(&mut self, indent: usize) -> Result<Value, PieChartError> {
+        match self.lines.get(self.pos) {
+            Some(next) if next.indent > indent => {
+                let indent = next.indent;
+
+                self.node(indent)
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+}
+
+fn is_sequence_entry(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Split `key: value` at the first colon outside quotes that is followed by a space or the end
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    let mut quote = None;
+
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') if index == 0 => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '{') | (None, '[') if index == 0 => return None,
+            (None, ':') => {
+                let rest = &text[index + 1..];
+
+                if rest.is_empty() || rest.starts_with(' ') {
+                    return Some((text[..index].trim(), rest.trim()));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    None
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '#') if previous.is_whitespace() => return &line[..index],
+            _ => (),
+        }
+
+        previous = c;
+    }
+
+    line
+}
+
+fn unquote(text: &str, number: usize) -> Result<String, PieChartError> {
+    match scalar(text, number)? {
+        Value::String(s) => Ok(s),
+        _ => Ok(text.to_string()),
+    }
+}
+
+fn scalar(text: &str, number: usize) -> Result<Value, PieChartError> {
+    let mut flow = Flow {
+        chars: text.chars().collect(),
+        pos: 0,
+        number,
+    };
+    let value = flow.value(false)?;
+
+    flow.skip_whitespace();
+
+    if flow.pos < flow.chars.len() {
+        return Err(parse_error(
+            format!("Unexpected text after '{}'", text),
+            number,
+        ));
+    }
+
+    Ok(value)
+}
+
+/// Parses scalars and `[...]`/`{...}` flow collections
+struct Flow {
+    chars: Vec<char>,
+    pos: usize,
+    number: usize,
+}
+
+impl Flow {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self, in_flow: bool) -> Result<Value, PieChartError> {
+        self.skip_whitespace();
+
+        match self.chars.get(self.pos) {
+            Some('[') => {
+                self.pos += 1;
+
+                let mut values = vec![];
+
+                loop {
+                    self.skip_whitespace();
+
+                    if self.chars.get(self.pos) == Some(&']') {
+                        self.pos += 1;
+                        break;
+                    }
+
+                    values.push(self.value(true)?);
+                    self.separator(']')?;
+                }
+
+                Ok(Value::Array(values))
+            }
+            Some('{') => {
+                self.pos += 1;
+
+                let mut map = Map::new();
+
+                loop {
+                    self.skip_whitespace();
+
+                    if self.chars.get(self.pos) == Some(&'}') {
+                        self.pos += 1;
+                        break;
+                    }
+
+                    let key = match self.value(true)? {
+                        Value::String(key) => key,
+                        other => other.to_string(),
+                    };
+
+                    self.skip_whitespace();
+
+                    if self.chars.get(self.pos) != Some(&':') {
+                        return Err(self.error("Expected ':' in flow mapping"));
+                    }
+
+                    self.pos += 1;
+
+                    let value = self.value(true)?;
+
+                    map.insert(key, value);
+                    self.separator('}')?;
+                }
+
+                Ok(Value::Object(map))
+            }
+            Some('"') => self.double_quoted().map(Value::String),
+            Some('\'') => self.single_quoted().map(Value::String),
+            Some(_) => {
+                let start = self.pos;
+
+                while let Some(&c) = self.chars.get(self.pos) {
+                    if in_flow && (c == ',' || c == ']' || c == '}') {
+                        break;
+                    }
+
+                    if in_flow
+                        && c == ':'
+                        && self
+                            .chars
+                            .get(self.pos + 1)
+                            .is_none_or(|c| c.is_whitespace())
+                    {
+                        break;
+                    }
+
+                    self.pos += 1;
+                }
+
+                let plain: String = self.chars[start..self.pos].iter().collect();
+
+                Ok(plain_scalar(plain.trim()))
+            }
+            None => Ok(Value::Null),
+        }
+    }
+
+    fn separator(&mut self, close: char) -> Result<(), PieChartError> {
+        self.skip_whitespace();
+
+        match self.chars.get(self.pos) {
+            Some(',') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(&c) if c == close => Ok(()),
+            _ => Err(self.error(format!("Expected ',' or '{}'", close))),
+        }
+    }
+
+    fn double_quoted(&mut self) -> Result<String, PieChartError> {
+        let mut text = String::new();
+
+        self.pos += 1;
+
+        while let Some(&c) = self.chars.get(self.pos) {
+            self.pos += 1;
+
+            match c {
+                '"' => return Ok(text),
+                '\\' => {
+                    let escaped = self
+                        .chars
+                        .get(self.pos)
+                        .copied()
+                        .ok_or_else(|| self.error("Unterminated string"))?;
+
+                    self.pos += 1;
+                    text.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        '0' => '\0',
+                        other => other,
+                    });
+                }
+                _ => text.push(c),
+            }
+        }
+
+        Err(self.error("Unterminated string"))
+    }
+
+    fn single_quoted(&mut self) -> Result<String, PieChartError> {
+        let mut text = String::new();
+
+        self.pos += 1;
+
+        while let Some(&c) = self.chars.get(self.pos) {
+            self.pos += 1;
+
+            if c == '\'' {
+                if self.chars.get(self.pos) == Some(&'\'') {
+                    text.push('\'');
+                    self.pos += 1;
+                } else {
+                    return Ok(text);
+                }
+            } else {
+                text.push(c);
+            }
+        }
+
+        Err(self.error("Unterminated string"))
+    }
+
+    fn error(&self, message: impl Into<String>) -> PieChartError {
+        parse_error(message, self.number)
+    }
+}
+
+fn plain_scalar(text: &str) -> Value {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => Value::Null,
+        "true" | "True" | "TRUE" => Value::Bool(true),
+        "false" | "False" | "FALSE" => Value::Bool(false),
+        _ => {
+            if let Ok(integer) = text.parse::<i64>() {
+                Value::from(integer)
+            } else if let Some(number) = text
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .and_then(serde_json::Number::from_f64)
+            {
+                Value::Number(number)
+            } else {
+                Value::String(text.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_nested_blocks_and_flows() {
+        let value = parse(
+            r#"
+# A chart
+title: "Disk: usage" # trailing comment
+items:
+- key: A
+  value: 2.5
+  color: '#ff0000'
+-
+  key: B
+  value: 30
+- {key: "C, D", value: 1}
+options:
+  donut: true
+  size: ~
+styles: [".a{fill:red}", '.b{}']
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            value,
+            json!({
+                "title": "Disk: usage",
+                "items": [
+                    {"key": "A", "value": 2.5, "color": "#ff0000"},
+                    {"key": "B", "value": 30},
+                    {"key": "C, D", "value": 1},
+                ],
+                "options": {"donut": true, "size": null},
+                "styles": [".a{fill:red}", ".b{}"],
+            })
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_lines() {
+        match parse("title: x\n  bad: indent\n") {
+            Err(PieChartError::Parse { line, .. }) => assert_eq!(line, Some(2)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
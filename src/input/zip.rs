@@ -0,0 +1,327 @@
+//! A reader for the files in a zip archive, such as an Excel workbook. Only stored and deflated
+//! files are supported, which is all that spreadsheet programs write.
+
+use crate::PieChartError;
+
+fn invalid(message: &str) -> PieChartError {
+    PieChartError::Parse {
+        message: format!("Not a valid zip file: {}", message),
+        line: None,
+        column: None,
+    }
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Result<usize, PieChartError> {
+    data.get(pos..pos + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+        .ok_or_else(|| invalid("unexpected end of file"))
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Result<usize, PieChartError> {
+    data.get(pos..pos + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| invalid("unexpected end of file"))
+}
+
+/// Read the file with the given name from the archive, or `None` if there is no such file
+pub(crate) fn read(data: &[u8], name: &str) -> Result<Option<Vec<u8>>, PieChartError> {
+    // The end of central directory record is last, followed only by a comment
+    let end = (0..data.len().saturating_sub(21))
+        .rev()
+        .find(|&pos| data[pos..].starts_with(b"PK\x05\x06"))
+        .ok_or_else(|| invalid("no central directory"))?;
+    let count = u16_at(data, end + 10)?;
+    let mut pos = u32_at(data, end + 16)?;
+
+    for _ in 0..count {
+        if !data
+            .get(pos..)
+            .is_some_and(|d| d.starts_with(b"PK\x01\x02"))
+        {
+            return Err(invalid("bad central directory entry"));
+        }
+
+        let method = u16_at(data, pos + 10)?;
+        let compressed_size = u32_at(data, pos + 20)?;
+        let name_len = u16_at(data, pos + 28)?;
+        let extra_len = u16_at(data, pos + 30)?;
+        let comment_len = u16_at(data, pos + 32)?;
+        let header = u32_at(data, pos + 42)?;
+        let entry_name = data
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+
+        pos += 46 + name_len + extra_len + comment_len;
+
+        if entry_name != name.as_bytes() {
+            continue;
+        }
+
+        // The local header repeats the name and may have a different extra field
+        let start = header + 30 + u16_at(data, header + 26)? + u16_at(data, header + 28)?;
+        let contents = data
+            .get(start..start + compressed_size)
+            .ok_or_else(|| invalid("unexpected end of file"))?;
+
+        return match method {
+            0 => Ok(Some(contents.to_vec())),
+            8 => inflate(contents).map(Some),
+            _ => Err(invalid(&format!(
+                "'{}' uses unsupported compression method {}",
+                name, method
+            ))),
+        };
+    }
+
+    Ok(None)
+}
+
+pub(crate) const LENGTH_BASE: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+pub(crate) const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+pub(crate) const DISTANCE_BASE: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+pub(crate) const DISTANCE_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order that the code lengths of the code length alphabet are given in
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+impl Bits<'_> {
+    fn bits(self: &mut Self, count: u32) -> Result<usize, PieChartError> {
+        let mut value = 0;
+
+        for i in 0..count {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or_else(|| invalid("compressed data ends early"))?;
+
+            value |= ((byte >> self.bit) as usize & 1) << i;
+            self.bit += 1;
+
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+/// A canonical Huffman code, as the number of codes of each length and the symbols in code order
+struct Huffman {
+    counts: [usize; 16],
+    symbols: Vec<usize>,
+}
+
+impl Huffman {
+    fn new(lengths: &[usize]) -> Huffman {
+        let mut counts = [0; 16];
+
+        for &length in lengths {
+            counts[length] += 1;
+        }
+
+        counts[0] = 0;
+
+        let mut symbols: Vec<usize> = (0..lengths.len()).filter(|&s| lengths[s] != 0).collect();
+
+        symbols.sort_by_key(|&symbol| lengths[symbol]);
+
+        Huffman { counts, symbols }
+    }
+
+    fn decode(self: &Self, bits: &mut Bits) -> Result<usize, PieChartError> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+
+        for &count in &self.counts[1..] {
+            code |= bits.bits(1)?;
+
+            if code < first + count {
+                return Ok(self.symbols[index + code - first]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(invalid("bad Huffman code"))
+    }
+}
+
+/// Decompress raw DEFLATE data
+pub(crate) fn inflate(data: &[u8]) -> Result<Vec<u8>, PieChartError> {
+    let mut bits = Bits {
+        data,
+        pos: 0,
+        bit: 0,
+    };
+    let mut out = vec![];
+
+    loop {
+        let last = bits.bits(1)? == 1;
+
+        match bits.bits(2)? {
+            0 => {
+                if bits.bit != 0 {
+                    bits.bit = 0;
+                    bits.pos += 1;
+                }
+
+                let length = u16_at(data, bits.pos)?;
+                let start = bits.pos + 4;
+
+                out.extend_from_slice(
+                    data.get(start..start + length)
+                        .ok_or_else(|| invalid("compressed data ends early"))?,
+                );
+                bits.pos = start + length;
+            }
+            1 => {
+                let mut lengths = [8; 288];
+
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+
+                inflate_block(
+                    &mut bits,
+                    &mut out,
+                    &Huffman::new(&lengths),
+                    &Huffman::new(&[5; 30]),
+                )?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut bits)?;
+
+                inflate_block(&mut bits, &mut out, &literals, &distances)?;
+            }
+            _ => return Err(invalid("bad block type")),
+        }
+
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn dynamic_codes(bits: &mut Bits) -> Result<(Huffman, Huffman), PieChartError> {
+    let literal_count = bits.bits(5)? + 257;
+    let distance_count = bits.bits(5)? + 1;
+    let code_length_count = bits.bits(4)? + 4;
+    let mut code_lengths = [0; 19];
+
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[symbol] = bits.bits(3)?;
+    }
+
+    let code_lengths = Huffman::new(&code_lengths);
+    let mut lengths = vec![];
+
+    while lengths.len() < literal_count + distance_count {
+        let (length, repeat) = match code_lengths.decode(bits)? {
+            16 => (
+                *lengths
+                    .last()
+                    .ok_or_else(|| invalid("repeat with no previous length"))?,
+                3 + bits.bits(2)?,
+            ),
+            17 => (0, 3 + bits.bits(3)?),
+            18 => (0, 11 + bits.bits(7)?),
+            length => (length, 1),
+        };
+
+        lengths.extend(std::iter::repeat_n(length, repeat));
+    }
+
+    if lengths.len() > literal_count + distance_count {
+        return Err(invalid("too many code lengths"));
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count]),
+        Huffman::new(&lengths[literal_count..]),
+    ))
+}
+
+fn inflate_block(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), PieChartError> {
+    loop {
+        let symbol = literals.decode(bits)?;
+
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let index = symbol - 257;
+                let length = LENGTH_BASE[index] + bits.bits(LENGTH_EXTRA[index])?;
+                let index = distances.decode(bits)?;
+
+                if index >= DISTANCE_BASE.len() {
+                    return Err(invalid("bad distance code"));
+                }
+
+                let distance = DISTANCE_BASE[index] + bits.bits(DISTANCE_EXTRA[index])?;
+
+                if distance > out.len() {
+                    return Err(invalid("distance is too far back"));
+                }
+
+                // The copy can overlap the bytes being written, so it goes a byte at a time
+                for _ in 0..length {
+                    out.push(out[out.len() - distance]);
+                }
+            }
+            _ => return Err(invalid("bad length code")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inflates_fixed_and_dynamic_blocks() {
+        // From Python's zlib.compressobj(wbits=-15)
+        let fixed = [0x4b, 0x4c, 0x4a, 0x4e, 0x84, 0x21, 0x00];
+        let dynamic = [
+            0xd5, 0x8d, 0xd9, 0x15, 0x40, 0x30, 0x14, 0x05, 0x5b, 0xb9, 0x1a, 0x70, 0xec, 0x4b,
+            0x17, 0x3e, 0x34, 0x10, 0x04, 0xb1, 0x3d, 0x42, 0x82, 0x54, 0xef, 0xb5, 0xe1, 0x7b,
+            0x66, 0xce, 0xd4, 0xa3, 0xc4, 0x61, 0x54, 0x3b, 0xa3, 0xd1, 0x74, 0x6f, 0xe8, 0xe9,
+            0xc1, 0x64, 0xd6, 0xfd, 0x04, 0x59, 0xa9, 0x71, 0x31, 0x5e, 0x84, 0x7b, 0xd1, 0xd1,
+            0xe0, 0xa3, 0x12, 0xec, 0xad, 0x2f, 0x1a, 0x96, 0x6e, 0x75, 0x8d, 0xe8, 0x95, 0x95,
+            0x8c, 0x9c, 0xdc, 0xb0, 0xa8, 0xc3, 0x90, 0xe6, 0x76, 0x38, 0x3d, 0x04, 0x61, 0x14,
+            0x27, 0x69, 0x96, 0x17, 0x25, 0xea, 0xdf, 0x0f, 0x3e,
+        ];
+
+        assert_eq!(inflate(&fixed).unwrap(), b"abcabcabcabc");
+        assert_eq!(
+            inflate(&dynamic).unwrap(),
+            "The quick brown fox jumps over the lazy dog. Pack my box with five dozen liquor jugs! 0123456789 "
+                .repeat(3)
+                .as_bytes()
+        );
+    }
+}
@@ -1,361 +1,8018 @@
+mod animation;
+mod base64;
+mod builder;
+mod callout;
+mod color;
+mod color_vision;
+mod config;
+mod convert;
+mod diff;
+mod error;
+mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod flags;
+mod input;
 mod log_macros;
+mod number_format;
+mod output;
+mod palette;
+mod report;
+mod schema;
+mod serve;
+mod strings;
+mod summary;
+mod text;
+mod theme;
 
-use clap::Parser;
+pub use builder::ChartDataBuilder;
+pub use error::PieChartError;
+pub use export::ExportFormat;
+pub use input::InputFormat;
+pub use output::{AnimationFormat, OutputFormat};
+pub use palette::{ColorSpace, Palette};
+pub use report::{ChartReport, LegendEntry, Report, WedgeReport};
+pub use svg::Document;
+pub use theme::Theme;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use core::fmt::Arguments;
-use easy_error::{self, ResultExt};
+use flags::OptionArgs;
+use number_format::{Locale, NumberFormatter};
+use output::Renderer;
 use rand::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
-    error::Error,
-    fs::File,
-    io::{self, Read, Write},
-    path::PathBuf,
-    vec,
-};
-use svg::{
-    node::{element::path::*, *},
-    Document,
+    cell::RefCell,
+    f64::consts::{FRAC_PI_2, PI},
+    fs::{self, File},
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+    process, vec,
 };
+use strings::{Message, Strings};
+use svg::node::{element::path::*, *};
+
+const GOLDEN_RATIO_CONJUGATE: f32 = 0.618034;
+const DEFAULT_SATURATION: f64 = 0.5;
+const DEFAULT_BRIGHTNESS: f64 = 0.5;
+/// The lightness and chroma of random colors in OKLCH, about as muted as the HSV colors
+const OKLCH_LIGHTNESS: f64 = 0.6;
+const OKLCH_CHROMA: f64 = 0.1;
+const DEFAULT_HOLE_RATIO: f64 = 0.5;
+/// The largest size of the text in the hole of a donut, which is smaller if it would not fit
+const CENTER_FONT_SIZE: f64 = 24.0;
+const DEFAULT_PIE_DIAMETER: f64 = 400.0;
+const DEFAULT_GUTTER: f64 = 40.0;
+const DEFAULT_LEGEND_HEIGHT: f64 = 20.0;
+const LEGEND_GUTTER: f64 = 10.0;
+const LABEL_FONT_SIZE: f64 = 10.0;
+/// The distance between the middles of labels outside the pie
+const CALLOUT_SPACING: f64 = LABEL_FONT_SIZE * 1.2;
+/// The diameter of the breakout pie as a fraction of that of the pie
+const BREAKOUT_RATIO: f64 = 0.5;
+/// The space between the pie and the breakout pie
+const BREAKOUT_GAP: f64 = 60.0;
+/// The number of squares across and down a waffle chart
+const WAFFLE_SIZE: usize = 10;
+/// The space between the squares of a waffle chart, as a fraction of their spacing
+const WAFFLE_GAP: f64 = 0.1;
+/// The most space from the top of one bar of a bar chart to the next
+const MAX_BAR_SPACING: f64 = 40.0;
+/// The space between the bars of a bar chart, as a fraction of their spacing
+const BAR_GAP: f64 = 0.25;
+/// The least space between a label and the sides of the rectangle of a treemap it is inside
+const TREEMAP_PADDING: f64 = 2.0;
+/// The 12pt legend font, in pixels
+const LEGEND_FONT_SIZE: f64 = 16.0;
+const LEGEND_LINE_HEIGHT: f64 = LEGEND_FONT_SIZE * 1.2;
+/// Legend entries are not made wider than this to fit long labels on one line
+const MAX_LEGEND_ENTRY_WIDTH: f64 = 200.0;
+/// The space between the columns of the table below a diff chart
+const TABLE_COLUMN_GAP: f64 = 20.0;
+/// How far percentage input may add up to other than 100, to allow for rounding
+const PERCENTAGE_TOLERANCE: f64 = 1.0;
+/// The seed for the random colors and the decimal places for coordinates with `--deterministic`
+const DETERMINISTIC_SEED: u64 = 0;
+const DETERMINISTIC_PRECISION: usize = 3;
+const DEFAULT_FONT_FAMILY: &str = "Arial";
+/// Wedges at least this large are drawn as whole circles
+const FULL_PERCENTAGE: f64 = 1.0 - 1e-9;
+const DEFAULT_TITLE_FONT_SIZE: f64 = 12.0;
+/// The subtitle font size as a fraction of the title font size
+const SUBTITLE_FONT_SCALE: f64 = 0.8;
+/// The distance between the baselines of title lines as a multiple of the font size
+const TITLE_LINE_SPACING: f64 = 1.2;
+const CAPTION_FONT_SIZE: f64 = 10.0;
+/// The classes of the elements drawn around the wedges, which the classes of items cannot be as
+/// their styles would apply to those elements too
+const BUILT_IN_CLASSES: [&str; 22] = [
+    "background",
+    "bar-label",
+    "bars",
+    "breakout",
+    "callout",
+    "caption",
+    "center-text",
+    "changes",
+    "connector",
+    "heading",
+    "inverse",
+    "labels",
+    "leader",
+    "left",
+    "legend",
+    "number",
+    "pie",
+    "pie-chart-data",
+    "subtitle",
+    "title",
+    "treemap",
+    "waffle",
+];
+const CAPTION_LINE_HEIGHT: f64 = CAPTION_FONT_SIZE * 1.2;
+
+/// Render a chart to an SVG document. Any options not set in `options` are taken from
+/// the `options` block of the chart data.
+pub fn render(chart_data: &ChartData, options: &ChartOptions) -> Result<Document, PieChartError> {
+    PieChartTool::new(&SilentLog).render(chart_data, options)
+}
+
+/// Render several charts into one SVG document, laid out in a grid
+pub fn render_grid(
+    charts: &[ChartData],
+    options: &ChartOptions,
+) -> Result<Document, PieChartError> {
+    PieChartTool::new(&SilentLog).render_grid(charts, options)
+}
+
+/// Render two charts side by side with one legend, giving each key the same color in both
+pub fn render_comparison(
+    charts: &[ChartData; 2],
+    options: &ChartOptions,
+) -> Result<Document, PieChartError> {
+    PieChartTool::new(&SilentLog).render_comparison(charts, options)
+}
+
+/// Render charts as the frames of one animated SVG, with the wedges moving from each to the next
+pub fn render_animation(
+    frames: &[ChartData],
+    options: &ChartOptions,
+    frame_duration: f64,
+) -> Result<Document, PieChartError> {
+    PieChartTool::new(&SilentLog).render_animation(frames, options, frame_duration)
+}
+
+/// Render charts as the frames of an animation in a format, as the bytes of the file
+pub fn render_animation_as(
+    frames: &[ChartData],
+    options: &ChartOptions,
+    frame_duration: f64,
+    format: AnimationFormat,
+) -> Result<Vec<u8>, PieChartError> {
+    PieChartTool::new(&SilentLog).render_animation_as(frames, options, frame_duration, format)
+}
+
+/// Render the change in each item from one chart to the next, with a table of the changes
+pub fn render_diff(
+    before: &ChartData,
+    after: &ChartData,
+    options: &ChartOptions,
+) -> Result<Document, PieChartError> {
+    PieChartTool::new(&SilentLog).render_diff(before, after, options)
+}
+
+/// Render charts to a PDF document with one page per chart
+pub fn render_pdf(charts: &[ChartData], options: &ChartOptions) -> Result<Vec<u8>, PieChartError> {
+    PieChartTool::new(&SilentLog).render_pdf(charts, options)
+}
+
+/// Render charts to an HTML page with hover tooltips
+pub fn render_html(charts: &[ChartData], options: &ChartOptions) -> Result<String, PieChartError> {
+    PieChartTool::new(&SilentLog).render_html(charts, options)
+}
+
+/// Render charts as text with colored blocks for a terminal
+pub fn render_term(
+    charts: &[ChartData],
+    options: &ChartOptions,
+    color: bool,
+) -> Result<String, PieChartError> {
+    PieChartTool::new(&SilentLog).render_term(charts, options, color)
+}
+
+/// Render charts with a TinyTemplate template, given the layout of each chart
+pub fn render_template(
+    template: &str,
+    charts: &[ChartData],
+    options: &ChartOptions,
+) -> Result<String, PieChartError> {
+    PieChartTool::new(&SilentLog).render_template(template, charts, options)
+}
+
+/// Convert charts to a Vega-Lite spec, with the same values, colors and angles as they are drawn
+pub fn vega_lite_spec(
+    charts: &[ChartData],
+    options: &ChartOptions,
+) -> Result<serde_json::Value, PieChartError> {
+    PieChartTool::new(&SilentLog).vega_lite_spec(charts, options)
+}
+
+/// A description of how charts are drawn, with the angles, centroid, colors and legend entry of
+/// each wedge, which can find the wedge at a point with `Report::wedge_at`
+pub fn report(charts: &[ChartData], options: &ChartOptions) -> Result<Report, PieChartError> {
+    PieChartTool::new(&SilentLog).report(charts, options)
+}
+
+/// A table of the value and percentage of each wedge of a chart, with the running and full totals
+pub fn summary(
+    chart_data: &ChartData,
+    options: &ChartOptions,
+) -> Result<Vec<String>, PieChartError> {
+    PieChartTool::new(&SilentLog).summary(chart_data, options)
+}
+
+/// Write charts as a chart file in another format, such as CSV rows as JSON5
+pub fn convert(charts: &[ChartData], format: InputFormat) -> Result<String, PieChartError> {
+    PieChartTool::new(&SilentLog).convert(charts, format)
+}
+
+/// The colors that the wedges of a chart with `count` items are given by the palette options
+pub fn palette_colors(count: usize, options: &ChartOptions) -> Result<Vec<String>, PieChartError> {
+    PieChartTool::new(&SilentLog).palette_colors(count, options)
+}
+
+/// Render a chart to an SVG string
+pub fn svg_string(chart_data: &ChartData, options: &ChartOptions) -> Result<String, PieChartError> {
+    Ok(PieChartTool::serialize(
+        &render(chart_data, options)?,
+        &options.or(&chart_data.options),
+    ))
+}
+
+/// Render a chart to a base64 `data:image/svg+xml` URI, for Markdown images and HTML attributes
+pub fn svg_data_uri(
+    chart_data: &ChartData,
+    options: &ChartOptions,
+) -> Result<String, PieChartError> {
+    Ok(output::embed::data_uri(&svg_string(chart_data, options)?))
+}
 
-const GOLDEN_RATIO_CONJUGATE: f32 = 0.618033988749895;
+/// How warnings, errors and diagnostics are logged
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Plain text
+    #[default]
+    Text,
+    /// A JSON record on each line, with the level, the message and the file being read
+    Json,
+}
 
 pub trait PieChartLog {
     fn output(self: &Self, args: Arguments);
     fn warning(self: &Self, args: Arguments);
     fn error(self: &Self, args: Arguments);
+    /// Diagnostics such as totals and layout sizes, logged with `-v`
+    fn debug(self: &Self, _args: Arguments) {}
+    /// Diagnostics for each wedge, logged with `-vv`
+    fn trace(self: &Self, _args: Arguments) {}
+    /// A line of JSON logged with `--log-format json` instead of a warning, error or diagnostic
+    fn record(self: &Self, record: &str) {
+        eprintln!("{}", record);
+    }
 }
 
 pub struct PieChartTool<'a> {
+    log: VerboseLog<'a>,
+}
+
+/// Passes debug and trace messages on to a log only at the verbosity asked for, and turns
+/// messages into records in the JSON log format
+struct VerboseLog<'a> {
     log: &'a dyn PieChartLog,
+    verbosity: u8,
+    format: LogFormat,
+    /// The file being read, which records give as their context
+    file: RefCell<Option<String>>,
+}
+
+impl VerboseLog<'_> {
+    fn set_file(self: &Self, input: Option<&Path>) {
+        *self.file.borrow_mut() = input.map(|input| input.to_string_lossy().to_string());
+    }
+
+    fn emit(
+        self: &Self,
+        level: &str,
+        args: Arguments,
+        text: impl FnOnce(&dyn PieChartLog, Arguments),
+    ) {
+        match self.format {
+            LogFormat::Text => text(self.log, args),
+            LogFormat::Json => {
+                let mut context = serde_json::Map::new();
+
+                if let Some(ref file) = *self.file.borrow() {
+                    context.insert("file".to_string(), file.as_str().into());
+                }
+
+                self.log.record(
+                    &serde_json::json!({
+                        "level": level,
+                        "message": args.to_string(),
+                        "context": context,
+                    })
+                    .to_string(),
+                );
+            }
+        }
+    }
+}
+
+impl PieChartLog for VerboseLog<'_> {
+    fn output(self: &Self, args: Arguments) {
+        self.log.output(args);
+    }
+    fn warning(self: &Self, args: Arguments) {
+        self.emit("warning", args, |log, args| log.warning(args));
+    }
+    fn error(self: &Self, args: Arguments) {
+        self.emit("error", args, |log, args| log.error(args));
+    }
+    fn debug(self: &Self, args: Arguments) {
+        if self.verbosity >= 1 {
+            self.emit("debug", args, |log, args| log.debug(args));
+        }
+    }
+    fn trace(self: &Self, args: Arguments) {
+        if self.verbosity >= 2 {
+            self.emit("trace", args, |log, args| log.trace(args));
+        }
+    }
+    fn record(self: &Self, record: &str) {
+        self.log.record(record);
+    }
+}
+
+struct SilentLog;
+
+impl PieChartLog for SilentLog {
+    fn output(self: &Self, _args: Arguments) {}
+    fn warning(self: &Self, _args: Arguments) {}
+    fn error(self: &Self, _args: Arguments) {}
 }
 
 #[derive(Parser)]
-#[clap(version, about, long_about = None)]
+#[clap(version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+
+    /// Log totals, colors and layout sizes, and with `-vv` the details of each wedge
+    #[arg(long = "verbose", short = 'v', action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Log warnings, errors and diagnostics as text or as JSON records
+    #[arg(
+        long = "log-format",
+        value_name = "LOG_FORMAT",
+        default_value = "text",
+        global = true
+    )]
+    log_format: LogFormat,
+
+    /// A TOML file of default options, instead of ~/.config/pie_chart/config.toml
+    #[arg(long = "config", value_name = "CONFIG_FILE", global = true)]
+    config: Option<PathBuf>,
+
+    /// Rendering is what is done without a subcommand, so `pie-chart IN OUT` is the same as
+    /// `pie-chart render IN OUT`
+    #[command(flatten)]
+    render: RenderArgs,
+}
+
+/// Where the items are in an input file and what format it is in
+#[derive(Args)]
+struct InputArgs {
+    /// The input file format, instead of detecting it from the file name or content
+    #[arg(long = "input-format", value_name = "FORMAT")]
+    input_format: Option<InputFormat>,
+
+    /// The worksheet to read from an Excel workbook, instead of the first
+    #[arg(long = "sheet", value_name = "NAME")]
+    sheet: Option<String>,
+
+    /// The column of keys in a CSV file or worksheet, as a letter like `B` or a header name
+    #[arg(long = "key-column", value_name = "COLUMN")]
+    key_column: Option<String>,
+
+    /// The column of values in a CSV file or worksheet, as a letter like `C` or a header name
+    #[arg(long = "value-column", value_name = "COLUMN")]
+    value_column: Option<String>,
+}
+
+/// The files to render and how to render them
+#[derive(Args)]
+struct RenderArgs {
     /// Disable colors in output
     #[arg(long = "no-color", short = 'n', env = "NO_CLI_COLOR")]
     no_color: bool,
 
-    /// The input file
-    #[arg(value_name = "INPUT_FILE")]
-    input_file: Option<PathBuf>,
+    /// The input file and optional output file, or with `--out-dir` any number of input files
+    #[arg(value_name = "FILES")]
+    files: Vec<PathBuf>,
+
+    /// Render one SVG per input file into this directory, named after the input file
+    #[arg(long = "out-dir", value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+
+    /// Read one JSON chart per line of the input, rendering each to its own file named by this
+    /// template, where `{n}` is the line number and `{title}` the chart title
+    #[arg(
+        long = "out-template",
+        value_name = "TEMPLATE",
+        conflicts_with_all = ["out_dir", "watch", "sqlite"]
+    )]
+    out_template: Option<String>,
+
+    /// Check that the charts can be rendered, reporting their percentages and any layout
+    /// problems, without writing any output
+    #[arg(long = "check", conflicts_with_all = ["watch", "out_template"])]
+    check: bool,
+
+    /// Render the first chart of each of two input files side by side, with one legend and the
+    /// same color for each key
+    #[arg(
+        long = "compare",
+        conflicts_with_all = ["out_dir", "watch", "out_template", "check", "sqlite", "template"]
+    )]
+    compare: bool,
+
+    /// Write a JSON description of the charts as they are drawn to this file: the size of the
+    /// document, and the angles, colors and legend entry of each wedge
+    #[arg(
+        long = "report",
+        value_name = "REPORT_FILE",
+        conflicts_with_all = ["out_dir", "out_template", "compare", "check", "template"]
+    )]
+    report: Option<PathBuf>,
+
+    /// Print a table of the value, percentage and cumulative percentage of each wedge of the
+    /// charts, with their total, as well as rendering them
+    #[arg(long = "summary", conflicts_with_all = ["out_template", "template"])]
+    summary: bool,
+
+    /// Write the output file next to the input file, named after it, when only an input file is
+    /// given
+    #[arg(
+        long = "auto-out",
+        conflicts_with_all = ["out_dir", "out_template", "sqlite", "check", "compare"]
+    )]
+    auto_out: bool,
+
+    /// Open the output files in the default viewer for their type once they are written
+    #[arg(long = "open", conflicts_with_all = ["check", "out_template"])]
+    open: bool,
+
+    /// Overwrite output files that already exist, which are otherwise left as they are
+    #[arg(long = "force", short = 'f')]
+    force: bool,
+
+    /// Re-render whenever an input file changes
+    #[arg(long = "watch", short = 'w')]
+    watch: bool,
+
+    /// The output format, instead of detecting it from the output file name
+    #[arg(long = "format", value_name = "FORMAT")]
+    format: Option<OutputFormat>,
+
+    #[command(flatten)]
+    input: InputArgs,
+
+    /// Chart the rows of a query against this SQLite database instead of reading a chart file,
+    /// in which case the only file given is the output file
+    #[arg(
+        long = "sqlite",
+        value_name = "DB_FILE",
+        requires = "query",
+        conflicts_with_all = ["out_dir", "watch"]
+    )]
+    sqlite: Option<PathBuf>,
+
+    /// The query for `--sqlite`, returning `(key, value)` rows
+    #[arg(long = "query", value_name = "SQL", requires = "sqlite")]
+    query: Option<String>,
+
+    /// Render the laid out charts with this template instead of in an output format
+    #[arg(long = "template", value_name = "TEMPLATE_FILE")]
+    template: Option<PathBuf>,
+
+    #[command(flatten)]
+    options: OptionArgs,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Render chart files, which is also what is done when no subcommand is given
+    Render(Box<RenderArgs>),
+    /// Check chart files against the JSON Schema instead of rendering them
+    Validate {
+        /// The chart files, or standard input
+        #[arg(value_name = "FILES")]
+        files: Vec<PathBuf>,
+
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Convert a chart file to another format without rendering it, checking its charts and
+    /// leaving out the options that are not set
+    Convert {
+        /// The chart file and an optional output file, or standard input and output
+        #[arg(value_name = "FILES", num_args = 0..=2)]
+        files: Vec<PathBuf>,
+
+        /// The format to convert to, instead of detecting it from the output file name
+        #[arg(long = "to", value_name = "FORMAT")]
+        to: Option<InputFormat>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(long = "force", short = 'f')]
+        force: bool,
+
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Print the colors that the wedges of a chart are given by a palette, one to a line
+    Palette {
+        /// The number of colors to print
+        #[arg(long = "count", value_name = "COUNT", default_value_t = 10)]
+        count: usize,
 
-    /// The output file
-    #[arg(value_name = "OUTPUT_FILE")]
-    output_file: Option<PathBuf>,
+        #[command(flatten)]
+        options: Box<OptionArgs>,
+    },
+    /// Render the charts of each request sent to an address over HTTP, replying with SVG
+    Serve {
+        /// The address to listen on
+        #[arg(
+            long = "address",
+            value_name = "ADDRESS",
+            default_value = "127.0.0.1:8080"
+        )]
+        address: String,
+
+        #[command(flatten)]
+        options: Box<OptionArgs>,
+    },
+    /// Print the JSON Schema of chart files, for editors to complete and check them with
+    Schema,
+    /// Render the change in each item from the first chart of one file to that of another, as a
+    /// pie of how much each item changed by and a table of the changes as percentages
+    Diff {
+        /// The earlier and later chart files, and an optional SVG output file
+        #[arg(value_name = "FILES", num_args = 2..=3, required = true)]
+        files: Vec<PathBuf>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(long = "force", short = 'f')]
+        force: bool,
+
+        #[command(flatten)]
+        options: Box<OptionArgs>,
+    },
+    /// Render the charts of the input files as the frames of one animation, in which the wedges
+    /// move from each frame to the next
+    Animate {
+        /// The chart files, each of which may hold several frames, or standard input
+        #[arg(value_name = "FILES")]
+        files: Vec<PathBuf>,
+
+        /// The output file, or standard output
+        #[arg(long = "output", short = 'o', value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// The format of the animation, guessed from the output file extension if not given
+        #[arg(long = "format", value_name = "FORMAT")]
+        format: Option<AnimationFormat>,
+
+        /// The seconds each frame is shown for, the second half of which is spent moving to the
+        /// next
+        #[arg(long = "frame-duration", value_name = "SECONDS", default_value_t = 2.0)]
+        frame_duration: f64,
+
+        /// Overwrite the output file if it already exists
+        #[arg(long = "force", short = 'f')]
+        force: bool,
+
+        #[command(flatten)]
+        options: Box<OptionArgs>,
+    },
+    /// Convert the charts of a file to the chart definitions of another tool
+    Export {
+        /// The format to convert the charts to
+        #[arg(long = "format", value_name = "FORMAT", required = true)]
+        format: ExportFormat,
+
+        /// The chart file and an optional output file
+        #[arg(value_name = "FILES", num_args = 0..=2)]
+        files: Vec<PathBuf>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(long = "force", short = 'f')]
+        force: bool,
+
+        #[command(flatten)]
+        options: Box<OptionArgs>,
+    },
+}
+
+/// An input file and the output file to render it to, where `None` is standard input or output
+type Job = (Option<PathBuf>, Option<PathBuf>);
+
+/// The size of a grid of charts and the top left corner of each chart in it
+type GridLayout = ((f64, f64), Vec<(f64, f64)>);
+
+impl RenderArgs {
+    fn get_jobs(&self) -> Result<Vec<Job>, PieChartError> {
+        if let Some(ref out_dir) = self.out_dir {
+            if self.files.is_empty() {
+                return Err(PieChartError::Usage(
+                    "No input files were given for '--out-dir'".to_string(),
+                ));
+            }
+
+            // Checking writes nothing, so the directory is only made when rendering
+            if !self.check {
+                std::fs::create_dir_all(out_dir).map_err(PieChartError::io(format!(
+                    "Unable to create directory '{}'",
+                    out_dir.to_string_lossy()
+                )))?;
+            }
+
+            return Ok(self
+                .files
+                .iter()
+                .map(|input| {
+                    let mut name = input.file_stem().unwrap_or(input.as_os_str()).to_owned();
+
+                    name.push(".");
+                    name.push(self.format.unwrap_or_default().extension());
+                    (Some(input.clone()), Some(out_dir.join(name)))
+                })
+                .collect());
+        }
+
+        if self.sqlite.is_some() {
+            return match self.files.as_slice() {
+                [] => Ok(vec![(None, None)]),
+                [output] => Ok(vec![(None, Some(output.clone()))]),
+                _ => Err(PieChartError::Usage(
+                    "Only an output file can be given with '--sqlite'".to_string(),
+                )),
+            };
+        }
+
+        match self.files.as_slice() {
+            [] => Ok(vec![(None, None)]),
+            [input] if self.auto_out => {
+                let output = input.with_extension(self.format.unwrap_or_default().extension());
+
+                if output == *input {
+                    return Err(PieChartError::Usage(format!(
+                        "'{}' would be overwritten by its own output",
+                        input.to_string_lossy()
+                    )));
+                }
+
+                Ok(vec![(Some(input.clone()), Some(output))])
+            }
+            [input] => Ok(vec![(Some(input.clone()), None)]),
+            [input, output] => Ok(vec![(Some(input.clone()), Some(output.clone()))]),
+            _ => Err(PieChartError::Usage(
+                "Use '--out-dir' to render more than one input file".to_string(),
+            )),
+        }
+    }
+}
+
+impl InputArgs {
+    /// Where the items are in CSV files and workbooks
+    fn table(self: &Self) -> input::Table<'_> {
+        input::Table {
+            sheet: self.sheet.as_deref(),
+            key_column: self.key_column.as_deref(),
+            value_column: self.value_column.as_deref(),
+        }
+    }
 }
 
 impl Cli {
-    fn get_output(&self) -> Result<Box<dyn Write>, Box<dyn Error>> {
-        match self.output_file {
-            Some(ref path) => File::create(path)
-                .context(format!(
-                    "Unable to create file '{}'",
+    /// Create the output file, or use standard output. A file that already exists is only
+    /// overwritten with `force`, so that hand edited output is not lost by mistake.
+    fn get_output(path: Option<&Path>, force: bool) -> Result<Box<dyn Write>, PieChartError> {
+        let Some(path) = path else {
+            return Ok(Box::new(io::stdout()));
+        };
+        let file = if force {
+            File::create(path)
+        } else {
+            File::create_new(path)
+        };
+
+        match file {
+            Ok(file) => Ok(Box::new(file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                Err(PieChartError::Usage(format!(
+                    "'{}' already exists, so use '--force' to overwrite it",
                     path.to_string_lossy()
-                ))
-                .map(|f| Box::new(f) as Box<dyn Write>)
-                .map_err(|e| Box::new(e) as Box<dyn Error>),
-            None => Ok(Box::new(io::stdout())),
+                )))
+            }
+            Err(e) => Err(PieChartError::io(format!(
+                "Unable to create '{}'",
+                path.to_string_lossy()
+            ))(e)),
         }
     }
 
-    fn get_input(&self) -> Result<Box<dyn Read>, Box<dyn Error>> {
-        match self.input_file {
-            Some(ref path) => File::open(path)
-                .context(format!("Unable to open file '{}'", path.to_string_lossy()))
+    /// Write the rendered chart to the file, or to standard output
+    fn write_output(path: Option<&Path>, bytes: &[u8], force: bool) -> Result<(), PieChartError> {
+        let name = path.map_or("standard output".to_string(), |path| {
+            format!("'{}'", path.to_string_lossy())
+        });
+
+        Self::get_output(path, force)?
+            .write_all(bytes)
+            .map_err(PieChartError::io(format!("Unable to write {}", name)))
+    }
+
+    fn get_input(path: Option<&Path>) -> Result<Box<dyn Read>, PieChartError> {
+        match path {
+            Some(path) => File::open(path)
                 .map(|f| Box::new(f) as Box<dyn Read>)
-                .map_err(|e| Box::new(e) as Box<dyn Error>),
+                .map_err(PieChartError::io(format!(
+                    "Unable to open file '{}'",
+                    path.to_string_lossy()
+                ))),
             None => Ok(Box::new(io::stdin())),
         }
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct ChartData {
-    pub title: String,
-    pub items: Vec<ItemData>,
+/// The text drawn on top of each wedge
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum WedgeLabels {
+    /// No labels
+    #[default]
+    None,
+    /// The item key
+    Key,
+    /// The item percentage
+    Percent,
+    /// The item key and percentage
+    Both,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct ItemData {
-    pub key: String,
-    pub value: f64,
+/// Where the wedge labels are drawn
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LabelPosition {
+    /// On the wedge
+    #[default]
+    Inside,
+    /// Beside the pie, with a leader line back to the wedge
+    Outside,
 }
 
-#[derive(Debug)]
-struct Gutter {
-    left: f64,
-    top: f64,
-    right: f64,
-    bottom: f64,
+/// The order in which the wedges are drawn around the pie, from the start angle
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortOrder {
+    /// Largest value first
+    ValueDesc,
+    /// Smallest value first
+    ValueAsc,
+    /// Alphabetically by key
+    Key,
+    /// The order of the input file
+    #[default]
+    None,
 }
 
-impl Gutter {
-    pub fn height(&self) -> f64 {
-        self.bottom + self.top
-    }
+/// What to do with items that have negative values
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum NegativeValues {
+    /// Fail to render the chart
+    #[default]
+    Error,
+    /// Leave the items out
+    Skip,
+    /// Use the size of the values, without their sign
+    Abs,
+}
 
-    pub fn width(&self) -> f64 {
-        self.right + self.left
-    }
+/// What to do with items that have a value of zero
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ZeroValues {
+    /// Leave the items out, including from the legend
+    Skip,
+    /// Keep the items in the legend, with no wedge
+    #[default]
+    Keep,
 }
 
-#[derive(Debug)]
-struct WedgeData {
-    title: String,
-    percentage: f64,
+/// Where the chart title is drawn
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum TitlePosition {
+    /// Above the pie
+    #[default]
+    Top,
+    /// Below the legend
+    Bottom,
+    /// Not drawn, with the space for it removed
+    Hidden,
 }
 
-#[derive(Debug)]
-struct RenderData {
-    title: String,
-    gutter: Gutter,
-    pie_diameter: f64,
-    styles: Vec<String>,
-    legend_gutter: Gutter,
-    legend_height: f64,
-    legend_rect_corner_radius: f64,
-    wedges: Vec<WedgeData>,
+/// Font and placement of the chart title
+#[derive(Args, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TitleStyle {
+    /// Font family of the title
+    #[arg(long = "title-font", id = "title_font_family", value_name = "FAMILY")]
+    pub font_family: Option<String>,
+
+    /// Font size of the title
+    #[arg(long = "title-size", id = "title_font_size", value_name = "SIZE")]
+    pub font_size: Option<f64>,
+
+    /// Font weight of the title, such as `bold` or `600`
+    #[arg(long = "title-weight", id = "title_font_weight", value_name = "WEIGHT")]
+    pub font_weight: Option<String>,
+
+    /// Color of the title
+    #[arg(long = "title-color", id = "title_color", value_name = "COLOR")]
+    pub color: Option<String>,
+
+    /// Position of the title
+    #[arg(
+        long = "title-position",
+        id = "title_position",
+        value_name = "POSITION"
+    )]
+    pub position: Option<TitlePosition>,
 }
 
-impl<'a> PieChartTool<'a> {
-    pub fn new(log: &'a dyn PieChartLog) -> PieChartTool {
-        PieChartTool { log }
+impl TitleStyle {
+    /// Returns this style with any unset values taken from `other`
+    pub fn or(self: &Self, other: &TitleStyle) -> TitleStyle {
+        TitleStyle {
+            font_family: self.font_family.clone().or(other.font_family.clone()),
+            font_size: self.font_size.or(other.font_size),
+            font_weight: self.font_weight.clone().or(other.font_weight.clone()),
+            color: self.color.clone().or(other.color.clone()),
+            position: self.position.or(other.position),
+        }
     }
+}
 
-    pub fn run(
-        self: &mut Self,
-        args: impl IntoIterator<Item = std::ffi::OsString>,
-    ) -> Result<(), Box<dyn Error>> {
-        let cli = match Cli::try_parse_from(args) {
-            Ok(m) => m,
-            Err(err) => {
-                output!(self.log, "{}", err.to_string());
-                return Ok(());
-            }
-        };
+/// The values shown after each key in the legend
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LegendValues {
+    /// The item percentage
+    #[default]
+    Percent,
+    /// The item value
+    Value,
+    /// The item value and percentage
+    Both,
+    /// Only the key
+    None,
+}
 
-        let chart_data = Self::read_chart_file(cli.get_input()?)?;
-        let render_data = self.process_chart_data(&chart_data)?;
-        let document = self.render_chart(&render_data)?;
+/// Whether wedges are filled with patterns, so that they can be told apart when printed in
+/// black and white
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum PatternMode {
+    /// Colors only
+    #[default]
+    None,
+    /// Patterns drawn over the colors
+    Overlay,
+    /// Black patterns on white instead of colors
+    Only,
+}
 
-        Self::write_svg_file(cli.get_output()?, &document)?;
+/// Chart options, given either on the command line or in the chart file
+#[derive(Args, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChartOptions {
+    /// The font family of the legend, labels and title
+    #[arg(long = "font", value_name = "FAMILY")]
+    pub font_family: Option<String>,
 
-        Ok(())
-    }
+    /// A WOFF2, WOFF, TrueType or OpenType font file to embed in the SVG. It is named after the
+    /// first `--font` family, or after the file if none is given.
+    #[arg(long = "embed-font", value_name = "FONT_FILE")]
+    pub embed_font: Option<PathBuf>,
 
-    fn read_chart_file(mut reader: Box<dyn Read>) -> Result<ChartData, Box<dyn Error>> {
-        let mut content = String::new();
+    /// Render the chart as a donut
+    #[arg(long = "donut", num_args = 0, default_missing_value = "true")]
+    pub donut: Option<bool>,
 
-        reader.read_to_string(&mut content)?;
+    /// Render the chart as a semicircle gauge, with the flat edge at the bottom
+    #[arg(long = "half", num_args = 0, default_missing_value = "true")]
+    pub half: Option<bool>,
 
-        let chart_data: ChartData = json5::from_str(&content)?;
+    /// Angle the first wedge starts at, in degrees clockwise from 3 o'clock, so -90 is the top.
+    /// Semicircles always start at the flat edge.
+    #[arg(
+        long = "start-angle",
+        value_name = "DEGREES",
+        allow_negative_numbers = true
+    )]
+    pub start_angle: Option<f64>,
 
-        Ok(chart_data)
-    }
+    /// Draw the wedges counterclockwise from the start angle
+    #[arg(
+        long = "counterclockwise",
+        num_args = 0,
+        default_missing_value = "true"
+    )]
+    pub counterclockwise: Option<bool>,
 
-    fn write_svg_file(writer: Box<dyn Write>, document: &Document) -> Result<(), Box<dyn Error>> {
-        svg::write(writer, document)?;
+    /// Draw the children of items as rings around them
+    #[arg(long = "sunburst", num_args = 0, default_missing_value = "true")]
+    pub sunburst: Option<bool>,
 
-        Ok(())
-    }
+    /// Give every wedge the same angle, with a radius proportional to its value
+    #[arg(long = "rose", num_args = 0, default_missing_value = "true")]
+    pub rose: Option<bool>,
 
-    fn hsv_to_rgb(h: f32, s: f32, v: f32) -> u32 {
-        let h_i = (h * 6.0) as usize;
-        let f = h * 6.0 - h_i as f32;
-        let p = v * (1.0 - s);
-        let q = v * (1.0 - f * s);
-        let t = v * (1.0 - (1.0 - f) * s);
+    /// Draw the children of the item with this key, or the items grouped into the other item, as
+    /// a smaller pie beside the pie
+    #[arg(long = "breakout", value_name = "KEY")]
+    pub breakout: Option<String>,
 
-        fn rgb(r: f32, g: f32, b: f32) -> u32 {
-            ((r * 256.0) as u32) << 16 | ((g * 256.0) as u32) << 8 | ((b * 256.0) as u32)
-        }
+    /// Draw the items as a grid of 10 by 10 squares in place of the pie, each of which is 1% of
+    /// the total
+    #[arg(long = "waffle", num_args = 0, default_missing_value = "true")]
+    pub waffle: Option<bool>,
 
-        if h_i == 0 {
-            rgb(v, t, p)
-        } else if h_i == 1 {
-            rgb(q, v, p)
-        } else if h_i == 2 {
-            rgb(p, v, t)
-        } else if h_i == 3 {
-            rgb(p, q, v)
-        } else if h_i == 4 {
-            rgb(t, p, v)
-        } else {
-            rgb(v, p, q)
-        }
-    }
+    /// Draw the items as horizontal bars in place of the pie, as long as their values
+    #[arg(long = "as-bars", num_args = 0, default_missing_value = "true")]
+    pub as_bars: Option<bool>,
 
-    fn process_chart_data(self: &Self, cd: &ChartData) -> Result<RenderData, Box<dyn Error>> {
-        // Generate random resource colors based on https://martin.ankerl.com/2009/12/09/how-to-create-random-colors-programmatically/
-        let mut rng = rand::thread_rng();
-        let mut h: f32 = rng.gen();
-        let mut wedges = vec![];
-        let mut styles = vec![
-            ".labels{fill:rgb(0,0,0);font-size:10;font-family:Arial}".to_string(),
-            ".title{font-family:Arial;font-size:12;text-anchor:middle;}".to_string(),
-            ".legend{font-family:Arial;font-size:12pt;text-anchor:left;}".to_string(),
-        ];
-        let total: f64 = cd.items.iter().fold(0.0, |acc, item| acc + item.value);
+    /// Draw the items as rectangles filling a square in place of the pie, with areas in
+    /// proportion to their values
+    #[arg(long = "treemap", num_args = 0, default_missing_value = "true")]
+    pub treemap: Option<bool>,
 
-        for tuple in cd.items.iter().enumerate() {
-            let (index, item) = tuple;
-            let rgb = Self::hsv_to_rgb(h, 0.5, 0.5);
+    /// Inner radius of a donut chart as a fraction of the outer radius
+    #[arg(long = "hole-ratio", value_name = "RATIO")]
+    pub hole_ratio: Option<f64>,
 
-            styles.push(format!(
-                ".wedge-{}{{fill:#{1:06x};stroke-width:0}}",
-                index, rgb,
-            ));
+    /// Text drawn in the hole of a donut, in which `{total}` is the total value and `{largest}`
+    /// the percentage of the largest item
+    #[arg(long = "center-text", value_name = "TEXT")]
+    pub center_text: Option<String>,
 
-            wedges.push(WedgeData {
-                title: item.key.to_string(),
-                percentage: item.value / total,
-            });
+    /// Seed for the random wedge colors
+    #[arg(long = "seed", value_name = "SEED")]
+    pub seed: Option<u64>,
 
-            h = (h + GOLDEN_RATIO_CONJUGATE) % 1.0;
-        }
+    /// Render the same output on every run, for comparing with saved output in tests, by seeding
+    /// the random colors and rounding coordinates unless `--seed` and `--precision` are given
+    #[arg(long = "deterministic", num_args = 0, default_missing_value = "true")]
+    pub deterministic: Option<bool>,
 
-        let pie_diameter = 400.0;
-        let gutter = Gutter {
-            top: 40.0,
-            bottom: 40.0,
-            left: 40.0,
-            right: 40.0,
-        };
-        let legend_height = 20.0;
-        let legend_gutter = Gutter {
-            top: 10.0,
-            bottom: 10.0,
-            left: 10.0,
-            right: 10.0,
-        };
+    /// Diameter of the pie in pixels
+    #[arg(long = "size", value_name = "PIXELS")]
+    pub size: Option<f64>,
 
-        Ok(RenderData {
-            title: cd.title.to_string(),
-            gutter,
-            pie_diameter,
-            legend_gutter,
-            legend_height,
-            legend_rect_corner_radius: 3.0,
-            styles,
-            wedges,
-        })
-    }
+    /// Gutter around the chart in pixels
+    #[arg(long = "gutter", value_name = "PIXELS")]
+    pub gutter: Option<f64>,
 
-    fn render_chart(self: &Self, rd: &RenderData) -> Result<Document, Box<dyn Error>> {
-        let width = rd.gutter.left + rd.pie_diameter + rd.gutter.right;
-        let height = rd.gutter.top
-            + rd.pie_diameter
-            + rd.legend_gutter.height()
-            + rd.legend_height
-            + rd.gutter.bottom;
-        let radius = rd.pie_diameter / 2.0;
-        let x_center = rd.gutter.left + radius;
-        let y_center = rd.gutter.bottom + radius;
-        let mut document = Document::new()
-            .set("xmlns", "http://www.w3.org/2000/svg")
-            .set("width", width)
-            .set("height", height)
-            .set("viewBox", format!("0 0 {} {}", width, height))
-            .set("style", "background-color: white;");
-        let style = element::Style::new(rd.styles.join("\n"));
-        let mut a = -90f64.to_radians();
-        let mut pie = element::Group::new();
+    /// Height of the legend color blocks in pixels
+    #[arg(long = "legend-height", value_name = "PIXELS")]
+    pub legend_height: Option<f64>,
 
-        for (index, wedge) in rd.wedges.iter().enumerate() {
-            let b = a + (wedge.percentage * 360.0).to_radians();
+    /// Palette for the wedge colors
+    #[arg(long = "palette", value_name = "PALETTE")]
+    pub palette: Option<Palette>,
 
-            pie.append(
-                element::Path::new()
-                    .set("class", format!("wedge-{}", index))
-                    .set(
-                        "d",
-                        Data::new()
-                            .move_to((x_center, y_center))
-                            .line_to((x_center + radius * a.cos(), y_center + radius * a.sin()))
-                            .elliptical_arc_to((
-                                radius,
-                                radius,
-                                0.0,
-                                if wedge.percentage > 0.5 { 1.0 } else { 0.0 },
-                                1.0,
-                                x_center + radius * b.cos(),
-                                y_center + radius * b.sin(),
-                            ))
-                            .close(),
-                    ),
-            );
+    /// The color space that random wedge colors are generated in
+    #[arg(long = "color-space", value_name = "SPACE")]
+    pub color_space: Option<ColorSpace>,
 
-            a = b;
-        }
+    /// Saturation of random HSV wedge colors, from gray at 0 to fully saturated at 1
+    #[arg(long = "saturation", value_name = "FRACTION")]
+    pub saturation: Option<f64>,
 
-        let title = element::Text::new(format!("{}", &rd.title))
-            .set("class", "title")
-            .set("x", width / 2.0)
-            .set("y", rd.gutter.top / 2.0);
+    /// Brightness of random HSV wedge colors, from black at 0 to brightest at 1
+    #[arg(long = "brightness", value_name = "FRACTION")]
+    pub brightness: Option<f64>,
 
-        let mut legend = element::Group::new();
-        let text_width = (width - rd.legend_gutter.width()) / (rd.wedges.len() as f64);
+    /// Background, text and wedge outline colors, and the default palette
+    #[arg(long = "theme", value_name = "THEME")]
+    pub theme: Option<Theme>,
 
-        for i in 0..rd.wedges.len() {
-            let wedge = &rd.wedges[i];
-            let y = rd.gutter.top + rd.pie_diameter;
-            let block = element::Rectangle::new()
-                .set("class", format!("wedge-{}", i))
-                .set("x", rd.legend_gutter.left + (i as f64) * text_width)
-                .set("y", y + rd.legend_gutter.top)
-                .set("rx", rd.legend_rect_corner_radius)
-                .set("ry", rd.legend_rect_corner_radius)
-                .set("width", rd.legend_height)
-                .set("height", rd.legend_height);
+    /// Background color in place of the theme's, or `transparent` for none
+    #[arg(long = "background", value_name = "COLOR")]
+    pub background: Option<String>,
 
-            legend.append(block);
+    /// Draw the background as a rectangle, for programs that ignore the style of the SVG element
+    #[arg(long = "background-rect", num_args = 0, default_missing_value = "true")]
+    pub background_rect: Option<bool>,
 
-            let text = element::Text::new(format!(
-                "{} ({:.0}%)",
-                &wedge.title,
-                wedge.percentage * 100f64
-            ))
-            .set("class", "legend")
-            .set("x", rd.legend_gutter.left + (i as f64) * text_width)
-            .set("y", y + rd.legend_gutter.top + rd.legend_height * 2.0);
+    /// Text to draw on each wedge
+    #[arg(long = "labels", value_name = "LABELS")]
+    pub labels: Option<WedgeLabels>,
 
-            legend.append(text);
-        }
+    /// The text of each wedge label, instead of that chosen by `--labels`, in which `{key}`,
+    /// `{value}` and `{percent}` are replaced by the key, value and percentage number
+    #[arg(long = "label-format", value_name = "FORMAT")]
+    pub label_format: Option<String>,
 
-        document.append(style);
-        document.append(pie);
-        document.append(title);
-        document.append(legend);
+    /// Where to draw the labels
+    #[arg(long = "label-position", value_name = "POSITION")]
+    pub label_position: Option<LabelPosition>,
 
-        Ok(document)
-    }
-}
+    /// Group items below this percentage into a single wedge
+    #[arg(long = "min-percent", value_name = "PERCENT")]
+    pub min_percent: Option<f64>,
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Key for the wedge of grouped small items
+    #[arg(long = "other-label", value_name = "LABEL")]
+    pub other_label: Option<String>,
+
+    /// What to do with items that have negative values
+    #[arg(long = "negatives", value_name = "POLICY")]
+    pub negatives: Option<NegativeValues>,
+
+    /// What to do with items that have a value of zero
+    #[arg(long = "zeros", value_name = "POLICY")]
+    pub zeros: Option<ZeroValues>,
+
+    /// Take the values as percentages, which must add up to 100, rather than shares of their total
+    #[arg(long = "percent-input", num_args = 0, default_missing_value = "true")]
+    pub percentages: Option<bool>,
+
+    /// Keep only this many of the largest items
+    #[arg(long = "top", value_name = "COUNT")]
+    pub top: Option<usize>,
+
+    /// Group the items left out by `--top` into a single wedge, keyed by `--other-label`
+    #[arg(
+        long = "top-other",
+        requires = "top",
+        num_args = 0,
+        default_missing_value = "true"
+    )]
+    pub top_other: Option<bool>,
+
+    /// Add together the items that share a key, instead of drawing a wedge for each
+    #[arg(
+        long = "merge-duplicates",
+        num_args = 0,
+        default_missing_value = "true"
+    )]
+    pub merge_duplicates: Option<bool>,
+
+    /// Order of the wedges
+    #[arg(long = "sort", value_name = "ORDER")]
+    pub sort: Option<SortOrder>,
+
+    /// Order of the legend entries, instead of the order of the wedges. Each entry keeps the
+    /// color of its wedge.
+    #[arg(long = "legend-sort", value_name = "ORDER")]
+    pub legend_sort: Option<SortOrder>,
+
+    /// Values shown after each key in the legend
+    #[arg(long = "legend-values", value_name = "VALUES")]
+    pub legend_values: Option<LegendValues>,
+
+    /// The text of each legend entry, instead of that chosen by `--legend-values`, with the same
+    /// placeholders as `--label-format`, such as `{key}: {value} ({percent}%)`
+    #[arg(long = "legend-format", value_name = "FORMAT")]
+    pub legend_format: Option<String>,
+
+    /// Decimal places for values in the legend and labels
+    #[arg(long = "decimals", value_name = "DIGITS")]
+    pub decimals: Option<usize>,
+
+    /// Decimal places for percentages in the legend and labels
+    #[arg(long = "percent-decimals", value_name = "DIGITS")]
+    pub percent_decimals: Option<usize>,
+
+    /// Separate the thousands in values
+    #[arg(
+        long = "thousands-separator",
+        num_args = 0,
+        default_missing_value = "true"
+    )]
+    pub thousands_separator: Option<bool>,
+
+    /// Abbreviate large values with SI prefixes such as 1.2k or 3.4M
+    #[arg(long = "si-prefix", num_args = 0, default_missing_value = "true")]
+    pub si_prefix: Option<bool>,
+
+    /// Units of the values, such as `GB`, `req/s` or `€`, shown with the values in the legend
+    /// and the center text. Currency symbols are placed as the locale places them.
+    #[arg(long = "units", value_name = "UNITS")]
+    pub units: Option<String>,
+
+    /// Locale for number formatting and for built-in text such as `Other`, such as `de` or `fr-FR`.
+    /// Charts in Hebrew or Arabic are laid out right to left.
+    #[arg(long = "locale", value_name = "LOCALE")]
+    pub locale: Option<String>,
+
+    #[command(flatten)]
+    #[serde(default)]
+    pub title_style: TitleStyle,
+
+    /// Style sheet file added to the generated styles
+    #[arg(long = "css", value_name = "CSS_FILE")]
+    pub css: Option<PathBuf>,
+
+    /// Use only the given styles instead of the generated ones
+    #[arg(long = "replace-styles", num_args = 0, default_missing_value = "true")]
+    pub replace_styles: Option<bool>,
+
+    /// The color of the line drawn around each wedge, by default the background color
+    #[arg(long = "stroke-color", value_name = "COLOR")]
+    pub stroke_color: Option<String>,
+
+    /// The width of the line drawn around each wedge
+    #[arg(long = "stroke-width", value_name = "WIDTH")]
+    pub stroke_width: Option<f64>,
+
+    /// Animate the wedges growing in when the chart is shown
+    #[arg(long = "animate", num_args = 0, default_missing_value = "true")]
+    pub animate: Option<bool>,
+
+    /// Fill wedges with hatch and dot patterns
+    #[arg(long = "pattern", value_name = "MODE")]
+    pub pattern: Option<PatternMode>,
+
+    /// Warn when adjacent wedges would be hard to tell apart with a color vision deficiency
+    #[arg(
+        long = "check-colorblind",
+        num_args = 0,
+        default_missing_value = "true"
+    )]
+    pub check_colorblind: Option<bool>,
+
+    /// Truncate legend labels longer than this many characters with an ellipsis, instead of
+    /// wrapping them to fit
+    #[arg(long = "max-label-len", value_name = "CHARS")]
+    pub max_label_len: Option<usize>,
+
+    /// Number of legend entries in each row, instead of as many as fit
+    #[arg(long = "legend-columns", value_name = "COLUMNS")]
+    pub legend_columns: Option<usize>,
+
+    /// Leave out the legend
+    #[arg(long = "no-legend", num_args = 0, default_missing_value = "true")]
+    pub no_legend: Option<bool>,
+
+    /// Decimal places for coordinates in the SVG, instead of full precision
+    #[arg(long = "precision", value_name = "DIGITS")]
+    pub precision: Option<usize>,
+
+    /// Remove the line breaks between elements of the SVG
+    #[arg(long = "minify", num_args = 0, default_missing_value = "true")]
+    pub minify: Option<bool>,
+
+    /// Indent the elements of the SVG by how deeply they are nested, for reviewing changes
+    #[arg(long = "pretty", num_args = 0, default_missing_value = "true")]
+    pub pretty: Option<bool>,
+
+    /// Leave out the width and height of the SVG so that it scales to fit where it is placed,
+    /// keeping its aspect ratio
+    #[arg(long = "responsive", num_args = 0, default_missing_value = "true")]
+    pub responsive: Option<bool>,
+
+    /// Embed the chart data in a `<metadata>` element, so that the SVG can be read back as a
+    /// chart file
+    #[arg(long = "embed-data", num_args = 0, default_missing_value = "true")]
+    pub embed_data: Option<bool>,
+
+    /// Number of columns when a file holds several charts
+    #[arg(long = "columns", value_name = "COLUMNS")]
+    pub columns: Option<usize>,
+}
+
+impl ChartOptions {
+    /// Returns these options with any unset values taken from `other`
+    pub fn or(self: &Self, other: &ChartOptions) -> ChartOptions {
+        ChartOptions {
+            font_family: self.font_family.clone().or(other.font_family.clone()),
+            embed_font: self.embed_font.clone().or(other.embed_font.clone()),
+            donut: self.donut.or(other.donut),
+            half: self.half.or(other.half),
+            start_angle: self.start_angle.or(other.start_angle),
+            counterclockwise: self.counterclockwise.or(other.counterclockwise),
+            sunburst: self.sunburst.or(other.sunburst),
+            rose: self.rose.or(other.rose),
+            breakout: self.breakout.clone().or(other.breakout.clone()),
+            waffle: self.waffle.or(other.waffle),
+            as_bars: self.as_bars.or(other.as_bars),
+            treemap: self.treemap.or(other.treemap),
+            hole_ratio: self.hole_ratio.or(other.hole_ratio),
+            center_text: self.center_text.clone().or(other.center_text.clone()),
+            seed: self.seed.or(other.seed),
+            deterministic: self.deterministic.or(other.deterministic),
+            size: self.size.or(other.size),
+            gutter: self.gutter.or(other.gutter),
+            legend_height: self.legend_height.or(other.legend_height),
+            palette: self.palette.or(other.palette),
+            color_space: self.color_space.or(other.color_space),
+            saturation: self.saturation.or(other.saturation),
+            brightness: self.brightness.or(other.brightness),
+            theme: self.theme.or(other.theme),
+            background: self.background.clone().or(other.background.clone()),
+            background_rect: self.background_rect.or(other.background_rect),
+            labels: self.labels.or(other.labels),
+            label_format: self.label_format.clone().or(other.label_format.clone()),
+            label_position: self.label_position.or(other.label_position),
+            min_percent: self.min_percent.or(other.min_percent),
+            other_label: self.other_label.clone().or(other.other_label.clone()),
+            merge_duplicates: self.merge_duplicates.or(other.merge_duplicates),
+            negatives: self.negatives.or(other.negatives),
+            zeros: self.zeros.or(other.zeros),
+            percentages: self.percentages.or(other.percentages),
+            top: self.top.or(other.top),
+            top_other: self.top_other.or(other.top_other),
+            sort: self.sort.or(other.sort),
+            legend_sort: self.legend_sort.or(other.legend_sort),
+            legend_values: self.legend_values.or(other.legend_values),
+            legend_format: self.legend_format.clone().or(other.legend_format.clone()),
+            decimals: self.decimals.or(other.decimals),
+            percent_decimals: self.percent_decimals.or(other.percent_decimals),
+            thousands_separator: self.thousands_separator.or(other.thousands_separator),
+            si_prefix: self.si_prefix.or(other.si_prefix),
+            units: self.units.clone().or(other.units.clone()),
+            locale: self.locale.clone().or(other.locale.clone()),
+            title_style: self.title_style.or(&other.title_style),
+            css: self.css.clone().or(other.css.clone()),
+            replace_styles: self.replace_styles.or(other.replace_styles),
+            stroke_color: self.stroke_color.clone().or(other.stroke_color.clone()),
+            stroke_width: self.stroke_width.or(other.stroke_width),
+            animate: self.animate.or(other.animate),
+            pattern: self.pattern.or(other.pattern),
+            check_colorblind: self.check_colorblind.or(other.check_colorblind),
+            max_label_len: self.max_label_len.or(other.max_label_len),
+            legend_columns: self.legend_columns.or(other.legend_columns),
+            no_legend: self.no_legend.or(other.no_legend),
+            precision: self.precision.or(other.precision),
+            minify: self.minify.or(other.minify),
+            pretty: self.pretty.or(other.pretty),
+            responsive: self.responsive.or(other.responsive),
+            embed_data: self.embed_data.or(other.embed_data),
+            columns: self.columns.or(other.columns),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ChartData {
+    /// Broken onto several lines at each `\n`
+    pub title: String,
+    /// Drawn below the title in a smaller font, and also broken onto several lines at each `\n`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    /// Drawn below the legend in a smaller font, such as the source of the data. Also broken onto
+    /// several lines at each `\n`.
+    #[serde(alias = "source", skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    /// Left empty for charts of series, which have items of their own
+    #[serde(default)]
+    pub items: Vec<ItemData>,
+    /// Several sets of items drawn as rings around each other, the first innermost, with the
+    /// wedges of each key in the same color
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub series: Vec<SeriesData>,
+    /// Inner radius of a donut chart as a fraction of the outer radius. Kept for older chart
+    /// files, as `hole_ratio` in `options` takes precedence.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hole_ratio: Option<f64>,
+    /// Seed for the random wedge colors, for reproducible output. Kept for older chart files, as
+    /// `seed` in `options` takes precedence.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Chart options, overridden by any given on the command line and overriding any in the
+    /// config file
+    #[serde(default)]
+    pub options: ChartOptions,
+    /// CSS rules added to the generated styles
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub styles: Vec<String>,
+}
+
+impl ChartData {
+    /// Start building chart data in code
+    pub fn builder() -> ChartDataBuilder {
+        ChartDataBuilder::new()
+    }
+
+    /// Check that the chart has items, or series that each have items, and that their values
+    /// and colors are usable
+    pub fn validate(self: &Self) -> Result<(), PieChartError> {
+        if self.series.is_empty() {
+            return Self::validate_chart_items(&self.items);
+        }
+
+        if !self.items.is_empty() {
+            return Err(PieChartError::Validation(
+                "Charts with series take their items from them, so cannot have items too"
+                    .to_string(),
+            ));
+        }
+
+        for series in &self.series {
+            Self::validate_chart_items(&series.items)?;
+        }
+
+        Ok(())
+    }
+
+    fn validate_chart_items(items: &[ItemData]) -> Result<(), PieChartError> {
+        if items.is_empty() {
+            return Err(PieChartError::NoItems);
+        }
+
+        Self::validate_items(items)?;
+
+        if items.iter().all(|item| item.total() == 0.0) {
+            return Err(PieChartError::ZeroTotal);
+        }
+
+        Ok(())
+    }
+
+    fn validate_items(items: &[ItemData]) -> Result<(), PieChartError> {
+        for item in items.iter() {
+            if !item.value.is_finite() {
+                return Err(PieChartError::NonFiniteValue {
+                    key: item.key.to_string(),
+                    value: item.value,
+                });
+            }
+
+            if item.value < 0.0 {
+                return Err(PieChartError::NegativeValue {
+                    key: item.key.to_string(),
+                    value: item.value,
+                });
+            }
+
+            if let Some(ref color) = item.color {
+                if color::Color::parse(color).is_err() {
+                    return Err(PieChartError::InvalidColor {
+                        key: item.key.to_string(),
+                        color: color.to_string(),
+                    });
+                }
+            }
+
+            // Charts are often embedded in pages, where script links would run, so only web and
+            // mail links are allowed. Browsers ignore whitespace and control characters in links,
+            // so they are ignored here too.
+            if let Some(ref href) = item.href {
+                let href: String = href
+                    .chars()
+                    .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+                    .collect::<String>()
+                    .to_ascii_lowercase();
+                let scheme = href
+                    .split_once(':')
+                    .map(|(scheme, _)| scheme)
+                    .filter(|scheme| !scheme.contains(['/', '?', '#']));
+
+                if !matches!(scheme, None | Some("http" | "https" | "mailto")) {
+                    return Err(PieChartError::Validation(format!(
+                        "Item '{}' has a link that is not a web or mail link, which is not allowed",
+                        item.key
+                    )));
+                }
+            }
+
+            // Classes are written into the styles as selectors
+            if let Some(ref class) = item.class {
+                if class.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+                    || !class
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    return Err(PieChartError::Validation(format!(
+                        "Item '{}' has an invalid class '{}', which should be letters, digits, '-' and '_'",
+                        item.key, class
+                    )));
+                }
+
+                if BUILT_IN_CLASSES.contains(&class.as_str()) {
+                    return Err(PieChartError::Validation(format!(
+                        "Item '{}' has the class '{}', which is used by the chart itself, so choose another",
+                        item.key, class
+                    )));
+                }
+            }
+
+            Self::validate_items(&item.children)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// One set of items of a chart of several, such as the values of one year
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SeriesData {
+    /// Shown with the values of the series when hovering over its wedges
+    pub name: String,
+    pub items: Vec<ItemData>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ItemData {
+    pub key: String,
+    /// Ignored for items with children, which take the total of theirs
+    #[serde(default)]
+    pub value: f64,
+    /// Wedge color as hex, `rgb()`, `hsl()` or a named CSS color
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Link followed when the wedge or its legend entry is clicked, either `http:`, `https:`,
+    /// `mailto:` or relative
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+    /// CSS class of the wedge and its legend block instead of the generated `wedge-N`, for
+    /// styling them from a stylesheet of your own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    /// Items drawn in a ring outside this one by a sunburst chart
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ItemData>,
+}
+
+impl ItemData {
+    pub fn new(key: impl Into<String>, value: f64) -> ItemData {
+        ItemData {
+            key: key.into(),
+            value,
+            color: None,
+            href: None,
+            class: None,
+            children: vec![],
+        }
+    }
+
+    /// The value of the item, or the total of its children if it has any
+    pub fn total(self: &Self) -> f64 {
+        if self.children.is_empty() {
+            self.value
+        } else {
+            self.children.iter().map(ItemData::total).sum()
+        }
+    }
+
+    /// Replace the value of each item with children by their total, at every level
+    fn with_totals(self: &Self) -> ItemData {
+        let children: Vec<ItemData> = self.children.iter().map(ItemData::with_totals).collect();
+
+        ItemData {
+            value: self.total(),
+            children,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Gutter {
+    left: f64,
+    top: f64,
+    right: f64,
+    bottom: f64,
+}
+
+impl Gutter {
+    pub fn height(&self) -> f64 {
+        self.bottom + self.top
+    }
+
+    pub fn width(&self) -> f64 {
+        self.right + self.left
+    }
+}
+
+/// The colors of the pattern filling a wedge
+#[derive(Debug)]
+struct WedgePattern {
+    background: String,
+    foreground: String,
+}
+
+#[derive(Debug)]
+struct WedgeData {
+    key: String,
+    value: f64,
+    legend: String,
+    /// The legend wrapped or truncated to fit its entry
+    legend_lines: Vec<String>,
+    color: String,
+    percentage: f64,
+    label: Option<String>,
+    label_inverse: bool,
+    pattern: Option<WedgePattern>,
+    href: Option<String>,
+    /// The class of the wedge and its legend block, such as `wedge-0` or `wedge-0-1` for a
+    /// sunburst ring
+    class: String,
+    /// The wedges of the next sunburst ring out
+    children: Vec<WedgeData>,
+}
+
+#[derive(Debug)]
+struct RenderData {
+    title: String,
+    subtitle: Option<String>,
+    subtitle_font_size: f64,
+    caption: Option<String>,
+    /// The background color, which may be `transparent`
+    background: String,
+    background_rect: bool,
+    title_position: TitlePosition,
+    label_position: LabelPosition,
+    title_gutter: f64,
+    title_font_size: f64,
+    title_font_weight: Option<String>,
+    title_color: String,
+    text_color: String,
+    wedge_stroke: Option<(String, f64)>,
+    gutter: Gutter,
+    pie_diameter: f64,
+    half: bool,
+    /// Measures text in the font the chart is drawn in
+    metrics: text::Metrics,
+    /// The angle the first wedge starts at, in radians, for full charts
+    start_angle: f64,
+    counterclockwise: bool,
+    rose: bool,
+    hole_ratio: f64,
+    center_text: Option<String>,
+    styles: Vec<String>,
+    legend_gutter: Gutter,
+    legend_height: f64,
+    legend_rect_corner_radius: f64,
+    legend_columns: usize,
+    /// Whether the legend is drawn
+    legend: bool,
+    wedges: Vec<WedgeData>,
+    /// The index of the wedge of each legend entry, in the order they are listed
+    legend_order: Vec<usize>,
+    /// The number of sunburst rings outside the wedges
+    rings: usize,
+    /// Prefixed to element ids, to keep them unique when several charts share a document
+    id_prefix: String,
+    strings: Strings,
+    /// Whether the chart reads right to left, with the legend and caption on the right
+    rtl: bool,
+    /// The name of each series of a chart of several, the first of which has the wedges
+    series: Vec<String>,
+    /// The wedges of each series after the first, each drawn as a ring outside the one before
+    series_wedges: Vec<Vec<WedgeData>>,
+    /// How the wedges move through the frames of an animated chart
+    animation: Option<animation::Animation>,
+    breakout: Option<Breakout>,
+    /// Whether the wedges are drawn as the squares of a waffle chart in place of the pie
+    waffle: bool,
+    /// Whether the wedges are drawn as the bars of a bar chart in place of the pie
+    bars: bool,
+    /// Whether the wedges are drawn as the rectangles of a treemap in place of the pie
+    treemap: bool,
+}
+
+/// A wedge drawn again as a smaller pie of its children, to the right of the pie
+#[derive(Debug)]
+struct Breakout {
+    /// The index of the wedge that is broken out, which is turned to face the breakout pie
+    index: usize,
+    /// The children of the wedge, with their percentages of it
+    wedges: Vec<WedgeData>,
+}
+
+impl WedgeData {
+    /// The key, value and percentage at full precision, shown when hovering over the wedge
+    fn tooltip(self: &Self) -> String {
+        format!(
+            "{}: {} ({}%)",
+            self.key,
+            self.value,
+            self.percentage * 100.0
+        )
+    }
+
+    /// The number of sunburst rings outside this wedge
+    fn depth(self: &Self) -> usize {
+        self.children
+            .iter()
+            .map(|child| child.depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl RenderData {
+    /// The width and height of the rendered chart
+    fn size(self: &Self) -> (f64, f64) {
+        let width = self.gutter.left + self.pie_diameter + self.gutter.right;
+        let height = self.gutter.top
+            + self.pie_height()
+            + self.legend_gutter.height()
+            + self.legend_layout().1
+            + self
+                .caption_lines()
+                .last()
+                .map_or(0.0, |(_, y)| y - self.caption_top())
+            + self.gutter.bottom;
+
+        (width, height)
+    }
+
+    /// The top of the space below the legend
+    fn caption_top(self: &Self) -> f64 {
+        self.gutter.top + self.pie_height() + self.legend_gutter.height() + self.legend_layout().1
+    }
+
+    /// The lines of the caption with their baselines, starting clear of the last legend labels,
+    /// which hang below their color blocks
+    fn caption_lines(self: &Self) -> Vec<(&str, f64)> {
+        let top = self.caption_top() + self.legend_height + CAPTION_FONT_SIZE;
+
+        self.caption
+            .iter()
+            .flat_map(|caption| caption.split('\n'))
+            .enumerate()
+            .map(|(index, line)| (line, top + index as f64 * CAPTION_LINE_HEIGHT))
+            .collect()
+    }
+
+    /// The lines of the title and then of the subtitle, with their baselines and whether they are
+    /// subtitle lines. The lines are centered together in the title gutter.
+    fn title_lines(self: &Self) -> Vec<(&str, f64, bool)> {
+        let (_, height) = self.size();
+        let middle = match self.title_position {
+            TitlePosition::Top => self.title_gutter / 2.0,
+            TitlePosition::Bottom => height - self.title_gutter / 2.0,
+            TitlePosition::Hidden => return vec![],
+        };
+        let mut lines = vec![];
+        let mut y = 0.0;
+
+        for (index, line) in self.title.split('\n').enumerate() {
+            if index > 0 {
+                y += self.title_font_size * TITLE_LINE_SPACING;
+            }
+
+            lines.push((line, y, false));
+        }
+
+        for line in self
+            .subtitle
+            .iter()
+            .flat_map(|subtitle| subtitle.split('\n'))
+        {
+            y += self.subtitle_font_size * TITLE_LINE_SPACING;
+            lines.push((line, y, true));
+        }
+
+        lines
+            .into_iter()
+            .map(|(line, line_y, is_subtitle)| (line, middle - y / 2.0 + line_y, is_subtitle))
+            .collect()
+    }
+
+    /// The wedges of each ring with its level, which are those of the pie and of each series
+    /// after the first
+    fn rings(self: &Self) -> impl Iterator<Item = (usize, &[WedgeData])> {
+        std::iter::once(self.wedges.as_slice())
+            .chain(self.series_wedges.iter().map(Vec::as_slice))
+            .enumerate()
+    }
+
+    /// The tooltip of a wedge, after the name of its series if it has one
+    fn wedge_tooltip(self: &Self, wedge: &WedgeData, level: usize) -> String {
+        match self.series.get(level) {
+            Some(name) => format!("{}, {}", name, wedge.tooltip()),
+            None => wedge.tooltip(),
+        }
+    }
+
+    /// The center of the pie
+    fn center(self: &Self) -> (f64, f64) {
+        let radius = self.pie_diameter / 2.0;
+
+        (self.gutter.left + radius, self.gutter.top + radius)
+    }
+
+    /// The center and the inner and outer radius of the breakout pie, which is beside the pie on
+    /// the right
+    fn breakout_circle(self: &Self) -> ((f64, f64), f64, f64) {
+        let (x_center, y_center) = self.center();
+        let radius = self.pie_diameter / 2.0 * BREAKOUT_RATIO;
+
+        (
+            (
+                x_center + self.pie_diameter / 2.0 + BREAKOUT_GAP + radius,
+                y_center,
+            ),
+            radius * self.hole_ratio,
+            radius,
+        )
+    }
+
+    /// The wedges of the breakout pie with the angles they start and end at, starting at the top
+    fn breakout_wedges(self: &Self) -> Vec<(&WedgeData, f64, f64)> {
+        let (_, sweep) = self.angles();
+        let mut a = -FRAC_PI_2;
+
+        self.breakout
+            .iter()
+            .flat_map(|breakout| &breakout.wedges)
+            .map(|wedge| {
+                let b = a + wedge.percentage * sweep;
+                let angles = (wedge, a, b);
+
+                a = b;
+                angles
+            })
+            .collect()
+    }
+
+    /// The lines from the edges of the broken out wedge to the top and bottom of the breakout pie.
+    /// Wedges of more than half the pie are joined from its top and bottom instead.
+    fn connectors(self: &Self) -> Vec<((f64, f64), (f64, f64))> {
+        let Some(ref breakout) = self.breakout else {
+            return vec![];
+        };
+        let (x_center, y_center) = self.center();
+        let radius = self.pie_diameter / 2.0;
+        let ((x, y), _, breakout_radius) = self.breakout_circle();
+        let half_span = (self.span(&self.wedges[breakout.index]).abs() / 2.0).min(FRAC_PI_2);
+
+        [-1.0, 1.0]
+            .into_iter()
+            .map(|side| {
+                (
+                    (
+                        x_center + radius * half_span.cos(),
+                        y_center + side * radius * half_span.sin(),
+                    ),
+                    (x, y + side * breakout_radius),
+                )
+            })
+            .collect()
+    }
+
+    /// The wedge that each square of a waffle chart is part of, with the top left corner of the
+    /// square, in rows from the top left. Each wedge has a square for each of its percentages,
+    /// rounded so that they add up to 100.
+    fn waffle_cells(self: &Self) -> Vec<(usize, f64, f64)> {
+        let percentages: Vec<f64> = self.wedges.iter().map(|wedge| wedge.percentage).collect();
+        let spacing = self.pie_diameter / WAFFLE_SIZE as f64;
+        let left = self.gutter.left;
+        let top = self.gutter.top;
+
+        NumberFormatter::default()
+            .round_percentages(&percentages)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(index, fraction)| {
+                std::iter::repeat_n(index, (fraction * 100.0).round() as usize)
+            })
+            .enumerate()
+            .map(|(cell, index)| {
+                (
+                    index,
+                    left + (cell % WAFFLE_SIZE) as f64 * spacing,
+                    top + (cell / WAFFLE_SIZE) as f64 * spacing,
+                )
+            })
+            .collect()
+    }
+
+    /// The width and height of each square of a waffle chart
+    fn waffle_cell_size(self: &Self) -> f64 {
+        self.pie_diameter / WAFFLE_SIZE as f64 * (1.0 - WAFFLE_GAP)
+    }
+
+    /// The bar of each wedge of a bar chart, as the index of the wedge and the left, top, width
+    /// and height of the bar. The bars are in rows down the middle of the space of the pie, with
+    /// the largest as long as leaves room for the widest label after it.
+    fn bars(self: &Self) -> Vec<(usize, f64, f64, f64, f64)> {
+        let spacing = (self.pie_diameter / self.wedges.len().max(1) as f64).min(MAX_BAR_SPACING);
+        let top = self.gutter.top + (self.pie_diameter - spacing * self.wedges.len() as f64) / 2.0;
+        let largest = self
+            .wedges
+            .iter()
+            .map(|wedge| wedge.percentage)
+            .fold(0.0, f64::max);
+        let widest = self
+            .wedges
+            .iter()
+            .filter_map(|wedge| wedge.label.as_deref())
+            .map(|label| callout::TEXT_GAP + self.metrics.width(label, LABEL_FONT_SIZE))
+            .fold(0.0, f64::max);
+        let length = (self.pie_diameter - widest).max(self.pie_diameter / 2.0);
+
+        self.wedges
+            .iter()
+            .enumerate()
+            .filter(|(_, wedge)| wedge.percentage > 0.0)
+            .map(|(index, wedge)| {
+                (
+                    index,
+                    self.gutter.left,
+                    top + (index as f64 + BAR_GAP / 2.0) * spacing,
+                    length * wedge.percentage / largest,
+                    spacing * (1.0 - BAR_GAP),
+                )
+            })
+            .collect()
+    }
+
+    /// The rectangle of each wedge of a treemap, as the index of the wedge and the left, top,
+    /// width and height of the rectangle. The rectangles fill the space of the pie from the
+    /// largest at the top left, in rows or columns across the shorter side of the space that is
+    /// left, which take as many rectangles as keeps them closest to square.
+    fn treemap(self: &Self) -> Vec<(usize, f64, f64, f64, f64)> {
+        let mut areas: Vec<(usize, f64)> = self
+            .wedges
+            .iter()
+            .enumerate()
+            .filter(|(_, wedge)| wedge.percentage > 0.0)
+            .map(|(index, wedge)| (index, wedge.percentage))
+            .collect();
+        let total: f64 = areas.iter().map(|(_, area)| area).sum();
+        let (mut x, mut y) = (self.gutter.left, self.gutter.top);
+        let (mut width, mut height) = (self.pie_diameter, self.pie_diameter);
+        let mut rects = vec![];
+
+        for (_, area) in areas.iter_mut() {
+            *area *= width * height / total;
+        }
+
+        areas.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        // The largest ratio of the longer side to the shorter of the rectangles of a row
+        let worst = |row: &[(usize, f64)], side: f64| {
+            let sum: f64 = row.iter().map(|(_, area)| area).sum();
+
+            row.iter()
+                .map(|(_, area)| {
+                    (side * side * area / (sum * sum)).max(sum * sum / (side * side * area))
+                })
+                .fold(0.0, f64::max)
+        };
+        let mut rest = areas.as_slice();
+
+        while !rest.is_empty() {
+            let side = width.min(height);
+            let mut count = 1;
+
+            while count < rest.len() && worst(&rest[..=count], side) <= worst(&rest[..count], side)
+            {
+                count += 1;
+            }
+
+            let (row, next) = rest.split_at(count);
+            let thickness = row.iter().map(|(_, area)| area).sum::<f64>() / side;
+            let mut offset = 0.0;
+
+            for &(index, area) in row {
+                let length = area / thickness;
+
+                rects.push(if width >= height {
+                    (index, x, y + offset, thickness, length)
+                } else {
+                    (index, x + offset, y, length, thickness)
+                });
+                offset += length;
+            }
+
+            if width >= height {
+                x += thickness;
+                width -= thickness;
+            } else {
+                y += thickness;
+                height -= thickness;
+            }
+
+            rest = next;
+        }
+
+        rects
+    }
+
+    /// Whether a label fits inside the rectangle of a treemap, with a little room on either side
+    fn fits_in(self: &Self, label: &str, width: f64, height: f64) -> bool {
+        self.metrics.width(label, LABEL_FONT_SIZE) + 2.0 * TREEMAP_PADDING <= width
+            && LABEL_FONT_SIZE <= height
+    }
+
+    /// The rectangle of each wedge of a bar chart or treemap, as given by `bars` or `treemap`
+    fn rects(self: &Self) -> Vec<(usize, f64, f64, f64, f64)> {
+        if self.bars {
+            self.bars()
+        } else if self.treemap {
+            self.treemap()
+        } else {
+            vec![]
+        }
+    }
+
+    /// The inner and outer radius of a sunburst ring, with the wedges at level 0. The rings share
+    /// the space outside the hole equally.
+    fn ring_radii(self: &Self, level: usize) -> (f64, f64) {
+        let radius = self.pie_diameter / 2.0;
+        let inner_radius = radius * self.hole_ratio;
+        let ring_width = (radius - inner_radius) / (self.rings + 1) as f64;
+
+        (
+            inner_radius + ring_width * level as f64,
+            inner_radius + ring_width * (level + 1) as f64,
+        )
+    }
+
+    /// The height of the pie, which is only the top half for a semicircle
+    fn pie_height(self: &Self) -> f64 {
+        if self.half {
+            self.pie_diameter / 2.0
+        } else {
+            self.pie_diameter
+        }
+    }
+
+    /// The angle the first wedge starts at and the angle all the wedges span, in radians, which
+    /// is negative when they are drawn counterclockwise. Semicircles start on the left, or on the
+    /// right when counterclockwise, so that they span the top.
+    fn angles(self: &Self) -> (f64, f64) {
+        match (self.half, self.counterclockwise) {
+            (true, false) => (-PI, PI),
+            (true, true) => (0.0, -PI),
+            (false, false) => (self.start_angle, 2.0 * PI),
+            (false, true) => (self.start_angle, -2.0 * PI),
+        }
+    }
+
+    /// The angle spanned by the wedge, which is the same for every wedge of a rose chart
+    fn span(self: &Self, wedge: &WedgeData) -> f64 {
+        let (_, sweep) = self.angles();
+
+        if self.rose {
+            sweep / self.wedges.len() as f64
+        } else {
+            wedge.percentage * sweep
+        }
+    }
+
+    /// The inner and outer radius of a wedge in a sunburst ring. The wedges of a rose chart only
+    /// reach the outside of the ring if they have the largest value.
+    fn wedge_radii(self: &Self, wedge: &WedgeData, level: usize) -> (f64, f64) {
+        let (inner_radius, radius) = self.ring_radii(level);
+
+        if self.rose {
+            let largest = self
+                .wedges
+                .iter()
+                .map(|wedge| wedge.value)
+                .fold(0.0, f64::max);
+
+            (
+                inner_radius,
+                inner_radius + (radius - inner_radius) * wedge.value / largest,
+            )
+        } else {
+            (inner_radius, radius)
+        }
+    }
+
+    /// The labels drawn beside the pie, with the index of their wedge
+    fn callouts(self: &Self) -> Vec<(usize, callout::Callout)> {
+        if self.label_position != LabelPosition::Outside {
+            return vec![];
+        }
+
+        let (start, _) = self.angles();
+        let mut a = start;
+        let mut indexes = vec![];
+        let mut wedges = vec![];
+
+        for (index, wedge) in self.wedges.iter().enumerate() {
+            let b = a + self.span(wedge);
+
+            if wedge.label.is_some() && wedge.percentage > 0.0 {
+                indexes.push(index);
+                wedges.push(((a + b) / 2.0, self.wedge_radii(wedge, 0).1));
+            }
+
+            a = b;
+        }
+
+        let (x_center, y_center) = self.center();
+        let radius = self.pie_diameter / 2.0;
+        let callouts = callout::layout(
+            (x_center, y_center),
+            radius,
+            &wedges,
+            CALLOUT_SPACING,
+            y_center - radius,
+            if self.half {
+                y_center
+            } else {
+                y_center + radius
+            },
+        );
+
+        indexes.into_iter().zip(callouts).collect()
+    }
+
+    /// Scope the class selectors and references of the styles to the element with the given id,
+    /// so that several charts can share a document
+    fn scope(self: &mut Self, id: &str) {
+        for style in self.styles.iter_mut() {
+            if style.starts_with('.') {
+                *style = format!("#{} {}", id, style);
+            }
+
+            *style = style.replace("url(#", &format!("url(#{}-", id));
+        }
+
+        self.id_prefix = format!("{}-", id);
+    }
+
+    fn has_background(self: &Self) -> bool {
+        self.background != "transparent"
+    }
+
+    /// Whether the wedge covers the whole of a full chart
+    fn is_whole(self: &Self, wedge: &WedgeData) -> bool {
+        !self.half
+            && if self.rose {
+                self.wedges.len() == 1
+            } else {
+                wedge.percentage >= FULL_PERCENTAGE
+            }
+    }
+
+    /// The offset of each legend row from the top of the legend, and the height of the legend.
+    /// Rows grow to fit their labels wrapped onto more than one line.
+    fn legend_layout(self: &Self) -> (Vec<f64>, f64) {
+        let mut offsets = vec![];
+        let mut offset = 0.0;
+        let mut height = 0.0;
+
+        if !self.legend {
+            return (offsets, height);
+        }
+
+        for row in self.legend_wedges().chunks(self.legend_columns) {
+            let lines = row
+                .iter()
+                .map(|wedge| wedge.legend_lines.len())
+                .max()
+                .unwrap_or(1);
+            let extra_height = lines.saturating_sub(1) as f64 * LEGEND_LINE_HEIGHT;
+
+            offsets.push(offset);
+            height = offset + self.legend_height + extra_height;
+            offset += self.legend_height * 2.0 + extra_height + LEGEND_GUTTER;
+        }
+
+        (offsets, height)
+    }
+
+    /// The wedges in the order of the legend
+    fn legend_wedges(self: &Self) -> Vec<&WedgeData> {
+        self.legend_order
+            .iter()
+            .map(|&index| &self.wedges[index])
+            .collect()
+    }
+
+    /// The top left corner of the color block of each legend entry, in the order of the legend.
+    /// Right to left legends are mirrored, with the first entry on the right.
+    fn legend_entries(self: &Self) -> Vec<(f64, f64)> {
+        if !self.legend {
+            return vec![];
+        }
+
+        let (offsets, _) = self.legend_layout();
+        let (width, _) = self.size();
+        let entry_width = (width - self.legend_gutter.width()) / self.legend_columns as f64;
+        let top = self.gutter.top + self.pie_height() + self.legend_gutter.top;
+
+        (0..self.wedges.len())
+            .map(|index| {
+                let x =
+                    self.legend_gutter.left + (index % self.legend_columns) as f64 * entry_width;
+
+                (
+                    if self.rtl {
+                        width - x - self.legend_height
+                    } else {
+                        x
+                    },
+                    top + offsets[index / self.legend_columns],
+                )
+            })
+            .collect()
+    }
+}
+
+impl<'a> PieChartTool<'a> {
+    pub fn new(log: &'a dyn PieChartLog) -> PieChartTool<'a> {
+        PieChartTool {
+            log: VerboseLog {
+                log,
+                verbosity: 0,
+                format: LogFormat::Text,
+                file: RefCell::new(None),
+            },
+        }
+    }
+
+    /// The log, at the verbosity and in the format that the command line asked for
+    pub fn log(self: &Self) -> &dyn PieChartLog {
+        &self.log
+    }
+
+    pub fn run(
+        self: &mut Self,
+        args: impl IntoIterator<Item = std::ffi::OsString>,
+    ) -> Result<(), PieChartError> {
+        let cli = match Cli::try_parse_from(args) {
+            Ok(m) => m,
+            // Help and version are asked for, so they are output rather than errors
+            Err(err) if !err.use_stderr() => {
+                output!(self.log, "{}", err.to_string());
+                return Ok(());
+            }
+            Err(err) => {
+                let message = err.to_string();
+
+                return Err(PieChartError::Usage(
+                    message
+                        .strip_prefix("error: ")
+                        .unwrap_or(&message)
+                        .trim_end()
+                        .to_string(),
+                ));
+            }
+        };
+
+        self.log.verbosity = cli.verbose;
+        self.log.format = cli.log_format;
+
+        // The schema does not depend on the config file, so it is output even if that is broken
+        let config = || config::load(cli.config.as_deref());
+
+        match cli.command {
+            None => self.render_files(&cli.render, &config()?),
+            Some(CliCommand::Render(ref args)) => self.render_files(args, &config()?),
+            Some(CliCommand::Validate {
+                ref files,
+                ref input,
+            }) => self.validate(files, input),
+            Some(CliCommand::Convert {
+                ref files,
+                to,
+                force,
+                ref input,
+            }) => self.convert_file(files, to, force, input),
+            Some(CliCommand::Schema) => {
+                output!(
+                    self.log,
+                    "{}",
+                    serde_json::to_string_pretty(&schema::schema()).unwrap()
+                );
+                Ok(())
+            }
+            Some(CliCommand::Palette { count, ref options }) => {
+                for color in self.palette_colors(count, &options.or(&config()?))? {
+                    output!(self.log, "{}", color);
+                }
+
+                Ok(())
+            }
+            Some(CliCommand::Serve {
+                ref address,
+                ref options,
+            }) => self.serve(address, options, &config()?),
+            Some(CliCommand::Diff {
+                ref files,
+                force,
+                ref options,
+            }) => self.diff(&cli.render, &config()?, files, force, options),
+            Some(CliCommand::Export {
+                format,
+                ref files,
+                force,
+                ref options,
+            }) => self.export(&cli.render, &config()?, format, files, force, options),
+            Some(CliCommand::Animate {
+                ref files,
+                ref output,
+                format,
+                frame_duration,
+                force,
+                ref options,
+            }) => {
+                let frames = self.read_frames(&cli.render, &config()?, files)?;
+                let format = match format {
+                    Some(format) => format,
+                    None if OutputFormat::detect(output.as_deref()) == OutputFormat::Svg => {
+                        AnimationFormat::detect(output.as_deref())
+                    }
+                    None => {
+                        return Err(PieChartError::Usage(
+                            "Only SVG, GIF and APNG output can be rendered by 'animate'"
+                                .to_string(),
+                        ))
+                    }
+                };
+                let bytes = self.render_animation_as(&frames, options, frame_duration, format)?;
+
+                Cli::write_output(output.as_deref(), &bytes, force)
+            }
+        }
+    }
+
+    /// Render a chart to SVG text with options set in code rather than given as command line
+    /// arguments. Options not set in `options` are taken from the `options` block of the chart
+    /// data. No config file is read, and only SVG is rendered, so other output formats, reports and
+    /// summaries are made with their own methods, such as `render_pdf`, `report` and `summary`.
+    pub fn run_with_options(
+        self: &Self,
+        chart_data: ChartData,
+        options: ChartOptions,
+    ) -> Result<String, PieChartError> {
+        self.svg_text(&[chart_data], &options)
+    }
+
+    /// The SVG text of one chart, or of several in a grid
+    pub(crate) fn svg_text(
+        self: &Self,
+        charts: &[ChartData],
+        options: &ChartOptions,
+    ) -> Result<String, PieChartError> {
+        let document = match charts {
+            [chart_data] => self.render(chart_data, options)?,
+            charts => self.render_grid(charts, options)?,
+        };
+
+        Ok(Self::serialize(
+            &document,
+            &charts
+                .first()
+                .map_or(options.clone(), |chart| options.or(&chart.options)),
+        ))
+    }
+
+    /// Render the input files as the command line asks, to files, templates or the terminal, or
+    /// check them or watch them for changes instead
+    fn render_files(
+        self: &Self,
+        cli: &RenderArgs,
+        config: &ChartOptions,
+    ) -> Result<(), PieChartError> {
+        if let Some(ref template) = cli.out_template {
+            self.log.set_file(cli.files.first().map(PathBuf::as_path));
+
+            return match cli.files.as_slice() {
+                [] => self.render_lines(cli, config, io::stdin().lock(), template),
+                [input] => self.render_lines(
+                    cli,
+                    config,
+                    io::BufReader::new(Cli::get_input(Some(input))?),
+                    template,
+                ),
+                _ => Err(PieChartError::Usage(
+                    "Only an input file can be given with '--out-template'".to_string(),
+                )),
+            };
+        }
+
+        if cli.compare {
+            return self.compare(cli, config);
+        }
+
+        let jobs = cli.get_jobs()?;
+
+        if cli.open && jobs.iter().any(|(_, output)| output.is_none()) {
+            return Err(PieChartError::Usage(
+                "Give an output file to open with '--open'".to_string(),
+            ));
+        }
+
+        // The table would be mixed in with charts written to standard output, unless they are
+        // drawn for the terminal too
+        if cli.summary
+            && jobs.iter().any(|(_, output)| {
+                output.is_none()
+                    && !matches!(
+                        cli.format,
+                        Some(OutputFormat::Term | OutputFormat::Sixel | OutputFormat::Kitty)
+                    )
+            })
+        {
+            return Err(PieChartError::Usage(
+                "Give an output file to print a summary alongside with '--summary'".to_string(),
+            ));
+        }
+
+        if cli.watch {
+            return self.watch(cli, config, &jobs);
+        }
+
+        if cli.check {
+            for (input, _) in jobs {
+                self.check_file(cli, config, input.as_deref())?;
+            }
+
+            return Ok(());
+        }
+
+        for (input, output) in jobs {
+            self.render_file(cli, config, input.as_deref(), output.as_deref())
+                .inspect_err(|_| {
+                    // Say which of the many input files failed, keeping the error as it is
+                    if let (Some(_), Some(input)) = (&cli.out_dir, &input) {
+                        error!(self.log, "Unable to render '{}'", input.to_string_lossy());
+                    }
+                })?;
+
+            if let (true, Some(output)) = (cli.open, &output) {
+                Self::open(output)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Open a file in the default viewer for its type, without waiting for the viewer to close
+    fn open(path: &Path) -> Result<(), PieChartError> {
+        Self::open_command(path)
+            .spawn()
+            .map(|_| ())
+            .map_err(PieChartError::io(format!(
+                "Unable to open '{}'",
+                path.to_string_lossy()
+            )))
+    }
+
+    /// The command that opens a file in the default viewer of this platform
+    fn open_command(path: &Path) -> process::Command {
+        let mut command = if cfg!(target_os = "macos") {
+            process::Command::new("open")
+        } else if cfg!(windows) {
+            // The empty title stops `start` taking a quoted path as the window title
+            let mut command = process::Command::new("cmd");
+
+            command.args(["/C", "start", ""]);
+            command
+        } else {
+            process::Command::new("xdg-open")
+        };
+
+        command.arg(path);
+        command
+    }
+
+    /// Render the first charts of two input files side by side into one SVG
+    fn compare(self: &Self, cli: &RenderArgs, config: &ChartOptions) -> Result<(), PieChartError> {
+        let (inputs, output) = match cli.files.as_slice() {
+            [first, second] => ([first, second], None),
+            [first, second, output] => ([first, second], Some(output.as_path())),
+            _ => {
+                return Err(PieChartError::Usage(
+                    "Give two input files and an optional output file with '--compare'".to_string(),
+                ))
+            }
+        };
+
+        if cli.format.unwrap_or_else(|| OutputFormat::detect(output)) != OutputFormat::Svg {
+            return Err(PieChartError::Usage(
+                "Only SVG output can be rendered with '--compare'".to_string(),
+            ));
+        }
+
+        if cli.open && output.is_none() {
+            return Err(PieChartError::Usage(
+                "Give an output file to open with '--open'".to_string(),
+            ));
+        }
+
+        let charts = [
+            self.read_first_chart(cli, config, inputs[0])?,
+            self.read_first_chart(cli, config, inputs[1])?,
+        ];
+        let document = self.render_comparison(&charts, &cli.options)?;
+        let svg = Self::serialize(&document, &cli.options.or(&charts[0].options));
+
+        Cli::write_output(output, svg.as_bytes(), cli.force)?;
+
+        match output {
+            Some(output) if cli.open => Self::open(output),
+            _ => Ok(()),
+        }
+    }
+
+    /// Every chart of the input files in order, or of standard input if there are none
+    fn read_frames(
+        self: &Self,
+        cli: &RenderArgs,
+        config: &ChartOptions,
+        files: &[PathBuf],
+    ) -> Result<Vec<ChartData>, PieChartError> {
+        if files.is_empty() {
+            return Self::read_charts(cli, config, None);
+        }
+
+        let mut frames = vec![];
+
+        for file in files {
+            self.log.set_file(Some(file));
+            frames.extend(Self::read_charts(cli, config, Some(file))?);
+        }
+
+        Ok(frames)
+    }
+
+    /// Render the change from the first chart of one file to that of another into an SVG
+    fn diff(
+        self: &Self,
+        cli: &RenderArgs,
+        config: &ChartOptions,
+        files: &[PathBuf],
+        force: bool,
+        options: &ChartOptions,
+    ) -> Result<(), PieChartError> {
+        let output = files.get(2).map(PathBuf::as_path);
+
+        if OutputFormat::detect(output) != OutputFormat::Svg {
+            return Err(PieChartError::Usage(
+                "Only SVG output can be rendered by 'diff'".to_string(),
+            ));
+        }
+
+        let before = self.read_first_chart(cli, config, &files[0])?;
+        let after = self.read_first_chart(cli, config, &files[1])?;
+        let document = self.render_diff(&before, &after, options)?;
+        let svg = Self::serialize(&document, &options.or(&after.options));
+
+        Cli::write_output(output, svg.as_bytes(), force)
+    }
+
+    /// Convert the charts of an input file to another chart file format
+    fn convert_file(
+        self: &Self,
+        files: &[PathBuf],
+        to: Option<InputFormat>,
+        force: bool,
+        cli: &InputArgs,
+    ) -> Result<(), PieChartError> {
+        let (input, output) = (
+            files.first().map(PathBuf::as_path),
+            files.get(1).map(PathBuf::as_path),
+        );
+
+        self.log.set_file(input);
+
+        let charts = Self::read_chart_file(Cli::get_input(input)?, input, cli)?;
+        let format = to.unwrap_or_else(|| match output {
+            Some(output) => InputFormat::detect(Some(output), ""),
+            None => InputFormat::Json5,
+        });
+        let text = self.convert(&charts, format)?;
+
+        Cli::write_output(output, text.as_bytes(), force)
+    }
+
+    /// Convert the charts of an input file to another tool's format
+    fn export(
+        self: &Self,
+        cli: &RenderArgs,
+        config: &ChartOptions,
+        format: ExportFormat,
+        files: &[PathBuf],
+        force: bool,
+        options: &ChartOptions,
+    ) -> Result<(), PieChartError> {
+        let input = files.first().map(PathBuf::as_path);
+
+        self.log.set_file(input);
+
+        let charts = Self::read_charts(cli, config, input)?;
+        let spec = match format {
+            ExportFormat::VegaLite => self.vega_lite_spec(&charts, options)?,
+        };
+        let json = serde_json::to_string_pretty(&spec).unwrap() + "\n";
+
+        Cli::write_output(files.get(1).map(PathBuf::as_path), json.as_bytes(), force)
+    }
+
+    /// Read the first chart of an input file, for modes that render charts from several files
+    fn read_first_chart(
+        self: &Self,
+        cli: &RenderArgs,
+        config: &ChartOptions,
+        input: &Path,
+    ) -> Result<ChartData, PieChartError> {
+        self.log.set_file(Some(input));
+
+        Self::read_charts(cli, config, Some(input))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                PieChartError::Validation(format!("'{}' has no charts", input.to_string_lossy()))
+            })
+    }
+
+    /// Check the input files against the schema, logging every place that does not match
+    fn validate(self: &Self, files: &[PathBuf], cli: &InputArgs) -> Result<(), PieChartError> {
+        let inputs: Vec<Option<&Path>> = match files {
+            [] => vec![None],
+            files => files.iter().map(|file| Some(file.as_path())).collect(),
+        };
+        let mut invalid = 0;
+
+        for &input in &inputs {
+            let name = input.map_or("standard input".to_string(), |input| {
+                format!("'{}'", input.to_string_lossy())
+            });
+            let mut content = vec![];
+
+            self.log.set_file(input);
+
+            Cli::get_input(input)?
+                .read_to_end(&mut content)
+                .map_err(PieChartError::io(format!("Unable to read {}", name)))?;
+
+            let errors = schema::validate(&input::read_value(
+                &content,
+                input,
+                cli.input_format,
+                &cli.table(),
+            )?);
+
+            if errors.is_empty() {
+                output!(self.log, "{} is valid", name);
+            } else {
+                invalid += 1;
+
+                for message in errors {
+                    error!(self.log, "{} {}", name, message);
+                }
+            }
+        }
+
+        match invalid {
+            0 => Ok(()),
+            _ => Err(PieChartError::Validation(format!(
+                "{} of {} chart files do not match the schema",
+                invalid,
+                inputs.len()
+            ))),
+        }
+    }
+
+    /// Render the jobs, then poll the input files for changes and render them again, reporting
+    /// errors instead of stopping. This never returns unless the jobs cannot be watched.
+    fn watch(
+        self: &Self,
+        cli: &RenderArgs,
+        config: &ChartOptions,
+        jobs: &[Job],
+    ) -> Result<(), PieChartError> {
+        if jobs
+            .iter()
+            .any(|(input, output)| input.is_none() || output.is_none())
+        {
+            return Err(PieChartError::Usage(
+                "Watching needs input and output files, not standard input or output".to_string(),
+            ));
+        }
+
+        if let Some((_, Some(output))) = jobs
+            .iter()
+            .find(|(_, output)| !cli.force && output.as_ref().is_some_and(|o| o.exists()))
+        {
+            return Err(PieChartError::Usage(format!(
+                "'{}' already exists, so use '--force' to overwrite it",
+                output.to_string_lossy()
+            )));
+        }
+
+        let mut last_modified = vec![None; jobs.len()];
+
+        loop {
+            self.render_modified(cli, config, jobs, &mut last_modified);
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+    }
+
+    /// Render each job whose input has changed since the time in `last_modified`, logging rather
+    /// than returning errors so that watching carries on
+    fn render_modified(
+        self: &Self,
+        cli: &RenderArgs,
+        config: &ChartOptions,
+        jobs: &[Job],
+        last_modified: &mut [Option<std::time::SystemTime>],
+    ) {
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        for ((input, output), last_modified) in jobs.iter().zip(last_modified.iter_mut()) {
+            let (input, output) = (input.as_deref().unwrap(), output.as_deref().unwrap());
+            let time = modified(input);
+
+            if time.is_none() || time == *last_modified {
+                continue;
+            }
+
+            *last_modified = time;
+
+            match self.render_file(cli, config, Some(input), Some(output)) {
+                Ok(()) => output!(
+                    self.log,
+                    "Rendered '{}' to '{}'",
+                    input.to_string_lossy(),
+                    output.to_string_lossy()
+                ),
+                Err(e) => error!(self.log, "{}: {}", input.to_string_lossy(), e),
+            }
+        }
+    }
+
+    /// Render each line of JSON as it is read to a file named by the template, so that any number
+    /// of charts can be streamed through. Blank lines are skipped.
+    fn render_lines(
+        self: &Self,
+        cli: &RenderArgs,
+        config: &ChartOptions,
+        reader: impl BufRead,
+        template: &str,
+    ) -> Result<(), PieChartError> {
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(PieChartError::io("Unable to read the input"))?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let n = index + 1;
+            let mut chart_data: ChartData =
+                serde_json::from_str(&line).map_err(|e| PieChartError::Parse {
+                    message: e
+                        .to_string()
+                        .rsplit_once(" at line ")
+                        .map_or(e.to_string(), |(message, _)| message.to_string()),
+                    line: Some(n),
+                    column: Some(e.column()),
+                })?;
+
+            chart_data.options = chart_data.options.or(config);
+
+            // Titles can hold anything, so only the characters that are safe in file names are kept
+            let title: String = chart_data
+                .title
+                .chars()
+                .map(|c| match c {
+                    c if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' => c,
+                    _ => '_',
+                })
+                .collect();
+            let output = PathBuf::from(
+                template
+                    .replace("{n}", &n.to_string())
+                    .replace("{title}", &title),
+            );
+
+            if let Some(parent) = output.parent().filter(|parent| !parent.exists()) {
+                fs::create_dir_all(parent).map_err(PieChartError::io(format!(
+                    "Unable to create directory '{}'",
+                    parent.to_string_lossy()
+                )))?;
+            }
+
+            self.write_charts(cli, &[chart_data], Some(&output))
+                .inspect_err(|_| error!(self.log, "Unable to render the chart on line {}", n))?;
+        }
+
+        Ok(())
+    }
+
+    /// Render an input file, with the command line options taking precedence over those in the
+    /// chart file, which in turn take precedence over those in the config file
+    fn render_file(
+        self: &Self,
+        cli: &RenderArgs,
+        config: &ChartOptions,
+        input: Option<&Path>,
+        output: Option<&Path>,
+    ) -> Result<(), PieChartError> {
+        self.log.set_file(input);
+
+        let charts = Self::read_charts(cli, config, input)?;
+
+        self.write_charts(cli, &charts, output)?;
+
+        if let Some(ref report) = cli.report {
+            let json = serde_json::to_string_pretty(&self.report(&charts, &cli.options)?).unwrap();
+
+            Cli::write_output(
+                Some(report),
+                (json + "\n").as_bytes(),
+                cli.force || cli.watch,
+            )?;
+        }
+
+        if cli.summary {
+            for chart_data in &charts {
+                for line in self.summary(chart_data, &cli.options)? {
+                    output!(self.log, "{}", line);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read the charts from the input file or database, with the config file options below
+    /// those in the chart file
+    fn read_charts(
+        cli: &RenderArgs,
+        config: &ChartOptions,
+        input: Option<&Path>,
+    ) -> Result<Vec<ChartData>, PieChartError> {
+        let mut charts = match (&cli.sqlite, &cli.query) {
+            (Some(database), Some(sql)) => {
+                let title = database
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                vec![input::sqlite::query(database, sql, &title)?]
+            }
+            _ => Self::read_chart_file(Cli::get_input(input)?, input, &cli.input)?,
+        };
+
+        for chart_data in charts.iter_mut() {
+            chart_data.options = chart_data.options.or(config);
+        }
+
+        Ok(charts)
+    }
+
+    /// Lay out the charts in an input file as if rendering them, outputting the percentage of
+    /// each wedge and warning of labels that do not fit
+    fn check_file(
+        self: &Self,
+        cli: &RenderArgs,
+        config: &ChartOptions,
+        input: Option<&Path>,
+    ) -> Result<(), PieChartError> {
+        let name = input.map_or("standard input".to_string(), |input| {
+            format!("'{}'", input.to_string_lossy())
+        });
+
+        self.log.set_file(input);
+
+        for chart_data in Self::read_charts(cli, config, input)? {
+            let options = cli.options.or(&chart_data.options);
+            let strings = Self::strings(&options);
+            let rd = self.process_chart_data(&chart_data, &options)?;
+            output!(self.log, "{} {}", name, rd.title.replace('\n', " "));
+
+            for wedge in &rd.wedges {
+                let span = rd.span(wedge);
+                let (inner_radius, radius) = rd.wedge_radii(wedge, 0);
+
+                output!(
+                    self.log,
+                    "  {}: {} ({:.2}%)",
+                    wedge.key,
+                    wedge.value,
+                    wedge.percentage * 100.0
+                );
+
+                // Labels on wedges are centered between the edges, so compare with the chord there
+                if let Some(label) = wedge
+                    .label
+                    .as_ref()
+                    .filter(|_| rd.label_position == LabelPosition::Inside && !rd.is_whole(wedge))
+                {
+                    let chord = (inner_radius + radius) * (span.abs() / 2.0).min(FRAC_PI_2).sin();
+
+                    if rd.metrics.width(label, LABEL_FONT_SIZE) > chord {
+                        warning!(
+                            self.log,
+                            "{}",
+                            strings.format(Message::LabelTooWide, &[&label])
+                        );
+                    }
+                }
+
+                if wedge.legend_lines.concat().ends_with('…') && !wedge.legend.ends_with('…') {
+                    warning!(
+                        self.log,
+                        "{}",
+                        strings.format(
+                            Message::LegendTruncated,
+                            &[&wedge.key, &wedge.legend_lines.concat()]
+                        )
+                    );
+                }
+            }
+
+            if callout::crowded(rd.callouts().iter().map(|(_, c)| c), CALLOUT_SPACING) {
+                warning!(self.log, "{}", strings.get(Message::LabelsOverlap));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Render charts in the output format, to a grid or pages if there is more than one
+    fn write_charts(
+        self: &Self,
+        cli: &RenderArgs,
+        charts: &[ChartData],
+        output: Option<&Path>,
+    ) -> Result<(), PieChartError> {
+        // Watching renders the same outputs again, which were checked before the first render
+        let force = cli.force || cli.watch;
+
+        if let Some(ref template) = cli.template {
+            let template = fs::read_to_string(template).map_err(PieChartError::io(format!(
+                "Unable to read template '{}'",
+                template.to_string_lossy()
+            )))?;
+            let text = self.render_template(&template, charts, &cli.options)?;
+
+            return Cli::write_output(output, text.as_bytes(), force);
+        }
+
+        let svg = || self.svg_text(charts, &cli.options);
+
+        match cli.format.unwrap_or_else(|| OutputFormat::detect(output)) {
+            OutputFormat::Svg => Cli::write_output(output, svg()?.as_bytes(), force),
+            OutputFormat::DataUri => {
+                let text = output::embed::data_uri(&svg()?) + "\n";
+
+                Cli::write_output(output, text.as_bytes(), force)
+            }
+            OutputFormat::Markdown => {
+                let titles: Vec<&str> = charts.iter().map(|chart| chart.title.as_str()).collect();
+                let text = output::embed::markdown(&titles.join(", "), &svg()?) + "\n";
+
+                Cli::write_output(output, text.as_bytes(), force)
+            }
+            OutputFormat::Pdf => {
+                let pdf = self.render_pdf(charts, &cli.options)?;
+
+                Cli::write_output(output, &pdf, force)
+            }
+            OutputFormat::Html => {
+                let html = self.render_html(charts, &cli.options)?;
+
+                Cli::write_output(output, html.as_bytes(), force)
+            }
+            format @ (OutputFormat::Term | OutputFormat::Sixel | OutputFormat::Kitty) => {
+                let graphics = match format {
+                    OutputFormat::Sixel => Some(output::graphics::Graphics::Sixel),
+                    OutputFormat::Kitty => Some(output::graphics::Graphics::Kitty),
+                    _ => None,
+                };
+                let text = self.render_terminal(charts, &cli.options, !cli.no_color, graphics)?;
+
+                Cli::write_output(output, text.as_bytes(), force)
+            }
+        }
+    }
+
+    /// A description of charts as they are rendered, alone or in a grid: the size of the document,
+    /// the position of each chart in it, and the angles, radii, color and legend entry of each
+    /// wedge, in the units of the SVG
+    pub fn report(
+        self: &Self,
+        charts: &[ChartData],
+        options: &ChartOptions,
+    ) -> Result<Report, PieChartError> {
+        let render_data = self.process_charts(charts, options)?;
+        let (size, positions) = match render_data.as_slice() {
+            [rd] => (rd.size(), vec![(0.0, 0.0)]),
+            render_data => Self::grid_layout(
+                &render_data.iter().map(RenderData::size).collect::<Vec<_>>(),
+                options.or(&charts[0].options).columns,
+            )?,
+        };
+        let laid_out: Vec<(&RenderData, (f64, f64))> = render_data.iter().zip(positions).collect();
+
+        Ok(report::report(&laid_out, size))
+    }
+
+    /// A table of the value, exact percentage and cumulative percentage of each wedge of a chart
+    /// in the order they are drawn, followed by their total, as lines of text with the columns
+    /// aligned
+    pub fn summary(
+        self: &Self,
+        chart_data: &ChartData,
+        options: &ChartOptions,
+    ) -> Result<Vec<String>, PieChartError> {
+        let rd = self.process_chart_data(chart_data, &options.or(&chart_data.options))?;
+
+        Ok(summary::table(&rd))
+    }
+
+    /// Write charts as a chart file in another format, after checking that they can be drawn.
+    /// Options that are not set are left out.
+    pub fn convert(
+        self: &Self,
+        charts: &[ChartData],
+        format: InputFormat,
+    ) -> Result<String, PieChartError> {
+        for chart_data in charts {
+            chart_data.validate()?;
+        }
+
+        convert::write(charts, &Self::chart_value(charts), format)
+    }
+
+    /// The colors that the wedges of a chart with `count` items are given with the palette, theme
+    /// and color options, as they are written in the SVG
+    pub fn palette_colors(
+        self: &Self,
+        count: usize,
+        options: &ChartOptions,
+    ) -> Result<Vec<String>, PieChartError> {
+        let chart_data = (1..=count)
+            .fold(ChartData::builder(), |builder, n| {
+                builder.item(n.to_string(), 1.0)
+            })
+            .build()?;
+        // Grouping or sorting the items would leave out or reorder their colors
+        let options = ChartOptions {
+            min_percent: None,
+            sort: None,
+            ..options.clone()
+        };
+
+        Ok(self
+            .process_chart_data(&chart_data, &options)?
+            .wedges
+            .into_iter()
+            .map(|wedge| wedge.color)
+            .collect())
+    }
+
+    /// Listen for HTTP requests at the address, replying to each with the SVG of the chart file
+    /// in its body. This never returns unless the address cannot be listened on.
+    fn serve(
+        self: &Self,
+        address: &str,
+        options: &ChartOptions,
+        config: &ChartOptions,
+    ) -> Result<(), PieChartError> {
+        let listener = std::net::TcpListener::bind(address).map_err(PieChartError::io(format!(
+            "Unable to listen on '{}'",
+            address
+        )))?;
+
+        output!(self.log, "Listening on http://{}", address);
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    error!(self.log, "Unable to accept a connection: {}", e);
+                    continue;
+                }
+            };
+            let response = serve::respond(self, &mut io::BufReader::new(&stream), options, config);
+
+            debug!(self.log, "Replied {}", response.status);
+
+            if let Err(e) = response.write(&mut &stream) {
+                error!(self.log, "Unable to reply: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Convert charts to a Vega-Lite spec of arc marks, with a view for each chart
+    pub fn vega_lite_spec(
+        self: &Self,
+        charts: &[ChartData],
+        options: &ChartOptions,
+    ) -> Result<serde_json::Value, PieChartError> {
+        let charts = charts
+            .iter()
+            .map(|chart_data| self.process_chart_data(chart_data, &options.or(&chart_data.options)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(export::vega_lite(&charts))
+    }
+
+    /// Render a chart to an SVG document, logging any warnings. Any options not set in
+    /// `options` are taken from the `options` block of the chart data.
+    pub fn render(
+        self: &Self,
+        chart_data: &ChartData,
+        options: &ChartOptions,
+    ) -> Result<Document, PieChartError> {
+        let options = options.or(&chart_data.options);
+        let render_data = self.process_chart_data(chart_data, &options)?;
+        let document = output::svg::SvgRenderer {
+            tool: self,
+            columns: None,
+        }
+        .render(&mut [render_data])?;
+        let document = Self::responsive(document, &options);
+
+        Ok(Self::embed_data(
+            document,
+            std::slice::from_ref(chart_data),
+            &options,
+        ))
+    }
+
+    /// Render several charts into one SVG document, laid out in a grid. Class selectors in the
+    /// styles of each chart are scoped to that chart.
+    pub fn render_grid(
+        self: &Self,
+        charts: &[ChartData],
+        options: &ChartOptions,
+    ) -> Result<Document, PieChartError> {
+        let mut render_data = self.process_charts(charts, options)?;
+        let first_options = options.or(&charts[0].options);
+        let document = Self::responsive(
+            self.render_grid_chart(&mut render_data, first_options.columns)?,
+            &first_options,
+        );
+
+        Ok(Self::embed_data(document, charts, options))
+    }
+
+    /// Render charts as the frames of one animated SVG, laid out as the first, in which the
+    /// wedges of each key keep their color and move from each frame to the next. Each frame is
+    /// shown for `frame_duration` seconds, the second half of which is spent moving.
+    pub fn render_animation(
+        self: &Self,
+        frames: &[ChartData],
+        options: &ChartOptions,
+        frame_duration: f64,
+    ) -> Result<Document, PieChartError> {
+        let (mut rd, animation, options) =
+            self.lay_out_animation(frames, options, frame_duration)?;
+
+        rd.animation = Some(animation);
+
+        let document = self.render_chart(&rd)?;
+
+        Ok(Self::responsive(document, &options))
+    }
+
+    /// Render charts as the frames of an animation in a format, as the bytes of the file. GIF and
+    /// APNG images draw only the pie, with a frame for each step of the wedges moving.
+    pub fn render_animation_as(
+        self: &Self,
+        frames: &[ChartData],
+        options: &ChartOptions,
+        frame_duration: f64,
+        format: AnimationFormat,
+    ) -> Result<Vec<u8>, PieChartError> {
+        if format == AnimationFormat::Svg {
+            let document = self.render_animation(frames, options, frame_duration)?;
+
+            return Ok(Self::serialize(&document, &options.or(&frames[0].options)).into_bytes());
+        }
+
+        let (mut rd, animation, _) = self.lay_out_animation(frames, options, frame_duration)?;
+        let images = output::animated::Frames::new(&mut rd, &animation)?;
+
+        Ok(match format {
+            AnimationFormat::Gif => images.gif(),
+            _ => images.apng(),
+        })
+    }
+
+    /// Lay out the frames of an animation as one chart, with the animation of its wedges and the
+    /// options it was laid out with
+    fn lay_out_animation(
+        self: &Self,
+        frames: &[ChartData],
+        options: &ChartOptions,
+        frame_duration: f64,
+    ) -> Result<(RenderData, animation::Animation, ChartOptions), PieChartError> {
+        if frames.len() < 2 {
+            return Err(PieChartError::Validation(format!(
+                "An animation needs at least two frames (found {})",
+                frames.len()
+            )));
+        }
+
+        if !(frame_duration.is_finite() && frame_duration > 0.0) {
+            return Err(PieChartError::Validation(format!(
+                "Frame duration must be greater than 0 (found {})",
+                frame_duration
+            )));
+        }
+
+        // Labels would stay where the first frame put them
+        let options = ChartOptions {
+            labels: Some(WedgeLabels::None),
+            label_format: None,
+            ..options.or(&frames[0].options)
+        };
+        // The frames are laid out together as series, so that they share their keys and colors
+        let chart_data = ChartData {
+            items: vec![],
+            series: frames
+                .iter()
+                .map(|frame| SeriesData {
+                    name: frame.title.clone(),
+                    items: frame.items.clone(),
+                })
+                .collect(),
+            ..frames[0].clone()
+        };
+        let mut rd = self.process_chart_data(&chart_data, &options)?;
+        let percentages: Vec<Vec<f64>> = std::iter::once(&rd.wedges)
+            .chain(&rd.series_wedges)
+            .map(|wedges| {
+                rd.wedges
+                    .iter()
+                    .map(|wedge| {
+                        wedges
+                            .iter()
+                            .filter(|frame_wedge| frame_wedge.key == wedge.key)
+                            .map(|frame_wedge| frame_wedge.percentage)
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // The frames take turns in the one ring
+        let titles = std::mem::take(&mut rd.series);
+
+        rd.series_wedges.clear();
+        rd.rings = 0;
+
+        let animation = animation::animate(&rd, &percentages, &titles, frame_duration);
+
+        Ok((rd, animation, options))
+    }
+
+    /// Render two charts side by side with one legend below them, in which each key has the same
+    /// color in both charts
+    pub fn render_comparison(
+        self: &Self,
+        charts: &[ChartData; 2],
+        options: &ChartOptions,
+    ) -> Result<Document, PieChartError> {
+        let first_options = options.or(&charts[0].options);
+        // Every key once, in the order they first appear, with the first color given for it
+        let mut keys: Vec<ItemData> = vec![];
+
+        for item in charts.iter().flat_map(|chart| &chart.items) {
+            match keys.iter_mut().find(|key| key.key == item.key) {
+                Some(key) => {
+                    key.value += item.total().abs();
+                    key.color = key.color.take().or(item.color.clone());
+                }
+                None => keys.push(ItemData {
+                    value: item.total().abs(),
+                    children: vec![],
+                    ..item.clone()
+                }),
+            }
+        }
+
+        let legend_chart = ChartData {
+            title: String::new(),
+            items: keys,
+            ..Default::default()
+        };
+        // The legend shows every key, and only the key
+        let legend_options = ChartOptions {
+            legend_values: Some(LegendValues::None),
+            labels: Some(WedgeLabels::None),
+            label_position: None,
+            title_style: TitleStyle {
+                position: Some(TitlePosition::Hidden),
+                ..first_options.title_style.clone()
+            },
+            top: None,
+            min_percent: None,
+            center_text: None,
+            no_legend: Some(false),
+            ..first_options.clone()
+        };
+        let colors: Vec<(String, String)> = PieChartTool::new(&SilentLog)
+            .process_chart_data(&legend_chart, &legend_options)?
+            .wedges
+            .into_iter()
+            .map(|wedge| (wedge.key, wedge.color))
+            .collect();
+        let mut pies = charts
+            .iter()
+            .map(|chart_data| {
+                let items = chart_data
+                    .items
+                    .iter()
+                    .map(|item| ItemData {
+                        color: item.color.clone().or_else(|| {
+                            colors
+                                .iter()
+                                .find(|(key, _)| *key == item.key)
+                                .map(|(_, color)| color.clone())
+                        }),
+                        ..item.clone()
+                    })
+                    .collect();
+                let options = ChartOptions {
+                    no_legend: Some(true),
+                    ..options.or(&chart_data.options)
+                };
+
+                self.process_chart_data(
+                    &ChartData {
+                        items,
+                        ..chart_data.clone()
+                    },
+                    &options,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut document = self.render_grid_chart(&mut pies, Some(2))?;
+        let (cell_width, cell_height) = pies
+            .iter()
+            .map(RenderData::size)
+            .fold((0.0, 0.0), |(width, height): (f64, f64), (w, h)| {
+                (width.max(w), height.max(h))
+            });
+        // The legend is as wide as both charts
+        let mut legend_rd = self.process_chart_data(
+            &legend_chart,
+            &ChartOptions {
+                size: Some(cell_width * 2.0 - first_options.gutter.unwrap_or(DEFAULT_GUTTER) * 2.0),
+                ..legend_options
+            },
+        )?;
+
+        legend_rd.scope("legend");
+
+        let legend_top = legend_rd.gutter.top + legend_rd.pie_height();
+        let width = cell_width * 2.0;
+        let height = cell_height
+            + legend_rd.legend_gutter.height()
+            + legend_rd.legend_layout().1
+            + legend_rd.gutter.bottom;
+
+        document = document
+            .set("height", height)
+            .set("viewBox", format!("0 0 {} {}", width, height))
+            .add(
+                element::Group::new()
+                    .set("id", "legend")
+                    .set(
+                        "transform",
+                        format!("translate(0,{})", cell_height - legend_top),
+                    )
+                    .add(element::Style::new(legend_rd.styles.join("\n")))
+                    .add(Self::legend(&legend_rd)),
+            );
+
+        Ok(Self::responsive(document, &first_options))
+    }
+
+    /// Render the change in each item from one chart to the next, as a pie of how much each item
+    /// changed by with a table of the values and their change below it
+    pub fn render_diff(
+        self: &Self,
+        before: &ChartData,
+        after: &ChartData,
+        options: &ChartOptions,
+    ) -> Result<Document, PieChartError> {
+        let changes = diff::changes(before, after);
+
+        if changes.iter().all(|change| change.delta() == 0.0) {
+            return Err(PieChartError::Validation(
+                "The charts have the same values, so there is no change to draw".to_string(),
+            ));
+        }
+
+        let strings = Self::strings(&options.or(&after.options));
+        let chart_data = diff::chart(before, after, &changes, strings);
+        let options = options.or(&chart_data.options);
+        let formatter = Self::number_formatter(&options)?;
+        let rd = self.process_chart_data(&chart_data, &options)?;
+        let (width, height) = rd.size();
+        let heading = |title: &str| title.replace('\n', " ");
+        let rows: Vec<[String; 4]> = std::iter::once([
+            String::new(),
+            heading(&before.title),
+            heading(&after.title),
+            strings.get(Message::Change).to_string(),
+        ])
+        .chain(changes.iter().map(|change| {
+            [
+                change.key.clone(),
+                formatter.value(change.before),
+                formatter.value(change.after),
+                change.percent(&formatter, strings),
+            ]
+        }))
+        .collect();
+        // Keys are aligned on the left of their column and the numbers on the right of theirs
+        let column_widths: Vec<f64> = (0..4)
+            .map(|column| {
+                rows.iter()
+                    .map(|row| rd.metrics.width(&row[column], LEGEND_FONT_SIZE))
+                    .fold(0.0, f64::max)
+            })
+            .collect();
+        let top = height - rd.gutter.bottom + LEGEND_GUTTER;
+        let table_width = column_widths.iter().sum::<f64>() + TABLE_COLUMN_GAP * 3.0;
+        let width = width.max(rd.gutter.left + table_width + rd.gutter.right);
+        // Right to left tables are mirrored, which keeps each cell at the start or end of its
+        // column as the anchors swap sides
+        let mirror = |x: f64| if rd.rtl { width - x } else { x };
+        let mut table = element::Group::new().set("class", "changes");
+
+        for (index, row) in rows.iter().enumerate() {
+            let y = top + (index + 1) as f64 * LEGEND_LINE_HEIGHT;
+            let mut x = rd.gutter.left;
+
+            for (column, cell) in row.iter().enumerate() {
+                let class = match (index, column) {
+                    (0, _) => "legend heading number",
+                    (_, 0) => "legend",
+                    _ => "legend number",
+                };
+
+                x += column_widths[column];
+
+                if column > 0 {
+                    x += TABLE_COLUMN_GAP;
+                } else if cell.is_empty() {
+                    continue;
+                }
+
+                table.append(
+                    element::Text::new(cell.as_str())
+                        .set("class", class)
+                        .set("x", mirror(if column == 0 { rd.gutter.left } else { x }))
+                        .set("y", y),
+                );
+            }
+        }
+
+        let height = top + rows.len() as f64 * LEGEND_LINE_HEIGHT + rd.gutter.bottom;
+        let document = self
+            .render_chart(&rd)?
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", format!("0 0 {} {}", width, height))
+            .add(element::Style::new(
+                ".changes .number{text-anchor:end}\n.changes .heading{font-weight:bold}",
+            ))
+            .add(table);
+
+        Ok(Self::responsive(document, &options))
+    }
+
+    /// Render charts to a PDF document with one page per chart
+    pub fn render_pdf(
+        self: &Self,
+        charts: &[ChartData],
+        options: &ChartOptions,
+    ) -> Result<Vec<u8>, PieChartError> {
+        let mut pages = self.process_charts(charts, options)?;
+
+        output::pdf::PdfRenderer.render(&mut pages)
+    }
+
+    /// Render charts to an HTML page holding the SVG, with tooltips that show the exact value of
+    /// each wedge
+    pub fn render_html(
+        self: &Self,
+        charts: &[ChartData],
+        options: &ChartOptions,
+    ) -> Result<String, PieChartError> {
+        let mut render_data = self.process_charts(charts, options)?;
+        let document = output::svg::SvgRenderer {
+            tool: self,
+            columns: options.or(&charts[0].options).columns,
+        }
+        .render(&mut render_data)?;
+        let document = Self::responsive(document, &options.or(&charts[0].options));
+        let document = Self::embed_data(document, charts, options);
+
+        Ok(output::html::render(
+            &Self::serialize(&document, &options.or(&charts[0].options)),
+            &render_data,
+        ))
+    }
+
+    /// Render charts as text for a terminal, drawn with block characters in 24 bit color or, if
+    /// `color` is not set, in a different shade of block for each wedge
+    pub fn render_term(
+        self: &Self,
+        charts: &[ChartData],
+        options: &ChartOptions,
+        color: bool,
+    ) -> Result<String, PieChartError> {
+        self.render_terminal(charts, options, color, None)
+    }
+
+    /// Render charts as text for a terminal, with the pie drawn in an image protocol if given
+    fn render_terminal(
+        self: &Self,
+        charts: &[ChartData],
+        options: &ChartOptions,
+        color: bool,
+        graphics: Option<output::graphics::Graphics>,
+    ) -> Result<String, PieChartError> {
+        let mut render_data = self.process_charts(charts, options)?;
+
+        output::term::TermRenderer { color, graphics }.render(&mut render_data)
+    }
+
+    /// Render charts with a template, which is given the layout of each chart as well as the
+    /// chart rendered as SVG
+    pub fn render_template(
+        self: &Self,
+        template: &str,
+        charts: &[ChartData],
+        options: &ChartOptions,
+    ) -> Result<String, PieChartError> {
+        let render_data = self.process_charts(charts, options)?;
+        let mut template_charts = vec![];
+
+        for (rd, chart_data) in render_data.iter().zip(charts) {
+            let (width, height) = rd.size();
+            let (center_x, center_y) = rd.center();
+            let (start, _) = rd.angles();
+
+            template_charts.push(output::template::Chart {
+                title: rd.title.clone(),
+                subtitle: rd.subtitle.clone(),
+                caption: rd.caption.clone(),
+                width,
+                height,
+                center_x,
+                center_y,
+                background: rd.background.clone(),
+                text_color: rd.text_color.clone(),
+                styles: rd.styles.join("\n"),
+                svg: Self::serialize(
+                    &Self::responsive(self.render_chart(rd)?, &options.or(&chart_data.options)),
+                    &options.or(&chart_data.options),
+                ),
+                wedges: Self::template_wedges(rd, &rd.wedges, start, 0),
+            });
+        }
+
+        output::template::render(template, &template_charts)
+    }
+
+    fn template_wedges(
+        rd: &RenderData,
+        wedges: &[WedgeData],
+        start: f64,
+        level: usize,
+    ) -> Vec<output::template::Wedge> {
+        let (x_center, y_center) = rd.center();
+        let mut a = start;
+        let mut result = vec![];
+
+        for wedge in wedges {
+            let b = a + rd.span(wedge);
+            let (inner_radius, outer_radius) = rd.wedge_radii(wedge, level);
+            let (mid, label_radius) = ((a + b) / 2.0, (inner_radius + outer_radius) / 2.0);
+
+            result.push(output::template::Wedge {
+                key: wedge.key.clone(),
+                value: wedge.value,
+                percent: wedge.percentage * 100.0,
+                legend: wedge.legend.clone(),
+                color: wedge.color.clone(),
+                href: wedge.href.clone(),
+                start_angle: (a + FRAC_PI_2).to_degrees(),
+                end_angle: (b + FRAC_PI_2).to_degrees(),
+                inner_radius,
+                outer_radius,
+                path: Value::from(Self::wedge_path(rd, wedge, a, b, level)).to_string(),
+                label_x: x_center + label_radius * mid.cos(),
+                label_y: y_center + label_radius * mid.sin(),
+                children: Self::template_wedges(rd, &wedge.children, a, level + 1),
+                class: wedge.class.clone(),
+            });
+            a = b;
+        }
+
+        result
+    }
+
+    /// Process each chart, with `options` taking precedence over those in the chart data
+    fn process_charts(
+        self: &Self,
+        charts: &[ChartData],
+        options: &ChartOptions,
+    ) -> Result<Vec<RenderData>, PieChartError> {
+        if charts.is_empty() {
+            return Err(PieChartError::Validation(
+                "There are no charts to render".to_string(),
+            ));
+        }
+
+        charts
+            .iter()
+            .map(|chart_data| self.process_chart_data(chart_data, &options.or(&chart_data.options)))
+            .collect()
+    }
+
+    /// The size of a grid of charts of the given sizes, with the top left corner of each chart.
+    /// Each is centered in a cell as large as the largest chart, with `columns` cells to a row
+    /// or as many as make the grid roughly square.
+    fn grid_layout(
+        sizes: &[(f64, f64)],
+        columns: Option<usize>,
+    ) -> Result<GridLayout, PieChartError> {
+        let columns = columns.unwrap_or_else(|| (sizes.len() as f64).sqrt().ceil() as usize);
+
+        if columns == 0 {
+            return Err(PieChartError::Validation(
+                "Columns must be greater than 0".to_string(),
+            ));
+        }
+
+        let cell_width = sizes.iter().fold(0.0, |acc: f64, (w, _)| acc.max(*w));
+        let cell_height = sizes.iter().fold(0.0, |acc: f64, (_, h)| acc.max(*h));
+        let rows = sizes.len().div_ceil(columns);
+        let positions = sizes
+            .iter()
+            .enumerate()
+            .map(|(index, (w, h))| {
+                let (row, column) = (index / columns, index % columns);
+
+                (
+                    column as f64 * cell_width + (cell_width - w) / 2.0,
+                    row as f64 * cell_height + (cell_height - h) / 2.0,
+                )
+            })
+            .collect();
+
+        Ok((
+            (
+                cell_width * columns.min(sizes.len()) as f64,
+                cell_height * rows as f64,
+            ),
+            positions,
+        ))
+    }
+
+    /// Lay out processed charts in a grid, each in its own nested SVG element
+    fn render_grid_chart(
+        self: &Self,
+        render_data: &mut [RenderData],
+        columns: Option<usize>,
+    ) -> Result<Document, PieChartError> {
+        let sizes: Vec<(f64, f64)> = render_data.iter().map(RenderData::size).collect();
+        let ((width, height), positions) = Self::grid_layout(&sizes, columns)?;
+        let mut cells = vec![];
+
+        for (index, rd) in render_data.iter_mut().enumerate() {
+            let id = format!("chart-{}", index);
+
+            rd.scope(&id);
+            cells.push((id, self.render_chart(rd)?));
+        }
+
+        let mut document = Document::new()
+            .set("xmlns", "http://www.w3.org/2000/svg")
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", format!("0 0 {} {}", width, height));
+
+        // Each chart draws its own background rectangle, if asked to
+        if render_data[0].has_background() && !render_data[0].background_rect {
+            document = document.set(
+                "style",
+                format!("background-color: {};", render_data[0].background),
+            );
+        }
+
+        for ((id, chart), (x, y)) in cells.into_iter().zip(positions) {
+            document.append(chart.set("id", id).set("x", x).set("y", y));
+        }
+
+        Ok(document)
+    }
+
+    fn read_chart_file(
+        mut reader: Box<dyn Read>,
+        path: Option<&Path>,
+        cli: &InputArgs,
+    ) -> Result<Vec<ChartData>, PieChartError> {
+        let mut content = vec![];
+
+        reader
+            .read_to_end(&mut content)
+            .map_err(PieChartError::io("Unable to read the chart file"))?;
+
+        let title = path
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        input::read(&content, path, cli.input_format, &title, &cli.table())
+    }
+
+    /// Replace the fixed size of the document with one that scales to fit, if asked to
+    fn responsive(mut document: Document, options: &ChartOptions) -> Document {
+        if options.responsive.unwrap_or_default() {
+            let attributes = document.get_attributes_mut();
+
+            attributes.remove("width");
+            attributes.remove("height");
+            document = document.set("preserveAspectRatio", "xMidYMid meet");
+        }
+
+        document
+    }
+
+    /// The charts as the value they serialize to, an object for one chart or an array for several,
+    /// leaving out the options that are not set
+    fn chart_value(charts: &[ChartData]) -> serde_json::Value {
+        fn unset(value: &serde_json::Value) -> bool {
+            value.is_null() || value.as_object().is_some_and(|map| map.is_empty())
+        }
+
+        fn strip(value: &mut serde_json::Value) {
+            if let Some(map) = value.as_object_mut() {
+                map.values_mut().for_each(strip);
+                map.retain(|_, value| !unset(value));
+            }
+        }
+
+        let mut value = match charts {
+            [chart_data] => serde_json::to_value(chart_data),
+            charts => serde_json::to_value(charts),
+        }
+        .unwrap_or_default();
+
+        match value {
+            serde_json::Value::Array(ref mut charts) => charts
+                .iter_mut()
+                .for_each(|chart| strip(&mut chart["options"])),
+            ref mut chart => strip(&mut chart["options"]),
+        }
+
+        value
+    }
+
+    /// Add the charts to the document as JSON in a `<metadata>` element, with the options they
+    /// were rendered with but leaving out those that are not set
+    fn embed_data(document: Document, charts: &[ChartData], options: &ChartOptions) -> Document {
+        let charts: Vec<ChartData> = charts
+            .iter()
+            .map(|chart_data| ChartData {
+                options: options.or(&chart_data.options),
+                ..chart_data.clone()
+            })
+            .collect();
+
+        if !charts
+            .first()
+            .is_some_and(|chart| chart.options.embed_data.unwrap_or_default())
+        {
+            return document;
+        }
+
+        let value = Self::chart_value(&charts);
+
+        // The svg crate has no type for metadata elements
+        let mut metadata = element::Element::new("metadata");
+
+        metadata.assign("class", input::svg::METADATA_CLASS);
+        metadata.append(Text::new(value.to_string()));
+        document.add(metadata)
+    }
+
+    /// The document as SVG text, with coordinates rounded and line breaks removed or indentation
+    /// added if asked for
+    fn serialize(document: &Document, options: &ChartOptions) -> String {
+        let mut svg = document.to_string();
+
+        let precision = options.precision.or(options
+            .deterministic
+            .unwrap_or_default()
+            .then_some(DETERMINISTIC_PRECISION));
+
+        if let Some(digits) = precision {
+            svg = output::compact::round_coordinates(&svg, digits);
+        }
+
+        if options.minify.unwrap_or_default() {
+            svg = output::compact::minify(&svg);
+        } else if options.pretty.unwrap_or_default() {
+            svg = output::pretty::indent(&svg);
+        }
+
+        svg
+    }
+
+    /// Format values and percentages as the options say
+    fn number_formatter(options: &ChartOptions) -> Result<NumberFormatter, PieChartError> {
+        Ok(NumberFormatter {
+            value_decimals: options.decimals,
+            percent_decimals: options.percent_decimals.unwrap_or(0),
+            thousands_separator: options.thousands_separator.unwrap_or_default(),
+            si_prefix: options.si_prefix.unwrap_or_default(),
+            locale: match options.locale {
+                Some(ref tag) => Locale::from_tag(tag).ok_or_else(|| {
+                    PieChartError::Validation(format!("Unsupported locale '{}'", tag))
+                })?,
+                None => Locale::default(),
+            },
+            units: options.units.clone(),
+        })
+    }
+
+    /// The built-in text in the language of the locale
+    fn strings(options: &ChartOptions) -> Strings {
+        options
+            .locale
+            .as_deref()
+            .map_or_else(Strings::default, Strings::from_tag)
+    }
+
+    /// Replace the `{name}` placeholders of a label or legend format in a single pass, so that
+    /// the values are never searched for placeholders themselves
+    fn fill_format(
+        kind: &str,
+        format: &str,
+        values: &[(&str, String)],
+    ) -> Result<String, PieChartError> {
+        let mut text = String::new();
+        let mut rest = format;
+
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').map(|end| start + end);
+            let value = end.and_then(|end| {
+                values
+                    .iter()
+                    .find(|(name, _)| *name == &rest[start + 1..end])
+                    .map(|(_, value)| value)
+            });
+            let (Some(end), Some(value)) = (end, value) else {
+                return Err(PieChartError::Validation(format!(
+                    "The {} format '{}' has an unknown placeholder, where only {{key}}, {{value}} and {{percent}} can be used",
+                    kind, format
+                )));
+            };
+
+            text.push_str(&rest[..start]);
+            text.push_str(value);
+            rest = &rest[end + 1..];
+        }
+
+        text.push_str(rest);
+        Ok(text)
+    }
+
+    /// Check that a user supplied value can be safely placed in a style sheet
+    fn css_value<'b>(name: &str, value: &'b str) -> Result<&'b str, PieChartError> {
+        if value.is_empty() || value.contains(['{', '}', ';', '<', '>']) {
+            Err(PieChartError::Validation(format!(
+                "Invalid {} '{}'",
+                name, value
+            )))
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Whether text on this color should be light rather than dark, based on its relative luminance
+    fn is_dark(rgb: u32) -> bool {
+        let linear = |shift: u32| {
+            let c = ((rgb >> shift) & 0xff) as f64 / 255.0;
+
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let luminance = 0.2126 * linear(16) + 0.7152 * linear(8) + 0.0722 * linear(0);
+
+        luminance < 0.179
+    }
+
+    /// Mix the color with white, from unchanged at 0 to white at 1
+    fn lighten(rgb: u32, amount: f64) -> u32 {
+        [16, 8, 0].iter().fold(0, |lighter, shift| {
+            let c = ((rgb >> shift) & 0xff) as f64;
+
+            lighter | ((c + (255.0 - c) * amount).round() as u32) << shift
+        })
+    }
+
+    /// The wedges of a sunburst ring and those outside it, with their styles. Wedges without a
+    /// color are lighter shades of their parent.
+    fn ring_wedges(
+        items: &[ItemData],
+        parent_color: &str,
+        total: f64,
+        parent_class: &str,
+        stroke: &str,
+        styles: &mut Vec<String>,
+    ) -> Vec<WedgeData> {
+        let mut wedges = vec![];
+
+        for (index, item) in items.iter().enumerate() {
+            let class = item
+                .class
+                .clone()
+                .unwrap_or_else(|| format!("{}-{}", parent_class, index));
+            let color = match item.color {
+                Some(ref color) => color.to_string(),
+                None => match color::rgb(parent_color) {
+                    Some(rgb) => format!(
+                        "#{:06x}",
+                        Self::lighten(rgb, 0.25 + 0.5 * index as f64 / items.len() as f64)
+                    ),
+                    None => parent_color.to_string(),
+                },
+            };
+
+            styles.push(format!(".{}{{fill:{};{}}}", class, color, stroke));
+
+            let children = Self::ring_wedges(&item.children, &color, total, &class, stroke, styles);
+
+            wedges.push(WedgeData {
+                key: item.key.to_string(),
+                value: item.value,
+                legend: item.key.to_string(),
+                legend_lines: vec![],
+                color,
+                percentage: item.value / total,
+                label: None,
+                label_inverse: false,
+                pattern: None,
+                href: item.href.clone(),
+                class,
+                children,
+            });
+        }
+
+        wedges
+    }
+
+    /// Leave out or flip the sign of negative values at every level, warning of each
+    fn adjust_negatives(
+        self: &Self,
+        items: &[ItemData],
+        policy: NegativeValues,
+        strings: Strings,
+    ) -> Vec<ItemData> {
+        items
+            .iter()
+            .filter_map(|item| {
+                let mut item = item.clone();
+
+                if item.value < 0.0 {
+                    if policy == NegativeValues::Skip {
+                        warning!(
+                            self.log,
+                            "{}",
+                            strings.format(Message::NegativeSkipped, &[&item.key, &item.value])
+                        );
+                        return None;
+                    }
+
+                    warning!(
+                        self.log,
+                        "{}",
+                        strings.format(Message::NegativeFlipped, &[&-item.value, &item.key])
+                    );
+                    item.value = -item.value;
+                }
+
+                item.children = self.adjust_negatives(&item.children, policy, strings);
+                Some(item)
+            })
+            .collect()
+    }
+
+    /// Leave out the items with a value of zero at every level, warning of each
+    fn skip_zeros(self: &Self, items: &[ItemData], strings: Strings) -> Vec<ItemData> {
+        items
+            .iter()
+            .filter(|item| {
+                if item.value == 0.0 {
+                    warning!(
+                        self.log,
+                        "{}",
+                        strings.format(Message::ZeroSkipped, &[&item.key])
+                    );
+                }
+
+                item.value != 0.0
+            })
+            .map(|item| ItemData {
+                children: self.skip_zeros(&item.children, strings),
+                ..item.clone()
+            })
+            .collect()
+    }
+
+    /// Replace the items sharing a key with the first of them, holding their total value and all
+    /// of their children
+    fn merge_duplicates(items: &[ItemData]) -> Vec<ItemData> {
+        let mut merged: Vec<ItemData> = vec![];
+
+        for item in items {
+            match merged.iter_mut().find(|merged| merged.key == item.key) {
+                Some(merged) => {
+                    merged.value += item.value;
+                    merged.children.extend(item.children.iter().cloned());
+                }
+                None => merged.push(item.clone()),
+            }
+        }
+
+        merged
+    }
+
+    /// Keep the `count` largest items in their order, and if `other_label` is given replace the
+    /// rest with a single item
+    fn top_items(items: &[ItemData], count: usize, other_label: Option<&str>) -> Vec<ItemData> {
+        let mut order: Vec<usize> = (0..items.len()).collect();
+
+        order.sort_by(|&a, &b| items[b].value.total_cmp(&items[a].value));
+
+        let (top, rest) = order.split_at(count.min(items.len()));
+        let mut kept: Vec<ItemData> = (0..items.len())
+            .filter(|index| top.contains(index))
+            .map(|index| items[index].clone())
+            .collect();
+
+        // The rest are kept as the children of the other item, so that they can be broken out
+        if let Some(other_label) = other_label.filter(|_| !rest.is_empty()) {
+            kept.push(ItemData {
+                children: rest.iter().map(|&index| items[index].clone()).collect(),
+                ..ItemData::new(
+                    other_label,
+                    rest.iter().map(|&index| items[index].value).sum(),
+                )
+            });
+        }
+
+        kept
+    }
+
+    /// Replace all items below `min_percent` of the total with a single item, if there is more than one
+    fn group_small_items(items: &[ItemData], min_percent: f64, other_label: &str) -> Vec<ItemData> {
+        let total: f64 = items.iter().fold(0.0, |acc, item| acc + item.value);
+        let (small, mut large): (Vec<&ItemData>, Vec<&ItemData>) = items
+            .iter()
+            .partition(|item| item.value / total * 100.0 < min_percent);
+
+        if small.len() < 2 {
+            return items.to_vec();
+        }
+
+        // The grouped items are kept as its children, so that they can be broken out
+        let other = ItemData {
+            key: other_label.to_string(),
+            value: small.iter().fold(0.0, |acc, item| acc + item.value),
+            color: None,
+            href: None,
+            class: None,
+            children: small.into_iter().cloned().collect(),
+        };
+
+        large.push(&other);
+        large.into_iter().cloned().collect()
+    }
+
+    fn process_chart_data(
+        self: &Self,
+        cd: &ChartData,
+        options: &ChartOptions,
+    ) -> Result<RenderData, PieChartError> {
+        if !cd.series.is_empty() {
+            return self.process_series(cd, options);
+        }
+
+        let strings = Self::strings(options);
+        let adjusted;
+        let cd = match options.negatives.unwrap_or_default() {
+            NegativeValues::Error => cd,
+            policy => {
+                adjusted = ChartData {
+                    items: self.adjust_negatives(&cd.items, policy, strings),
+                    ..cd.clone()
+                };
+                &adjusted
+            }
+        };
+
+        cd.validate()?;
+
+        let mut items: Vec<ItemData> = cd.items.iter().map(ItemData::with_totals).collect();
+
+        if options.zeros.unwrap_or_default() == ZeroValues::Skip {
+            items = self.skip_zeros(&items, strings);
+        }
+
+        if options.merge_duplicates.unwrap_or_default() {
+            items = Self::merge_duplicates(&items);
+        } else {
+            for (index, item) in items.iter().enumerate() {
+                let count = items.iter().filter(|other| other.key == item.key).count();
+
+                // Warn once, at the first of the items with the key
+                if count > 1 && !items[..index].iter().any(|other| other.key == item.key) {
+                    warning!(
+                        self.log,
+                        "{}",
+                        strings.format(Message::DuplicateKey, &[&item.key, &count])
+                    );
+                }
+            }
+        }
+
+        if options.percentages.unwrap_or_default() {
+            let sum: f64 = items.iter().map(|item| item.value).sum();
+
+            if (sum - 100.0).abs() > PERCENTAGE_TOLERANCE {
+                return Err(PieChartError::Validation(format!(
+                    "Percentages add up to {} rather than 100",
+                    sum
+                )));
+            }
+        }
+
+        if let Some(count) = options.top {
+            if count == 0 {
+                return Err(PieChartError::Validation(
+                    "Top count must be greater than 0".to_string(),
+                ));
+            }
+
+            items = Self::top_items(
+                &items,
+                count,
+                options.top_other.unwrap_or_default().then(|| {
+                    options
+                        .other_label
+                        .as_deref()
+                        .unwrap_or(strings.get(Message::Other))
+                }),
+            );
+        }
+
+        match options.sort.unwrap_or_default() {
+            SortOrder::ValueDesc => items.sort_by(|a, b| b.value.total_cmp(&a.value)),
+            SortOrder::ValueAsc => items.sort_by(|a, b| a.value.total_cmp(&b.value)),
+            SortOrder::Key => items.sort_by(|a, b| a.key.cmp(&b.key)),
+            SortOrder::None => (),
+        }
+
+        if let Some(min_percent) = options.min_percent {
+            items = Self::group_small_items(
+                &items,
+                min_percent,
+                options
+                    .other_label
+                    .as_deref()
+                    .unwrap_or(strings.get(Message::Other)),
+            );
+        }
+
+        let theme = options.theme.unwrap_or_default();
+        let palette = options.palette.unwrap_or(theme.palette());
+        let palette_colors = palette.colors(items.len());
+        let color_space = options.color_space.unwrap_or_default();
+        let fraction = |name: &str, value: Option<f64>, default: f64| match value {
+            Some(value) if !(0.0..=1.0).contains(&value) => Err(PieChartError::Validation(
+                format!("The {} must be from 0 to 1, not {}", name, value),
+            )),
+            value => Ok(value.unwrap_or(default)),
+        };
+        let saturation = fraction("saturation", options.saturation, DEFAULT_SATURATION)?;
+        let brightness = fraction("brightness", options.brightness, DEFAULT_BRIGHTNESS)?;
+        let labels = options.labels.unwrap_or_default();
+        let pattern_mode = options.pattern.unwrap_or_default();
+        let legend_values = options.legend_values.unwrap_or_default();
+        let formatter = Self::number_formatter(options)?;
+        // Generate random resource colors based on https://martin.ankerl.com/2009/12/09/how-to-create-random-colors-programmatically/
+        let seed = options.seed.or(cd.seed).or(options
+            .deterministic
+            .unwrap_or_default()
+            .then_some(DETERMINISTIC_SEED));
+        let mut h: f32 = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed).gen(),
+            None => rand::thread_rng().gen(),
+        };
+        let mut wedges = vec![];
+        let background = options.background.as_deref().unwrap_or(theme.background());
+
+        if background != "transparent" {
+            color::validate("background", background)?;
+        }
+
+        // Separators on a transparent background are drawn in the theme's background color
+        let background_color = if background == "transparent" {
+            theme.background()
+        } else {
+            background
+        };
+        // Either stroke option draws a separator, in the background color unless given
+        let wedge_stroke = match (&options.stroke_color, options.stroke_width) {
+            (None, None) => theme
+                .wedge_stroke()
+                .map(|(color, width)| (color.to_string(), width)),
+            (color, width) => {
+                let color = color.as_deref().unwrap_or(
+                    theme
+                        .wedge_stroke()
+                        .map_or(background_color, |(color, _)| color),
+                );
+                let width = width.unwrap_or(1.0);
+
+                color::validate("stroke", color)?;
+
+                if !(width.is_finite() && width >= 0.0) {
+                    return Err(PieChartError::Validation(format!(
+                        "Stroke width must be at least 0 (found {})",
+                        width
+                    )));
+                }
+
+                Some((color.to_string(), width))
+            }
+        };
+        let stroke = match wedge_stroke {
+            Some((ref color, width)) => format!("stroke:{};stroke-width:{}", color, width),
+            None => "stroke-width:0".to_string(),
+        };
+        let embedded_font = options
+            .embed_font
+            .as_ref()
+            .map(|path| {
+                fs::read(path).map_err(|e| PieChartError::Io {
+                    message: format!("Unable to read font file '{}'", path.to_string_lossy()),
+                    source: e,
+                })
+            })
+            .transpose()?;
+        // Text is measured in the embedded font where it can be read, which is all but WOFF2
+        let metrics = match embedded_font {
+            Some(ref font) => text::Metrics::from_font(font).unwrap_or_else(|| {
+                debug!(
+                    self.log,
+                    "Measuring text in Helvetica, as the font cannot be read"
+                );
+                text::Metrics::default()
+            }),
+            None => text::Metrics::default(),
+        };
+        // An embedded font is used by default, under the name of its file
+        let embedded_font_family = options
+            .embed_font
+            .as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string());
+        let font_family = Self::css_value(
+            "font",
+            options
+                .font_family
+                .as_deref()
+                .or(embedded_font_family.as_deref())
+                .unwrap_or(DEFAULT_FONT_FAMILY),
+        )?;
+        // Charts read right to left in Hebrew and Arabic, or when most of their keys are in them
+        let rtl = strings.rtl
+            || items
+                .iter()
+                .filter(|item| strings::is_rtl(&item.key))
+                .count()
+                * 2
+                > items.len();
+        let mut styles = vec![
+            format!(
+                ".labels{{fill:rgb(0,0,0);font-size:10;font-family:{};text-anchor:middle;dominant-baseline:central}}",
+                font_family
+            ),
+            format!(
+                ".legend{{font-family:{};font-size:12pt;fill:{};text-anchor:left;}}",
+                font_family,
+                theme.text_color()
+            ),
+            ".labels.inverse{fill:rgb(255,255,255)}".to_string(),
+        ];
+
+        if cd.caption.is_some() {
+            styles.push(format!(
+                ".caption{{font-family:{};font-size:{};fill:{};text-anchor:start;}}",
+                font_family,
+                CAPTION_FONT_SIZE,
+                theme.text_color()
+            ));
+        }
+
+        if let Some(len) = palette.len() {
+            if items.len() > len {
+                warning!(
+                    self.log,
+                    "{}",
+                    strings.format(Message::PaletteRepeats, &[&len, &items.len()])
+                );
+            }
+        }
+
+        let total: f64 = if options.percentages.unwrap_or_default() {
+            100.0
+        } else {
+            items.iter().fold(0.0, |acc, item| acc + item.value)
+        };
+
+        debug!(
+            self.log,
+            "Chart '{}' has {} items totalling {}",
+            cd.title.replace('\n', " "),
+            items.len(),
+            total
+        );
+
+        let displayed_percentages = formatter.round_percentages(
+            &items
+                .iter()
+                .map(|item| item.value / total)
+                .collect::<Vec<_>>(),
+        );
+
+        for tuple in items.iter().enumerate() {
+            let (index, item) = tuple;
+            let (fill, rgb) = match item.color {
+                // Colors have been validated
+                Some(ref color) => (color.to_string(), color::rgb(color)),
+                None => {
+                    let rgb = match palette_colors {
+                        Some(ref colors) => colors[index],
+                        None => match color_space {
+                            ColorSpace::Hsv => color::hsv_to_rgb(h as f64, saturation, brightness),
+                            ColorSpace::Oklch => {
+                                color::oklch_to_rgb(OKLCH_LIGHTNESS, OKLCH_CHROMA, h as f64)
+                            }
+                        },
+                    };
+
+                    (format!("#{:06x}", rgb), Some(rgb))
+                }
+            };
+
+            let pattern = match pattern_mode {
+                PatternMode::None => None,
+                PatternMode::Overlay => Some(WedgePattern {
+                    background: fill.clone(),
+                    foreground: if rgb.is_some_and(Self::is_dark) {
+                        "#ffffff"
+                    } else {
+                        "#000000"
+                    }
+                    .to_string(),
+                }),
+                PatternMode::Only => Some(WedgePattern {
+                    background: "#ffffff".to_string(),
+                    foreground: "#000000".to_string(),
+                }),
+            };
+
+            let class = item
+                .class
+                .clone()
+                .unwrap_or_else(|| format!("wedge-{}", index));
+
+            if pattern.is_some() {
+                styles.push(format!(
+                    ".{}{{fill:url(#pattern-{});{}}}",
+                    class, index, stroke
+                ));
+            } else {
+                styles.push(format!(".{}{{fill:{};{}}}", class, fill, stroke));
+            }
+
+            let percentage = item.value / total;
+            let displayed_percentage = formatter.percent(displayed_percentages[index]);
+            let placeholders = [
+                ("key", item.key.clone()),
+                ("value", formatter.value(item.value)),
+                (
+                    "percent",
+                    formatter.percent_number(displayed_percentages[index]),
+                ),
+            ];
+            let label = match (&options.label_format, labels) {
+                (Some(format), _) => Some(Self::fill_format("label", format, &placeholders)?),
+                (None, WedgeLabels::None) => None,
+                (None, WedgeLabels::Key) => Some(item.key.to_string()),
+                (None, WedgeLabels::Percent) => Some(displayed_percentage.clone()),
+                (None, WedgeLabels::Both) => Some(format!("{} {}", item.key, displayed_percentage)),
+            };
+
+            let legend = match (&options.legend_format, legend_values) {
+                (Some(format), _) => Self::fill_format("legend", format, &placeholders)?,
+                (None, LegendValues::Percent) => {
+                    format!("{} ({})", item.key, displayed_percentage)
+                }
+                (None, LegendValues::Value) => {
+                    format!("{} ({})", item.key, formatter.value(item.value))
+                }
+                (None, LegendValues::Both) => format!(
+                    "{} ({}, {})",
+                    item.key,
+                    formatter.value(item.value),
+                    displayed_percentage
+                ),
+                (None, LegendValues::None) => item.key.to_string(),
+            };
+
+            let children = if options.sunburst.unwrap_or_default() {
+                Self::ring_wedges(&item.children, &fill, total, &class, &stroke, &mut styles)
+            } else {
+                vec![]
+            };
+
+            wedges.push(WedgeData {
+                key: item.key.to_string(),
+                value: item.value,
+                legend,
+                legend_lines: vec![],
+                color: fill,
+                percentage,
+                label,
+                label_inverse: pattern_mode != PatternMode::Only && rgb.is_some_and(Self::is_dark),
+                children,
+                pattern,
+                href: item.href.clone(),
+                class,
+            });
+
+            trace!(
+                self.log,
+                "Wedge '{}' is {} ({:.2}%) in {}",
+                item.key,
+                item.value,
+                percentage * 100.0,
+                wedges[index].color
+            );
+
+            h = (h + GOLDEN_RATIO_CONJUGATE) % 1.0;
+        }
+
+        if options.check_colorblind.unwrap_or_default() && wedges.len() > 1 {
+            let colors: Vec<Option<u32>> = wedges
+                .iter()
+                .map(|wedge| color::rgb(&wedge.color))
+                .collect();
+
+            // The first and last wedges also touch, unless there are only two or the chart is a
+            // semicircle
+            for i in
+                0..colors.len() - usize::from(colors.len() == 2 || options.half.unwrap_or_default())
+            {
+                let j = (i + 1) % colors.len();
+
+                if let (Some(a), Some(b)) = (colors[i], colors[j]) {
+                    if let Some(deficiency) = color_vision::confused_by(a, b) {
+                        warning!(
+                            self.log,
+                            "{}",
+                            strings.format(
+                                Message::HardToTellApart,
+                                &[&wedges[i].key, &wedges[j].key, &deficiency]
+                            )
+                        );
+                    }
+                }
+            }
+        }
+
+        let breakout = match options.breakout {
+            Some(ref key) => {
+                if options.half.unwrap_or_default()
+                    || options.rose.unwrap_or_default()
+                    || options.sunburst.unwrap_or_default()
+                    || options.label_position == Some(LabelPosition::Outside)
+                {
+                    return Err(PieChartError::Validation(
+                        "Breakouts cannot be drawn with half, rose or sunburst charts, or with outside labels".to_string(),
+                    ));
+                }
+
+                let index = items
+                    .iter()
+                    .position(|item| item.key == *key && !item.children.is_empty())
+                    .ok_or_else(|| {
+                        PieChartError::Validation(format!(
+                            "There is no item '{}' with children to break out",
+                            key
+                        ))
+                    })?;
+                let mut breakout_wedges = Self::ring_wedges(
+                    &items[index].children,
+                    &wedges[index].color,
+                    items[index].value,
+                    &wedges[index].class,
+                    &stroke,
+                    &mut styles,
+                );
+
+                // The wedges have no legend entries, so they are always labeled with their key
+                for wedge in breakout_wedges.iter_mut() {
+                    let percent = formatter.percent(wedge.percentage);
+                    let placeholders = [
+                        ("key", wedge.key.clone()),
+                        ("value", formatter.value(wedge.value)),
+                        ("percent", formatter.percent_number(wedge.percentage)),
+                    ];
+
+                    wedge.label = Some(match (&options.label_format, labels) {
+                        (Some(format), _) => Self::fill_format("label", format, &placeholders)?,
+                        (None, WedgeLabels::Percent | WedgeLabels::Both) => {
+                            format!("{} {}", wedge.key, percent)
+                        }
+                        (None, _) => wedge.key.clone(),
+                    });
+                    wedge.label_inverse = color::rgb(&wedge.color).is_some_and(Self::is_dark);
+                    wedge.children.clear();
+                }
+
+                Some(Breakout {
+                    index,
+                    wedges: breakout_wedges,
+                })
+            }
+            None => None,
+        };
+        let pie_diameter = options.size.unwrap_or(DEFAULT_PIE_DIAMETER);
+        let gutter_size = options.gutter.unwrap_or(DEFAULT_GUTTER);
+        let legend_height = options.legend_height.unwrap_or(DEFAULT_LEGEND_HEIGHT);
+
+        if !(pie_diameter.is_finite() && pie_diameter > 0.0) {
+            return Err(PieChartError::Validation(format!(
+                "Size must be greater than 0 (found {})",
+                pie_diameter
+            )));
+        }
+
+        if !(gutter_size.is_finite() && gutter_size >= 0.0) {
+            return Err(PieChartError::Validation(format!(
+                "Gutter must be at least 0 (found {})",
+                gutter_size
+            )));
+        }
+
+        if !(legend_height.is_finite() && legend_height > 0.0) {
+            return Err(PieChartError::Validation(format!(
+                "Legend height must be greater than 0 (found {})",
+                legend_height
+            )));
+        }
+
+        if options.minify.unwrap_or_default() && options.pretty.unwrap_or_default() {
+            return Err(PieChartError::Validation(
+                "Minified and pretty output cannot be combined".to_string(),
+            ));
+        }
+
+        if options.rose.unwrap_or_default() && options.sunburst.unwrap_or_default() {
+            return Err(PieChartError::Validation(
+                "Rose and sunburst charts cannot be combined".to_string(),
+            ));
+        }
+
+        let hole_ratio = match options.hole_ratio.or(cd.hole_ratio) {
+            Some(ratio) => ratio,
+            None if options.donut.unwrap_or_default() => DEFAULT_HOLE_RATIO,
+            None => 0.0,
+        };
+
+        if !(0.0..1.0).contains(&hole_ratio) {
+            return Err(PieChartError::Validation(format!(
+                "Hole ratio must be at least 0 and less than 1 (found {})",
+                hole_ratio
+            )));
+        }
+
+        // Charts drawn without a pie can only be drawn one way
+        let shapes = [
+            (options.waffle.unwrap_or_default(), "Waffle"),
+            (options.as_bars.unwrap_or_default(), "Bar"),
+            (options.treemap.unwrap_or_default(), "Treemap"),
+        ];
+
+        if let Some((_, shape)) = shapes.iter().find(|(drawn, _)| *drawn) {
+            if shapes.iter().filter(|(drawn, _)| *drawn).count() > 1
+                || options.half.unwrap_or_default()
+                || options.rose.unwrap_or_default()
+                || options.sunburst.unwrap_or_default()
+                || hole_ratio > 0.0
+                || breakout.is_some()
+                || options.label_position == Some(LabelPosition::Outside)
+            {
+                return Err(PieChartError::Validation(format!(
+                    "{} charts have no pie, so cannot be half, rose, sunburst or donut charts, have breakouts or outside labels, or be drawn another way too",
+                    shape
+                )));
+            }
+        }
+
+        let center_text = match options.center_text {
+            Some(ref text) if hole_ratio > 0.0 => {
+                let largest = displayed_percentages.iter().copied().fold(0.0, f64::max);
+
+                styles.push(format!(
+                    ".center-text{{font-family:{};font-weight:bold;fill:{};text-anchor:middle;dominant-baseline:central}}",
+                    font_family,
+                    theme.text_color()
+                ));
+                Some(
+                    text.replace("{total}", &formatter.value(total))
+                        .replace("{largest}", &formatter.percent(largest)),
+                )
+            }
+            Some(_) => {
+                warning!(
+                    self.log,
+                    "{}",
+                    strings.format(Message::CenterTextNeedsDonut, &[])
+                );
+                None
+            }
+            None => None,
+        };
+
+        let title_style = &options.title_style;
+        let title_position = title_style.position.unwrap_or_default();
+        let title_font_size = title_style.font_size.unwrap_or(DEFAULT_TITLE_FONT_SIZE);
+
+        if !(title_font_size.is_finite() && title_font_size > 0.0) {
+            return Err(PieChartError::Validation(format!(
+                "Title size must be greater than 0 (found {})",
+                title_font_size
+            )));
+        }
+
+        let title_font_family = Self::css_value(
+            "title font",
+            title_style.font_family.as_deref().unwrap_or(font_family),
+        )?;
+        let mut title_css = format!(
+            ".title{{font-family:{};font-size:{};",
+            title_font_family, title_font_size
+        );
+
+        if let Some(ref weight) = title_style.font_weight {
+            title_css.push_str(&format!(
+                "font-weight:{};",
+                Self::css_value("title weight", weight)?
+            ));
+        }
+
+        let title_color = title_style.color.as_deref().unwrap_or(theme.text_color());
+
+        color::validate("title", title_color)?;
+
+        title_css.push_str(&format!("fill:{};", title_color));
+
+        title_css.push_str("text-anchor:middle;}");
+        styles.insert(1, title_css);
+
+        // Rounded to keep the style readable
+        let subtitle_font_size = (title_font_size * SUBTITLE_FONT_SCALE * 100.0).round() / 100.0;
+
+        if cd.subtitle.is_some() {
+            styles.insert(
+                2,
+                format!(
+                    ".subtitle{{font-family:{};font-size:{};fill:{};text-anchor:middle;}}",
+                    title_font_family,
+                    subtitle_font_size,
+                    theme.text_color()
+                ),
+            );
+        }
+
+        // The title sits in the middle of its own gutter, which must be tall enough for it and any
+        // further lines and subtitle. At the top this replaces the top gutter, which otherwise
+        // shrinks to the size of the legend gutter. At the bottom it goes below the legend.
+        let title_extra_height =
+            (cd.title.split('\n').count() - 1) as f64 * title_font_size * TITLE_LINE_SPACING
+                + cd.subtitle.as_ref().map_or(0.0, |subtitle| {
+                    subtitle.split('\n').count() as f64 * subtitle_font_size * TITLE_LINE_SPACING
+                });
+        let title_gutter = gutter_size.max(title_font_size * 2.0 + title_extra_height);
+        let mut gutter = Gutter {
+            top: match title_position {
+                TitlePosition::Top => title_gutter,
+                _ => gutter_size.min(LEGEND_GUTTER),
+            },
+            bottom: match title_position {
+                TitlePosition::Bottom => gutter_size + title_gutter,
+                _ => gutter_size,
+            },
+            left: gutter_size,
+            right: gutter_size,
+        };
+        let label_position = options.label_position.unwrap_or_default();
+
+        if options.as_bars.unwrap_or_default() {
+            styles.push(format!(
+                ".bar-label{{fill:{};font-size:{};font-family:{};dominant-baseline:central}}",
+                theme.text_color(),
+                LABEL_FONT_SIZE,
+                font_family
+            ));
+        }
+
+        if breakout.is_some() {
+            gutter.right += BREAKOUT_GAP + pie_diameter * BREAKOUT_RATIO;
+            styles.push(format!(
+                ".connector{{fill:none;stroke:{};stroke-width:1}}",
+                theme.text_color()
+            ));
+        }
+
+        if label_position == LabelPosition::Outside {
+            // Make room beside the pie for the leaders and the widest label
+            let widest = wedges
+                .iter()
+                .filter_map(|wedge| wedge.label.as_deref())
+                .map(|label| metrics.width(label, LABEL_FONT_SIZE))
+                .fold(0.0, f64::max);
+            let callout_width =
+                callout::ELBOW_GAP + callout::LEADER_LENGTH + callout::TEXT_GAP + widest;
+
+            gutter.left = gutter.left.max(callout_width + LEGEND_GUTTER);
+            gutter.right = gutter.right.max(callout_width + LEGEND_GUTTER);
+            // The start of right to left text is on its right, so the anchors are swapped to keep
+            // the labels beside the pie
+            let (start, end) = if rtl {
+                ("end", "start")
+            } else {
+                ("start", "end")
+            };
+
+            styles.push(format!(
+                ".callout{{fill:{};font-size:{};font-family:{};dominant-baseline:central;text-anchor:{}}}",
+                theme.text_color(),
+                LABEL_FONT_SIZE,
+                font_family,
+                start
+            ));
+            styles.push(format!(".callout.left{{text-anchor:{}}}", end));
+            styles.push(format!(
+                ".leader{{fill:none;stroke:{};stroke-width:1}}",
+                theme.text_color()
+            ));
+        }
+
+        let legend_gutter = Gutter {
+            top: LEGEND_GUTTER,
+            bottom: LEGEND_GUTTER,
+            left: LEGEND_GUTTER,
+            right: LEGEND_GUTTER,
+        };
+
+        let legends: Vec<String> = wedges
+            .iter()
+            .map(|wedge| match options.max_label_len {
+                Some(max_chars) => text::truncate(&wedge.legend, max_chars),
+                None => wedge.legend.clone(),
+            })
+            .collect();
+        let legend_width = gutter.left + pie_diameter + gutter.right - legend_gutter.width();
+        let legend_columns = match options.legend_columns {
+            Some(0) => {
+                return Err(PieChartError::Validation(
+                    "Legend columns must be greater than 0".to_string(),
+                ))
+            }
+            Some(columns) => columns.min(wedges.len()),
+            None => {
+                // As many columns as fit the widest label on one line, unless that would make them
+                // wider than the maximum, in which case the longer labels wrap
+                let widest = legends
+                    .iter()
+                    .map(|legend| metrics.width(legend, LEGEND_FONT_SIZE))
+                    .fold(0.0, f64::max);
+                let columns = (legend_width / (widest + LEGEND_GUTTER)) as usize;
+
+                columns
+                    .max((legend_width / MAX_LEGEND_ENTRY_WIDTH) as usize)
+                    .clamp(1, wedges.len())
+            }
+        };
+        // Each legend entry gets an equal share of the width, less a gap before the next one
+        let entry_width = legend_width / legend_columns as f64 - LEGEND_GUTTER;
+
+        for (wedge, legend) in wedges.iter_mut().zip(legends) {
+            wedge.legend_lines = match options.max_label_len {
+                Some(_) => vec![legend],
+                None => metrics.wrap(&legend, entry_width, LEGEND_FONT_SIZE),
+            };
+        }
+
+        if options.animate.unwrap_or_default() {
+            // Wedges grow from the center one after another, then the labels fade in
+            styles.push(format!(
+                ".pie path{{transform-origin:{}px {}px;animation:pie-grow 0.6s ease-out both}}",
+                gutter.left + pie_diameter / 2.0,
+                gutter.top + pie_diameter / 2.0
+            ));
+
+            let delay = 0.6 / wedges.len() as f64;
+
+            for (index, wedge) in wedges.iter().enumerate() {
+                styles.push(format!(
+                    ".pie .{}{{animation-delay:{:.3}s}}",
+                    wedge.class,
+                    index as f64 * delay
+                ));
+            }
+
+            styles.push(
+                ".pie text{animation:pie-fade 0.4s ease-in 1.2s both}\n\
+                 @keyframes pie-grow{from{transform:scale(0)}to{transform:scale(1)}}\n\
+                 @keyframes pie-fade{from{opacity:0}to{opacity:1}}\n\
+                 @media (prefers-reduced-motion:reduce){.pie path,.pie text{animation:none !important}}"
+                    .to_string(),
+            );
+        }
+
+        if options.replace_styles.unwrap_or_default() {
+            styles.clear();
+        }
+
+        if let (Some(path), Some(font)) = (&options.embed_font, &embedded_font) {
+            let extension = path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+            let (mime_type, format) = match extension.as_deref() {
+                Some("woff2") => ("font/woff2", "woff2"),
+                Some("woff") => ("font/woff", "woff"),
+                Some("ttf") => ("font/ttf", "truetype"),
+                Some("otf") => ("font/otf", "opentype"),
+                _ => {
+                    return Err(PieChartError::Validation(format!(
+                        "Font file '{}' must be a .woff2, .woff, .ttf or .otf file",
+                        path.to_string_lossy()
+                    )))
+                }
+            };
+
+            styles.push(format!(
+                "@font-face{{font-family:\"{}\";src:url(data:{};base64,{}) format(\"{}\")}}",
+                // The first family named by --font, or the file name
+                font_family
+                    .split(',')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .trim_matches(['"', '\'']),
+                mime_type,
+                base64::encode(font),
+                format
+            ));
+        }
+
+        styles.extend(cd.styles.iter().cloned());
+
+        if let Some(ref path) = options.css {
+            styles.push(fs::read_to_string(path).map_err(|e| PieChartError::Io {
+                message: format!("Unable to read CSS file '{}'", path.to_string_lossy()),
+                source: e,
+            })?);
+        }
+
+        debug!(
+            self.log,
+            "Pie is {} across with a legend of {} columns and {} high",
+            pie_diameter,
+            legend_columns,
+            legend_height
+        );
+
+        // A broken out wedge faces the breakout pie, at 3 o'clock
+        let start_angle = match breakout {
+            Some(ref breakout) => {
+                let sweep = if options.counterclockwise.unwrap_or_default() {
+                    -2.0
+                } else {
+                    2.0
+                } * PI;
+                let before: f64 = wedges[..breakout.index]
+                    .iter()
+                    .map(|wedge| wedge.percentage)
+                    .sum();
+
+                -sweep * (before + wedges[breakout.index].percentage / 2.0)
+            }
+            None => options
+                .start_angle
+                .map_or(-FRAC_PI_2, |degrees| degrees.to_radians()),
+        };
+
+        Ok(RenderData {
+            title: cd.title.to_string(),
+            subtitle: cd.subtitle.clone(),
+            subtitle_font_size,
+            caption: cd.caption.clone(),
+            background: background.to_string(),
+            background_rect: options.background_rect.unwrap_or_default(),
+            title_position,
+            label_position,
+            title_gutter,
+            title_font_size,
+            title_font_weight: title_style.font_weight.clone(),
+            title_color: title_color.to_string(),
+            text_color: theme.text_color().to_string(),
+            wedge_stroke,
+            gutter,
+            pie_diameter,
+            half: options.half.unwrap_or_default(),
+            metrics,
+            start_angle,
+            counterclockwise: options.counterclockwise.unwrap_or_default(),
+            rose: options.rose.unwrap_or_default(),
+            hole_ratio,
+            center_text,
+            legend_gutter,
+            legend_height,
+            legend_rect_corner_radius: 3.0,
+            legend_columns,
+            legend: !options.no_legend.unwrap_or_default(),
+            styles,
+            rings: wedges.iter().map(WedgeData::depth).max().unwrap_or(0),
+            legend_order: Self::legend_order(&wedges, options.legend_sort.unwrap_or_default()),
+            wedges,
+            id_prefix: String::new(),
+            strings,
+            rtl,
+            series: vec![],
+            series_wedges: vec![],
+            animation: None,
+            breakout,
+            waffle: options.waffle.unwrap_or_default(),
+            bars: options.as_bars.unwrap_or_default(),
+            treemap: options.treemap.unwrap_or_default(),
+        })
+    }
+
+    /// Process a chart of several series as rings around each other, laid out as the first.
+    /// The first series also has any keys of the others that it lacks, with a value of zero, so
+    /// that the legend lists every key and the other rings can take their classes, and so their
+    /// colors, from it.
+    fn process_series(
+        self: &Self,
+        cd: &ChartData,
+        options: &ChartOptions,
+    ) -> Result<RenderData, PieChartError> {
+        // The series themselves are checked as they are processed, once negatives are adjusted
+        if !cd.items.is_empty() {
+            cd.validate()?;
+        }
+
+        if options.sunburst.unwrap_or_default()
+            || options.rose.unwrap_or_default()
+            || options.breakout.is_some()
+            || options.waffle.unwrap_or_default()
+            || options.as_bars.unwrap_or_default()
+            || options.treemap.unwrap_or_default()
+            || options.label_position == Some(LabelPosition::Outside)
+        {
+            return Err(PieChartError::Validation(
+                "Series are drawn as rings, so cannot be drawn as a sunburst, rose, waffle, bars or treemap, broken out or with labels outside the pie"
+                    .to_string(),
+            ));
+        }
+
+        // The first item of each key, and the first color, link and class given for it
+        let mut keys: Vec<ItemData> = vec![];
+
+        for item in cd.series.iter().flat_map(|series| &series.items) {
+            match keys.iter_mut().find(|key| key.key == item.key) {
+                Some(key) => {
+                    key.color = key.color.take().or(item.color.clone());
+                    key.href = key.href.take().or(item.href.clone());
+                    key.class = key.class.take().or(item.class.clone());
+                }
+                None => keys.push(ItemData {
+                    value: 0.0,
+                    children: vec![],
+                    ..item.clone()
+                }),
+            }
+        }
+
+        let first = &cd.series[0];
+        let items = first
+            .items
+            .iter()
+            .map(|item| {
+                let key = keys.iter().find(|key| key.key == item.key);
+
+                ItemData {
+                    color: key.and_then(|key| key.color.clone()),
+                    href: key.and_then(|key| key.href.clone()),
+                    class: key.and_then(|key| key.class.clone()),
+                    ..item.clone()
+                }
+            })
+            .chain(
+                keys.iter()
+                    .filter(|key| !first.items.iter().any(|item| item.key == key.key))
+                    .cloned(),
+            )
+            .collect();
+        // The legend is of every series, so it has no values unless they are asked for
+        let options = ChartOptions {
+            legend_values: options.legend_values.or(Some(LegendValues::None)),
+            ..options.clone()
+        };
+        let chart = |items: Vec<ItemData>| ChartData {
+            items,
+            series: vec![],
+            ..cd.clone()
+        };
+        let mut rd = self.process_chart_data(&chart(items), &options)?;
+
+        for series in &cd.series[1..] {
+            let items = series
+                .items
+                .iter()
+                .map(|item| ItemData {
+                    class: rd
+                        .wedges
+                        .iter()
+                        .find(|wedge| wedge.key == item.key)
+                        .map(|wedge| wedge.class.clone())
+                        .or(item.class.clone()),
+                    ..item.clone()
+                })
+                .collect();
+
+            rd.series_wedges
+                .push(self.process_chart_data(&chart(items), &options)?.wedges);
+        }
+
+        rd.series = cd.series.iter().map(|series| series.name.clone()).collect();
+        rd.rings = cd.series.len() - 1;
+
+        Ok(rd)
+    }
+
+    /// The index of the wedge of each legend entry, sorted without moving the wedges
+    fn legend_order(wedges: &[WedgeData], sort: SortOrder) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..wedges.len()).collect();
+
+        match sort {
+            SortOrder::ValueDesc => {
+                order.sort_by(|&a, &b| wedges[b].value.total_cmp(&wedges[a].value))
+            }
+            SortOrder::ValueAsc => {
+                order.sort_by(|&a, &b| wedges[a].value.total_cmp(&wedges[b].value))
+            }
+            SortOrder::Key => order.sort_by(|&a, &b| wedges[a].key.cmp(&wedges[b].key)),
+            SortOrder::None => (),
+        }
+
+        order
+    }
+
+    /// One of eight hatch and dot patterns, repeating every eight wedges
+    fn pattern(id: &str, index: usize, pattern: &WedgePattern) -> element::Pattern {
+        const SIZE: f64 = 8.0;
+        let lines = |d: &str| {
+            element::Path::new()
+                .set("d", d)
+                .set("stroke", pattern.foreground.as_str())
+                .set("stroke-width", 1.5)
+        };
+        let dots = |radius: f64, offsets: &[(f64, f64)]| {
+            offsets
+                .iter()
+                .map(|(x, y)| {
+                    element::Circle::new()
+                        .set("cx", *x)
+                        .set("cy", *y)
+                        .set("r", radius)
+                        .set("fill", pattern.foreground.as_str())
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut element = element::Pattern::new()
+            .set("id", id)
+            .set("patternUnits", "userSpaceOnUse")
+            .set("width", SIZE)
+            .set("height", SIZE)
+            .add(
+                element::Rectangle::new()
+                    .set("width", SIZE)
+                    .set("height", SIZE)
+                    .set("fill", pattern.background.as_str()),
+            );
+
+        match index % 8 {
+            0 => element = element.add(lines("M0,8 L8,0 M-2,2 L2,-2 M6,10 L10,6")),
+            1 => element = element.add(lines("M0,0 L8,8 M-2,6 L2,10 M6,-2 L10,2")),
+            2 => element = element.add(lines("M0,4 L8,4")),
+            3 => element = element.add(lines("M4,0 L4,8")),
+            4 => element = element.add(lines("M0,4 L8,4 M4,0 L4,8")),
+            5 => {
+                for dot in dots(1.5, &[(2.0, 2.0), (6.0, 6.0)]) {
+                    element = element.add(dot);
+                }
+            }
+            6 => element = element.add(lines("M0,8 L8,0 M0,0 L8,8")),
+            _ => {
+                for dot in dots(2.5, &[(4.0, 4.0)]) {
+                    element = element.add(dot);
+                }
+            }
+        }
+
+        element
+    }
+
+    /// Add a closed wedge from angle `a` to `b` to the path data, with a hole of `inner_radius`
+    fn wedge(
+        data: Data,
+        x_center: f64,
+        y_center: f64,
+        radius: f64,
+        inner_radius: f64,
+        a: f64,
+        b: f64,
+    ) -> Data {
+        // Counterclockwise wedges are the same shape as clockwise ones from their end
+        let (a, b) = if b < a { (b, a) } else { (a, b) };
+        let large_arc = if b - a > PI { 1.0 } else { 0.0 };
+        let mut data = data
+            .move_to((x_center + radius * a.cos(), y_center + radius * a.sin()))
+            .elliptical_arc_to((
+                radius,
+                radius,
+                0.0,
+                large_arc,
+                1.0,
+                x_center + radius * b.cos(),
+                y_center + radius * b.sin(),
+            ));
+
+        if inner_radius > 0.0 {
+            data = data
+                .line_to((
+                    x_center + inner_radius * b.cos(),
+                    y_center + inner_radius * b.sin(),
+                ))
+                .elliptical_arc_to((
+                    inner_radius,
+                    inner_radius,
+                    0.0,
+                    large_arc,
+                    0.0,
+                    x_center + inner_radius * a.cos(),
+                    y_center + inner_radius * a.sin(),
+                ));
+        } else {
+            data = data.line_to((x_center, y_center));
+        }
+
+        data.close()
+    }
+
+    /// Add a closed circle to the path data, drawn clockwise if `sweep` is 1.0
+    fn circle(data: Data, x_center: f64, y_center: f64, radius: f64, sweep: f64) -> Data {
+        data.move_to((x_center, y_center - radius))
+            .elliptical_arc_to((radius, radius, 0.0, 1.0, sweep, x_center, y_center + radius))
+            .elliptical_arc_to((radius, radius, 0.0, 1.0, sweep, x_center, y_center - radius))
+            .close()
+    }
+
+    /// The path of a wedge from angle `a` to `b` in a sunburst ring
+    fn wedge_path(rd: &RenderData, wedge: &WedgeData, a: f64, b: f64, level: usize) -> Data {
+        let (x_center, y_center) = rd.center();
+        let (inner_radius, radius) = rd.wedge_radii(wedge, level);
+
+        Self::arc_path(
+            x_center,
+            y_center,
+            inner_radius,
+            radius,
+            rd.is_whole(wedge),
+            a,
+            b,
+        )
+    }
+
+    /// The path from angle `a` to `b` between two radii, or of the whole ring
+    fn arc_path(
+        x_center: f64,
+        y_center: f64,
+        inner_radius: f64,
+        radius: f64,
+        whole: bool,
+        a: f64,
+        b: f64,
+    ) -> Data {
+        if whole {
+            // An arc cannot start and end at the same point, so draw whole circles in two
+            // halves, with the hole going the other way so that it is not filled
+            let data = Self::circle(Data::new(), x_center, y_center, radius, 1.0);
+
+            if inner_radius > 0.0 {
+                Self::circle(data, x_center, y_center, inner_radius, 0.0)
+            } else {
+                data
+            }
+        } else {
+            Self::wedge(Data::new(), x_center, y_center, radius, inner_radius, a, b)
+        }
+    }
+
+    /// Add the wedges of a sunburst ring to the pie, each followed by the wedges outside it.
+    /// The wedges of the pie itself are the ring at level 0.
+    fn append_wedges(
+        pie: &mut element::Group,
+        rd: &RenderData,
+        wedges: &[WedgeData],
+        start: f64,
+        level: usize,
+    ) {
+        let mut a = start;
+        let animation = rd.animation.as_ref().filter(|_| level == 0);
+
+        for (index, wedge) in wedges.iter().enumerate() {
+            let b = a + rd.span(wedge);
+
+            // Empty wedges are only shown in the legend, unless they grow in a later frame
+            if wedge.percentage == 0.0 && animation.is_none() {
+                a = b;
+                continue;
+            }
+
+            let data = Self::wedge_path(rd, wedge, a, b, level);
+            // The values of the first frame would be wrong for the others
+            let tooltip = match animation {
+                Some(_) => wedge.key.clone(),
+                None => rd.wedge_tooltip(wedge, level),
+            };
+            let mut path = element::Path::new()
+                .set("class", wedge.class.as_str())
+                .set("d", data)
+                .set("aria-label", tooltip.as_str())
+                .add(element::Title::new(tooltip.as_str()));
+
+            if let Some(animation) = animation {
+                path = path.add(animation.path(index));
+            }
+
+            match wedge.href {
+                Some(ref href) => {
+                    pie.append(element::Anchor::new().set("href", href.as_str()).add(path))
+                }
+                None => pie.append(path),
+            }
+
+            Self::append_wedges(pie, rd, &wedge.children, a, level + 1);
+            a = b;
+        }
+    }
+
+    /// The squares of a waffle chart, grouped by wedge so that they share its class and tooltip
+    fn waffle(rd: &RenderData) -> element::Group {
+        let mut waffle = element::Group::new().set("class", "waffle");
+        let cells = rd.waffle_cells();
+        let size = rd.waffle_cell_size();
+
+        for (index, wedge) in rd.wedges.iter().enumerate() {
+            if !cells.iter().any(|(cell_index, _, _)| *cell_index == index) {
+                continue;
+            }
+
+            let squares = cells
+                .iter()
+                .filter(|(cell_index, _, _)| *cell_index == index)
+                .fold(
+                    element::Group::new()
+                        .set("class", wedge.class.as_str())
+                        .set("aria-label", wedge.tooltip())
+                        .add(element::Title::new(wedge.tooltip())),
+                    |squares, &(_, x, y)| {
+                        squares.add(
+                            element::Rectangle::new()
+                                .set("x", x)
+                                .set("y", y)
+                                .set("rx", rd.legend_rect_corner_radius)
+                                .set("ry", rd.legend_rect_corner_radius)
+                                .set("width", size)
+                                .set("height", size),
+                        )
+                    },
+                );
+
+            match wedge.href {
+                Some(ref href) => waffle.append(
+                    element::Anchor::new()
+                        .set("href", href.as_str())
+                        .add(squares),
+                ),
+                None => waffle.append(squares),
+            }
+        }
+
+        waffle
+    }
+
+    /// The bars of a bar chart, each followed by the label of its wedge
+    fn bars(rd: &RenderData) -> element::Group {
+        let mut group = element::Group::new().set("class", "bars");
+
+        for (index, x, y, width, height) in rd.bars() {
+            let wedge = &rd.wedges[index];
+            let bar = element::Rectangle::new()
+                .set("class", wedge.class.as_str())
+                .set("x", x)
+                .set("y", y)
+                .set("rx", rd.legend_rect_corner_radius)
+                .set("ry", rd.legend_rect_corner_radius)
+                .set("width", width)
+                .set("height", height)
+                .set("aria-label", wedge.tooltip())
+                .add(element::Title::new(wedge.tooltip()));
+
+            match wedge.href {
+                Some(ref href) => {
+                    group.append(element::Anchor::new().set("href", href.as_str()).add(bar))
+                }
+                None => group.append(bar),
+            }
+
+            if let Some(ref label) = wedge.label {
+                group.append(
+                    element::Text::new(label.as_str())
+                        .set("class", "bar-label")
+                        .set("x", x + width + callout::TEXT_GAP)
+                        .set("y", y + height / 2.0),
+                );
+            }
+        }
+
+        group
+    }
+
+    /// The rectangles of a treemap, each with the label of its wedge in the middle if it fits
+    fn treemap(rd: &RenderData) -> element::Group {
+        let mut group = element::Group::new().set("class", "treemap");
+
+        for (index, x, y, width, height) in rd.treemap() {
+            let wedge = &rd.wedges[index];
+            let rect = element::Rectangle::new()
+                .set("class", wedge.class.as_str())
+                .set("x", x)
+                .set("y", y)
+                .set("width", width)
+                .set("height", height)
+                .set("aria-label", wedge.tooltip())
+                .add(element::Title::new(wedge.tooltip()));
+
+            match wedge.href {
+                Some(ref href) => {
+                    group.append(element::Anchor::new().set("href", href.as_str()).add(rect))
+                }
+                None => group.append(rect),
+            }
+
+            if let Some(label) = wedge
+                .label
+                .as_ref()
+                .filter(|label| rd.fits_in(label, width, height))
+            {
+                group.append(
+                    element::Text::new(label.as_str())
+                        .set(
+                            "class",
+                            if wedge.label_inverse {
+                                "labels inverse"
+                            } else {
+                                "labels"
+                            },
+                        )
+                        .set("x", x + width / 2.0)
+                        .set("y", y + height / 2.0),
+                );
+            }
+        }
+
+        group
+    }
+
+    /// The breakout pie, with its labels and the lines joining it to the broken out wedge
+    fn breakout(rd: &RenderData) -> element::Group {
+        let mut group = element::Group::new().set("class", "breakout");
+        let ((x_center, y_center), inner_radius, radius) = rd.breakout_circle();
+
+        for (from, to) in rd.connectors() {
+            group.append(
+                element::Line::new()
+                    .set("class", "connector")
+                    .set("x1", from.0)
+                    .set("y1", from.1)
+                    .set("x2", to.0)
+                    .set("y2", to.1),
+            );
+        }
+
+        for (wedge, a, b) in rd.breakout_wedges() {
+            if wedge.percentage == 0.0 {
+                continue;
+            }
+
+            let whole = wedge.percentage >= FULL_PERCENTAGE;
+            let path = element::Path::new()
+                .set("class", wedge.class.as_str())
+                .set(
+                    "d",
+                    Self::arc_path(x_center, y_center, inner_radius, radius, whole, a, b),
+                )
+                .set("aria-label", wedge.tooltip())
+                .add(element::Title::new(wedge.tooltip()));
+
+            match wedge.href {
+                Some(ref href) => {
+                    group.append(element::Anchor::new().set("href", href.as_str()).add(path))
+                }
+                None => group.append(path),
+            }
+        }
+
+        for (wedge, a, b) in rd.breakout_wedges() {
+            if let Some(label) = wedge.label.as_ref().filter(|_| wedge.percentage > 0.0) {
+                let mid = (a + b) / 2.0;
+                let distance = if wedge.percentage >= FULL_PERCENTAGE && inner_radius == 0.0 {
+                    0.0
+                } else {
+                    (radius + inner_radius) / 2.0
+                };
+
+                group.append(
+                    element::Text::new(label.as_str())
+                        .set(
+                            "class",
+                            if wedge.label_inverse {
+                                "labels inverse"
+                            } else {
+                                "labels"
+                            },
+                        )
+                        .set("x", x_center + distance * mid.cos())
+                        .set("y", y_center + distance * mid.sin()),
+                );
+            }
+        }
+
+        group
+    }
+
+    /// The legend, with a color block and the legend text of each wedge
+    fn legend(rd: &RenderData) -> element::Group {
+        let mut legend = element::Group::new();
+        let entries = rd.legend_entries();
+
+        for (wedge, &(x, y)) in rd.legend_wedges().into_iter().zip(&entries) {
+            let block = element::Rectangle::new()
+                .set("class", wedge.class.as_str())
+                .set("x", x)
+                .set("y", y)
+                .set("rx", rd.legend_rect_corner_radius)
+                .set("ry", rd.legend_rect_corner_radius)
+                .set("width", rd.legend_height)
+                .set("height", rd.legend_height)
+                .add(element::Title::new(match rd.animation {
+                    Some(_) => wedge.key.clone(),
+                    None => wedge.tooltip(),
+                }));
+
+            let y = y + rd.legend_height * 2.0;
+            // Right to left text starts from the right edge of the block
+            let x = if rd.rtl { x + rd.legend_height } else { x };
+            // Wrapped lines after the first are placed below it
+            let text = wedge.legend_lines.iter().enumerate().skip(1).fold(
+                element::Text::new(wedge.legend_lines[0].as_str())
+                    .set("class", "legend")
+                    .set("x", x)
+                    .set("y", y),
+                |text, (line_index, line)| {
+                    text.add(
+                        element::TSpan::new(line.as_str())
+                            .set("x", x)
+                            .set("y", y + line_index as f64 * LEGEND_LINE_HEIGHT),
+                    )
+                },
+            );
+
+            match wedge.href {
+                Some(ref href) => legend.append(
+                    element::Anchor::new()
+                        .set("href", href.as_str())
+                        .add(block)
+                        .add(text),
+                ),
+                None => {
+                    legend.append(block);
+                    legend.append(text);
+                }
+            }
+        }
+
+        legend
+    }
+
+    fn render_chart(self: &Self, rd: &RenderData) -> Result<Document, PieChartError> {
+        let (width, height) = rd.size();
+
+        debug!(self.log, "Chart is {} by {}", width, height);
+
+        let (x_center, y_center) = rd.center();
+        // The accessible name of the chart is the title on one line
+        let name = rd.title.replace('\n', " ");
+        let mut document = Document::new()
+            .set("xmlns", "http://www.w3.org/2000/svg")
+            .set("width", width)
+            .set("height", height)
+            .set("viewBox", format!("0 0 {} {}", width, height))
+            .set("role", "img")
+            .set("aria-label", name.as_str());
+
+        if rd.rtl {
+            document = document.set("direction", "rtl");
+        }
+
+        if rd.has_background() && !rd.background_rect {
+            document = document.set("style", format!("background-color: {};", rd.background));
+        }
+
+        let style = element::Style::new(rd.styles.join("\n"));
+        let (start, _) = rd.angles();
+        let mut pie = element::Group::new().set("class", "pie");
+
+        if rd.waffle {
+            pie.append(Self::waffle(rd));
+        } else if rd.bars {
+            pie.append(Self::bars(rd));
+        } else if rd.treemap {
+            pie.append(Self::treemap(rd));
+        } else {
+            for (level, wedges) in rd.rings() {
+                Self::append_wedges(&mut pie, rd, wedges, start, level);
+            }
+        }
+
+        for (level, wedges) in rd.rings() {
+            let mut a = start;
+
+            for wedge in wedges {
+                let b = a + rd.span(wedge);
+                let (inner_radius, radius) = rd.wedge_radii(wedge, level);
+
+                if let Some(label) = wedge.label.as_ref().filter(|_| {
+                    wedge.percentage > 0.0
+                        && rd.label_position == LabelPosition::Inside
+                        && !rd.waffle
+                        && !rd.bars
+                        && !rd.treemap
+                }) {
+                    let label_radius = if rd.is_whole(wedge) && inner_radius == 0.0 {
+                        0.0
+                    } else {
+                        (radius + inner_radius) / 2.0
+                    };
+                    let mid = (a + b) / 2.0;
+
+                    pie.append(
+                        element::Text::new(label.as_str())
+                            .set(
+                                "class",
+                                if wedge.label_inverse {
+                                    "labels inverse"
+                                } else {
+                                    "labels"
+                                },
+                            )
+                            .set("x", x_center + label_radius * mid.cos())
+                            .set("y", y_center + label_radius * mid.sin()),
+                    );
+                }
+
+                a = b;
+            }
+        }
+
+        if rd.breakout.is_some() {
+            pie.append(Self::breakout(rd));
+        }
+
+        for (index, callout) in rd.callouts() {
+            let (x, y) = callout.end;
+
+            pie.append(element::Polyline::new().set("class", "leader").set(
+                "points",
+                format!(
+                    "{},{} {},{} {},{}",
+                    callout.anchor.0, callout.anchor.1, callout.elbow.0, callout.elbow.1, x, y
+                ),
+            ));
+            pie.append(
+                element::Text::new(rd.wedges[index].label.as_deref().unwrap_or_default())
+                    .set(
+                        "class",
+                        if callout.left {
+                            "callout left"
+                        } else {
+                            "callout"
+                        },
+                    )
+                    .set(
+                        "x",
+                        if callout.left {
+                            x - callout::TEXT_GAP
+                        } else {
+                            x + callout::TEXT_GAP
+                        },
+                    )
+                    .set("y", y),
+            );
+        }
+
+        let title_lines = rd.title_lines();
+        // Lines after the first of the title and of the subtitle are tspans of their first line
+        let mut titles: Vec<element::Text> = vec![];
+
+        for (index, &(line, y, is_subtitle)) in title_lines.iter().enumerate() {
+            if index > 0 && title_lines[index - 1].2 == is_subtitle {
+                let text = titles
+                    .pop()
+                    .unwrap()
+                    .add(element::TSpan::new(line).set("x", width / 2.0).set("y", y));
+
+                titles.push(text);
+            } else {
+                titles.push(
+                    element::Text::new(line)
+                        .set("class", if is_subtitle { "subtitle" } else { "title" })
+                        .set("x", width / 2.0)
+                        .set("y", y),
+                );
+            }
+        }
+
+        // Frames with titles of their own show them in turn, in place of the first line
+        if let Some(animation) = rd.animation.as_ref().filter(|a| !a.titles.is_empty()) {
+            if let Some(&(_, y, false)) = title_lines.first() {
+                titles.remove(0);
+
+                for (index, title) in animation.titles.iter().enumerate().rev() {
+                    titles.insert(
+                        0,
+                        element::Text::new(title.as_str())
+                            .set("class", "title")
+                            .set("x", width / 2.0)
+                            .set("y", y)
+                            .set("opacity", if index == 0 { 1 } else { 0 })
+                            .add(animation.title(index)),
+                    );
+                }
+            }
+        }
+
+        let legend = Self::legend(rd);
+
+        // Screen readers expect the title and description to come first
+        document.append(element::Title::new(name.as_str()));
+        document.append(
+            element::Description::new().add(svg::node::Text::new(
+                rd.strings.format(
+                    Message::Description,
+                    &[
+                        &rd.wedges.len(),
+                        &rd.wedges
+                            .iter()
+                            .map(|wedge| wedge.legend.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ],
+                ),
+            )),
+        );
+        document.append(style);
+
+        if rd.has_background() && rd.background_rect {
+            document.append(
+                element::Rectangle::new()
+                    .set("class", "background")
+                    .set("width", width)
+                    .set("height", height)
+                    .set("fill", rd.background.as_str()),
+            );
+        }
+
+        if rd.wedges.iter().any(|wedge| wedge.pattern.is_some()) {
+            let mut defs = element::Definitions::new();
+
+            for (index, wedge) in rd.wedges.iter().enumerate() {
+                if let Some(ref pattern) = wedge.pattern {
+                    defs.append(Self::pattern(
+                        &format!("{}pattern-{}", rd.id_prefix, index),
+                        index,
+                        pattern,
+                    ));
+                }
+            }
+
+            document.append(defs);
+        }
+
+        document.append(pie);
+
+        if let Some(ref text) = rd.center_text {
+            // As large as fits across the hole, less a margin
+            let (hole_radius, _) = rd.ring_radii(0);
+            let size = CENTER_FONT_SIZE
+                .min(hole_radius * 1.6 / rd.metrics.width(text, 1.0).max(f64::EPSILON));
+
+            document.append(
+                element::Text::new(text.as_str())
+                    .set("class", "center-text")
+                    .set("x", x_center)
+                    // Semicircles have only the top half of the hole
+                    .set(
+                        "y",
+                        if rd.half {
+                            y_center - size / 2.0
+                        } else {
+                            y_center
+                        },
+                    )
+                    .set("font-size", size),
+            );
+        }
+
+        for title in titles {
+            document.append(title);
+        }
+        document.append(legend);
+
+        let caption_lines = rd.caption_lines();
+
+        if let Some(&(first_line, first_y)) = caption_lines.first() {
+            let x = if rd.rtl {
+                width - rd.legend_gutter.right
+            } else {
+                rd.legend_gutter.left
+            };
+
+            document.append(
+                caption_lines.iter().skip(1).fold(
+                    element::Text::new(first_line)
+                        .set("class", "caption")
+                        .set("x", x)
+                        .set("y", first_y),
+                    |text, &(line, y)| text.add(element::TSpan::new(line).set("x", x).set("y", y)),
+                ),
+            );
+        }
+
+        Ok(document)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::{OsStr, OsString};
+
+    /// A scratch directory for a test, removed when the test ends, with an empty config file so
+    /// that the tool does not read the config file of the machine running the tests
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!("pie_chart_{}_{}", name, process::id()));
+
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("config.toml"), "").unwrap();
+            TempDir(dir)
+        }
+
+        fn join(self: &Self, path: impl AsRef<Path>) -> PathBuf {
+            self.0.join(path)
+        }
+
+        /// Run the tool with the arguments, and the config file of the directory
+        fn run(
+            self: &Self,
+            log: &dyn PieChartLog,
+            args: impl IntoIterator<Item = impl Into<OsString>>,
+        ) -> Result<(), PieChartError> {
+            PieChartTool::new(log).run(
+                [OsString::new()]
+                    .into_iter()
+                    .chain(args.into_iter().map(Into::into))
+                    .chain(["--config".into(), self.join("config.toml").into()]),
+            )
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(self: &mut Self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// A log of what the tool outputs, with warnings starting `warning: `, and of the records of
+    /// the JSON log format
+    #[derive(Default)]
+    struct RecordingLog(RefCell<Vec<String>>);
+
+    impl PieChartLog for RecordingLog {
+        fn output(self: &Self, args: Arguments) {
+            self.0.borrow_mut().push(args.to_string());
+        }
+        fn warning(self: &Self, args: Arguments) {
+            self.0.borrow_mut().push(format!("warning: {}", args));
+        }
+        fn error(self: &Self, args: Arguments) {
+            self.0.borrow_mut().push(format!("error: {}", args));
+        }
+        fn record(self: &Self, record: &str) {
+            self.0.borrow_mut().push(record.to_string());
+        }
+    }
+
+    #[test]
+    fn basic_test() {
+        struct TestLogger;
+
+        impl TestLogger {
+            fn new() -> TestLogger {
+                TestLogger {}
+            }
+        }
+
+        impl PieChartLog for TestLogger {
+            fn output(self: &Self, _args: Arguments) {}
+            fn warning(self: &Self, _args: Arguments) {}
+            fn error(self: &Self, _args: Arguments) {}
+        }
+
+        let logger = TestLogger::new();
+        let mut tool = PieChartTool::new(&logger);
+        let args: Vec<std::ffi::OsString> = vec!["".into(), "--help".into()];
+
+        tool.run(args).unwrap();
+    }
+
+    #[test]
+    fn out_template_test() {
+        let dir = TempDir::new("lines");
+        let input = dir.join("charts.ndjson");
+
+        fs::write(
+            &input,
+            "{\"title\": \"Acme Corp\", \"items\": [{\"key\": \"a\", \"value\": 1}]}\n\n{\"title\": \"b/c\", \"items\": [{\"key\": \"a\", \"value\": 1}]}\n",
+        )
+        .unwrap();
+
+        let template = dir.join("out/{n}-{title}.svg");
+        let result = dir.run(
+            &SilentLog,
+            [
+                OsString::from("--out-template"),
+                template.into(),
+                input.into(),
+            ],
+        );
+        let names: Vec<_> = fs::read_dir(dir.join("out"))
+            .map(|entries| entries.map(|e| e.unwrap().file_name()).collect())
+            .unwrap_or_default();
+
+        result.unwrap();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"1-Acme_Corp.svg".into()));
+        assert!(names.contains(&"3-b_c.svg".into()));
+    }
+
+    #[test]
+    fn subcommands_test() {
+        let dir = TempDir::new("commands");
+        let input = dir.join("costs.json5");
+        let log = RecordingLog::default();
+        let run = |args: &[&str]| dir.run(&log, args);
+
+        fs::write(&input, "{title: 'Costs', items: [{key: 'a', value: 1}]}").unwrap();
+
+        let input = input.to_str().unwrap();
+        // A bare input and output file is the same as the render subcommand
+        let results = [
+            run(&["render", input, dir.join("a.svg").to_str().unwrap()]),
+            run(&[input, dir.join("b.svg").to_str().unwrap()]),
+            run(&["validate", input]),
+            run(&["palette", "--palette", "tableau10", "--count", "2"]),
+        ];
+        let written = ["a.svg", "b.svg"].map(|name| dir.join(name).exists());
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(written, [true, true]);
+        assert_eq!(log.0.borrow()[1..], ["#4e79a7", "#f28e2b"]);
+        assert!(matches!(
+            run(&["--validate", input]),
+            Err(PieChartError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn auto_out_test() {
+        let dir = TempDir::new("auto_out");
+        let input = dir.join("costs.json5");
+
+        fs::write(&input, "{title: 'Costs', items: [{key: 'a', value: 1}]}").unwrap();
+
+        let run = |args: &[&str], input: &Path| {
+            dir.run(
+                &SilentLog,
+                ["--auto-out"]
+                    .iter()
+                    .chain(args)
+                    .map(OsString::from)
+                    .chain([input.into()]),
+            )
+        };
+        // An SVG input would be overwritten by SVG output
+        let results = [
+            run(&[], &input),
+            run(&["--format", "html"], &input),
+            run(&[], &dir.join("costs.svg")),
+        ];
+        let written = ["costs.svg", "costs.html"].map(|name| dir.join(name).exists());
+
+        assert!(results[0].is_ok() && results[1].is_ok());
+        assert_eq!(written, [true, true]);
+        assert!(matches!(results[2], Err(PieChartError::Usage(_))));
+    }
+
+    #[test]
+    fn force_test() {
+        let dir = TempDir::new("force");
+        let (input, output) = (dir.join("costs.json5"), dir.join("costs.svg"));
+
+        fs::write(&input, "{title: 'Costs', items: [{key: 'a', value: 1}]}").unwrap();
+        fs::write(&output, "hand tuned").unwrap();
+
+        let run = |args: &[&str]| {
+            dir.run(
+                &SilentLog,
+                args.iter()
+                    .map(OsString::from)
+                    .chain([input.clone().into(), output.clone().into()]),
+            )
+        };
+        let refused = run(&[]);
+        let kept = fs::read_to_string(&output).unwrap();
+        let forced = run(&["--force"]);
+        let overwritten = fs::read_to_string(&output).unwrap();
+
+        assert!(matches!(refused, Err(PieChartError::Usage(ref m)) if m.contains("--force")));
+        assert_eq!(kept, "hand tuned");
+        assert!(forced.is_ok());
+        assert!(overwritten.starts_with("<svg"));
+    }
+
+    #[test]
+    fn check_test() {
+        let dir = TempDir::new("check");
+        let input = dir.join("chart.json5");
+        let output = dir.join("chart.svg");
+
+        fs::write(
+            &input,
+            "{title: 'Costs', items: [{key: 'A rather long name', value: 1}, {key: 'b', value: 31}]}",
+        )
+        .unwrap();
+
+        let log = RecordingLog::default();
+        let result = dir.run(
+            &log,
+            [
+                OsString::from("--check"),
+                "--labels".into(),
+                "key".into(),
+                input.into(),
+                output.clone().into(),
+            ],
+        );
+        let written = output.exists();
+
+        result.unwrap();
+        assert!(!written);
+
+        let lines = log.0.into_inner();
+
+        assert!(lines.contains(&"  A rather long name: 1 (3.12%)".to_string()));
+        assert!(lines.contains(&"  b: 31 (96.88%)".to_string()));
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("warning: Label 'A rather")));
+    }
+
+    #[test]
+    fn labels_overlap_check_test() {
+        let dir = TempDir::new("overlap");
+        let check = |count: usize| {
+            let input = dir.join(format!("{}.json5", count));
+            let items: Vec<String> = (0..count)
+                .map(|i| format!("{{key: 'k{}', value: 1}}", i))
+                .collect();
+            let log = RecordingLog::default();
+
+            fs::write(
+                &input,
+                format!("{{title: 'Crowded', items: [{}]}}", items.join(", ")),
+            )
+            .unwrap();
+            dir.run(
+                &log,
+                [
+                    OsString::from("--check"),
+                    "--labels".into(),
+                    "key".into(),
+                    "--label-position".into(),
+                    "outside".into(),
+                    input.into(),
+                ],
+            )
+            .unwrap();
+            log.0.into_inner().iter().any(|line| {
+                line == &format!(
+                    "warning: {}",
+                    Strings::default().get(Message::LabelsOverlap)
+                )
+            })
+        };
+
+        // A hundred labels cannot be spread apart down the sides of the pie, but a few can
+        assert!(check(100));
+        assert!(!check(3));
+    }
+
+    #[test]
+    fn verbose_log_test() {
+        struct CountingLog(std::cell::Cell<usize>);
+
+        impl PieChartLog for CountingLog {
+            fn output(self: &Self, _args: Arguments) {}
+            fn warning(self: &Self, _args: Arguments) {}
+            fn error(self: &Self, _args: Arguments) {}
+            fn debug(self: &Self, _args: Arguments) {
+                self.0.set(self.0.get() + 1);
+            }
+            fn trace(self: &Self, _args: Arguments) {
+                self.0.set(self.0.get() + 100);
+            }
+        }
+
+        let log = CountingLog(Default::default());
+
+        for verbosity in 0..3 {
+            let log = VerboseLog {
+                log: &log,
+                verbosity,
+                format: LogFormat::Text,
+                file: RefCell::new(None),
+            };
+
+            debug!(log, "debug");
+            trace!(log, "trace");
+        }
+
+        assert_eq!(log.0.get(), 102);
+    }
+
+    #[test]
+    fn json_log_test() {
+        let recording = RecordingLog::default();
+        let log = VerboseLog {
+            log: &recording,
+            verbosity: 0,
+            format: LogFormat::Json,
+            file: RefCell::new(None),
+        };
+
+        warning!(log, "Too \"many\" colors");
+        log.set_file(Some(Path::new("chart.json5")));
+        error!(log, "Unable to render");
+        debug!(log, "Not logged");
+
+        let records: Vec<serde_json::Value> = recording
+            .0
+            .into_inner()
+            .iter()
+            .map(|record| serde_json::from_str(record).unwrap())
+            .collect();
+
+        assert_eq!(
+            records,
+            vec![
+                serde_json::json!({ "level": "warning", "message": "Too \"many\" colors", "context": {} }),
+                serde_json::json!({
+                    "level": "error",
+                    "message": "Unable to render",
+                    "context": { "file": "chart.json5" },
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn render_template_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: 'A & B', items: [{ key: 'x', value: 1 }, { key: 'y', value: 3 }] }",
+        )
+        .unwrap();
+        let text = PieChartTool::new(&SilentLog)
+            .render_template(
+                "{title}: {{ for wedge in wedges }}{wedge.key} {wedge.percent} {wedge.end_angle} {wedge.path}; {{ endfor }}",
+                &[chart_data],
+                &ChartOptions::default(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            text,
+            "A &amp; B: x 25.0 90.0 M240,40 A200,200,0,0,1,440,240 L240,240 z; y 75.0 360.0 M440,240 A200,200,0,1,1,240,40 L240,240 z; "
+        );
+    }
+
+    #[test]
+    fn run_error_test() {
+        let dir = TempDir::new("errors");
+        let run = |args: &[&str]| dir.run(&SilentLog, args);
+
+        assert!(matches!(
+            run(&["/nonexistent/chart.json5"]),
+            Err(PieChartError::Io { .. })
+        ));
+        assert!(matches!(
+            run(&["a.json5", "b.svg", "c.svg"]),
+            Err(PieChartError::Usage(_))
+        ));
+        assert_eq!(run(&["--no-such-option"]).unwrap_err().exit_code(), 2);
+        assert!(matches!(
+            run(&["--open", "a.json5"]),
+            Err(PieChartError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn render_test() {
+        let chart_data: ChartData = json5::from_str(
+            r#"{ title: "Test", items: [{ key: "a", value: 1 }, { key: "b", value: 3 }] }"#,
+        )
+        .unwrap();
+        let options = ChartOptions {
+            seed: Some(1),
+            ..Default::default()
+        };
+        let svg = svg_string(&chart_data, &options).unwrap();
+
+        assert!(svg.contains("class=\"wedge-1\""));
+        assert!(svg.contains("<title>a: 1 (25%)</title>"));
+        assert!(svg.contains("role=\"img\""));
+        assert_eq!(svg, svg_string(&chart_data, &options).unwrap());
+
+        let options = ChartOptions {
+            pattern: Some(PatternMode::Overlay),
+            ..Default::default()
+        };
+        let svg = svg_string(&chart_data, &options).unwrap();
+
+        assert!(svg.contains(".wedge-1{fill:url(#pattern-1)"));
+        assert!(svg.contains("<pattern height=\"8\" id=\"pattern-1\""));
+
+        let options = ChartOptions {
+            size: Some(-1.0),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            render(&chart_data, &options),
+            Err(PieChartError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn responsive_test() {
+        let chart_data: ChartData =
+            json5::from_str("{ title: '', items: [{ key: 'a', value: 1 }] }").unwrap();
+        let svg = svg_string(
+            &chart_data,
+            &ChartOptions {
+                responsive: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(svg.contains("viewBox=\"0 0 480 520\""));
+        assert!(svg.contains("preserveAspectRatio=\"xMidYMid meet\""));
+        assert!(!svg.contains("width=\"480\""));
+    }
+
+    #[test]
+    fn embed_data_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: 'a & b', items: [{ key: '<c>', value: 1 }, { key: 'd', value: 2 }] }",
+        )
+        .unwrap();
+        let options = ChartOptions {
+            embed_data: Some(true),
+            seed: Some(1),
+            ..Default::default()
+        };
+        let svg = svg_string(&chart_data, &options).unwrap();
+        let charts = input::parse(InputFormat::Svg, &svg, "", &Default::default()).unwrap();
+
+        assert_eq!(charts[0].title, "a & b");
+        assert_eq!(charts[0].items[0].key, "<c>");
+        assert_eq!(
+            svg_string(&charts[0], &ChartOptions::default()).unwrap(),
+            svg
+        );
+        assert!(!svg_string(&chart_data, &ChartOptions::default())
+            .unwrap()
+            .contains("<metadata"));
+    }
+
+    #[test]
+    fn deterministic_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 2 }] }",
+        )
+        .unwrap();
+        let options = ChartOptions {
+            deterministic: Some(true),
+            palette: Some(Palette::Random),
+            ..Default::default()
+        };
+        let svg = svg_string(&chart_data, &options).unwrap();
+
+        assert_eq!(svg, svg_string(&chart_data, &options).unwrap());
+        assert!(svg.contains("413.205,"));
+        assert_ne!(
+            svg,
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    seed: Some(1),
+                    ..options.clone()
+                }
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn background_test() {
+        let chart_data: ChartData =
+            json5::from_str("{ title: '', items: [{ key: 'a', value: 1 }] }").unwrap();
+        let chart = |background: &str, background_rect| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    background: Some(background.to_string()),
+                    background_rect: Some(background_rect),
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert!(!chart("transparent", true).unwrap().contains("background"));
+        assert!(chart("#123456", false)
+            .unwrap()
+            .contains("style=\"background-color: #123456;\""));
+        assert!(chart("#123456", true).unwrap().contains(
+            "<rect class=\"background\" fill=\"#123456\" height=\"520\" width=\"480\"/>"
+        ));
+        assert!(matches!(
+            chart("#12", false),
+            Err(PieChartError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn center_text_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+        )
+        .unwrap();
+        let chart = |donut| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    donut: Some(donut),
+                    center_text: Some("{total} in all, {largest} of them b".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let svg = chart(true);
+
+        assert!(svg.contains("4 in all, 75% of them b"));
+        assert!(svg.contains(".center-text{"));
+        assert!(!chart(false).contains("center-text"));
+    }
+
+    #[test]
+    fn render_grid_test() {
+        let chart_data: ChartData =
+            json5::from_str(r#"{ title: "Test", items: [{ key: "a", value: 1 }] }"#).unwrap();
+        let options = ChartOptions {
+            columns: Some(2),
+            ..Default::default()
+        };
+        let svg = render_grid(
+            &[chart_data.clone(), chart_data.clone(), chart_data],
+            &options,
+        )
+        .unwrap()
+        .to_string();
+
+        assert!(svg.contains("id=\"chart-2\""));
+        assert!(svg.contains("#chart-1 .legend"));
+        assert!(render_grid(&[], &options).is_err());
+    }
+
+    #[test]
+    fn render_comparison_test() {
+        let first: ChartData = json5::from_str(
+            "{ title: '2023', items: [{ key: 'a', value: 1 }, { key: 'b', value: 2 }] }",
+        )
+        .unwrap();
+        let second: ChartData = json5::from_str(
+            "{ title: '2024', items: [{ key: 'c', value: 1 }, { key: 'b', value: 3 }] }",
+        )
+        .unwrap();
+        let svg = render_comparison(&[first, second], &ChartOptions::default())
+            .unwrap()
+            .to_string();
+        let fill = |selector: &str| {
+            let start = svg.find(selector).unwrap() + selector.len();
+
+            svg[start..].split(';').next().unwrap().to_string()
+        };
+
+        assert_eq!(
+            fill("#chart-0 .wedge-1{fill:"),
+            fill("#chart-1 .wedge-1{fill:")
+        );
+        assert_ne!(
+            fill("#chart-0 .wedge-0{fill:"),
+            fill("#chart-1 .wedge-0{fill:")
+        );
+        assert_eq!(svg.matches("class=\"legend\"").count(), 3);
+        assert!(svg.contains("id=\"legend\""));
+    }
+
+    #[test]
+    fn render_diff_test() {
+        let before: ChartData = json5::from_str(
+            "{ title: 'May', items: [{ key: 'a', value: 4 }, { key: 'b', value: 2 }] }",
+        )
+        .unwrap();
+        let after: ChartData = json5::from_str(
+            "{ title: 'June', items: [{ key: 'a', value: 5 }, { key: 'b', value: 1 }] }",
+        )
+        .unwrap();
+        let svg = render_diff(&before, &after, &ChartOptions::default())
+            .unwrap()
+            .to_string();
+
+        assert!(svg.contains("Change from May to June"));
+        assert!(svg.contains("class=\"changes\""));
+        assert!(svg.contains("+25%"));
+        assert!(svg.contains("-50%"));
+        assert!(render_diff(&before, &before, &ChartOptions::default()).is_err());
+    }
+
+    #[test]
+    fn render_animation_test() {
+        let frames: Vec<ChartData> = json5::from_str(
+            "[{ title: '2020', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }, { title: '2021', items: [{ key: 'b', value: 1 }, { key: 'c', value: 1 }] }]",
+        )
+        .unwrap();
+        let svg = render_animation(&frames, &ChartOptions::default(), 1.0)
+            .unwrap()
+            .to_string();
+
+        // Every key has a wedge, which moves through both frames
+        assert_eq!(svg.matches("<animate attributeName=\"d\"").count(), 3);
+        assert!(svg.contains("dur=\"2s\""));
+        // The title of the second frame is hidden until its wedges are shown
+        assert!(svg.contains("<text class=\"title\" opacity=\"0\""));
+        assert_eq!(svg.matches("attributeName=\"opacity\"").count(), 2);
+        assert!(render_animation(&frames[..1], &ChartOptions::default(), 1.0).is_err());
+        assert!(render_animation(&frames, &ChartOptions::default(), 0.0).is_err());
+
+        let gif = render_animation_as(&frames, &ChartOptions::default(), 1.0, AnimationFormat::Gif)
+            .unwrap();
+        let apng = render_animation_as(
+            &frames,
+            &ChartOptions::default(),
+            1.0,
+            AnimationFormat::Apng,
+        )
+        .unwrap();
+
+        assert!(gif.starts_with(b"GIF89a"));
+        assert!(apng.starts_with(b"\x89PNG"));
+        // The frames are held, then drawn at each step of moving, except where they look the same
+        assert!(gif.windows(3).filter(|w| *w == [0x21, 0xf9, 4]).count() > 2);
+    }
+
+    #[test]
+    fn whole_and_empty_wedges_test() {
+        let chart_data: ChartData = json5::from_str(
+            r#"{ title: "Test", items: [{ key: "a", value: 1 }, { key: "b", value: 0 }] }"#,
+        )
+        .unwrap();
+        let svg = svg_string(&chart_data, &ChartOptions::default()).unwrap();
+
+        assert!(svg.contains("d=\"M240,40 A200,200,0,1,1,240,440 A200,200,0,1,1,240,40 z\""));
+        assert!(!svg.contains("<path aria-label=\"b"));
+        assert!(svg.contains("<title>b: 0 (0%)</title>"));
+    }
+
+    #[test]
+    fn href_test() {
+        let chart = |href: &str| {
+            let chart_data: ChartData = json5::from_str(&format!(
+                "{{ title: '', items: [{{ key: 'a', value: 1, href: '{}' }}] }}",
+                href
+            ))
+            .unwrap();
+
+            svg_string(&chart_data, &ChartOptions::default())
+        };
+
+        assert_eq!(
+            chart("/details/a")
+                .unwrap()
+                .matches("<a href=\"/details/a\">")
+                .count(),
+            2
+        );
+        assert!(chart("#a").is_ok());
+        assert!(chart("a.html?at=12:00").is_ok());
+        assert!(chart("https://example.com/a").is_ok());
+        assert!(chart("mailto:a@example.com").is_ok());
+        assert!(chart(" JavaScript:alert(1)").is_err());
+        // Browsers drop these characters from links, so they do not hide the scheme
+        assert!(chart("java\\tscript:alert(1)").is_err());
+        assert!(chart("java\\nscript:alert(1)").is_err());
+        assert!(chart("\\u0001javascript:alert(1)").is_err());
+        assert!(chart("vbscript:msgbox(1)").is_err());
+        assert!(chart("data:text/html,<script>alert(1)</script>").is_err());
+    }
+
+    #[test]
+    fn class_test() {
+        let chart = |class: &str| {
+            let chart_data: ChartData = json5::from_str(&format!(
+                "{{ title: '', items: [{{ key: 'a', value: 1, class: '{}', children: [{{ key: 'b', value: 1 }}] }}, {{ key: 'c', value: 1 }}] }}",
+                class
+            ))
+            .unwrap();
+
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    sunburst: Some(true),
+                    ..Default::default()
+                },
+            )
+        };
+        let svg = chart("storage").unwrap();
+
+        assert_eq!(svg.matches("class=\"storage\"").count(), 2);
+        assert!(svg.contains(".storage{fill:"));
+        assert!(svg.contains("class=\"storage-0\""));
+        assert!(svg.contains("class=\"wedge-1\""));
+        assert!(!svg.contains("wedge-0"));
+        assert!(matches!(
+            chart("a{fill:red}"),
+            Err(PieChartError::Validation(_))
+        ));
+        // The classes of the chart itself would be restyled with the colors of the wedge
+        for class in ["legend", "labels", "center-text", "title"] {
+            assert!(
+                matches!(chart(class), Err(PieChartError::Validation(message)) if message.contains("used by the chart"))
+            );
+        }
+    }
+
+    #[test]
+    fn subtitle_test() {
+        let chart_data: ChartData = json5::from_str(
+            r#"{ title: "Quarterly\nRevenue", subtitle: "Source: billing", items: [{ key: "a", value: 1 }] }"#,
+        )
+        .unwrap();
+        let svg = svg_string(&chart_data, &ChartOptions::default()).unwrap();
+
+        assert!(svg.contains("aria-label=\"Quarterly Revenue\""));
+        assert!(svg.contains("<tspan x=\"240\" y=\"26.4\">"));
+        assert!(svg.contains("<text class=\"subtitle\" x=\"240\" y=\"37.92\">"));
+    }
+
+    #[test]
+    fn caption_test() {
+        let chart_data: ChartData = json5::from_str(
+            r#"{ title: "T", source: "Source: Q3 billing export\nRounded", items: [{ key: "a", value: 1 }] }"#,
+        )
+        .unwrap();
+        let svg = svg_string(&chart_data, &ChartOptions::default()).unwrap();
+
+        assert!(svg.contains("<text class=\"caption\" x=\"10\" y=\"510\">"));
+        assert!(svg.contains("<tspan x=\"10\" y=\"522\">"));
+        assert!(svg.contains("height=\"562\""));
+    }
+
+    #[test]
+    fn legend_wrapping_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'A rather long key that cannot fit', value: 1 }, { key: 'b', value: 1 }] }",
+        )
+        .unwrap();
+        let wrapped = svg_string(&chart_data, &ChartOptions::default()).unwrap();
+        let truncated = svg_string(
+            &chart_data,
+            &ChartOptions {
+                max_label_len: Some(8),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(wrapped.contains("<tspan"));
+        assert!(wrapped.contains("height=\"539.2\""));
+        assert!(truncated.contains("A rathe…"));
+        assert!(!truncated.contains("<tspan"));
+    }
+
+    #[test]
+    fn legend_rows_test() {
+        let chart_data = ChartData {
+            items: (1..=12)
+                .map(|i| ItemData::new(format!("Category {}", i), i as f64))
+                .collect(),
+            ..Default::default()
+        };
+        let svg = |legend_columns| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    legend_columns,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        // Three columns fit, giving four rows
+        assert!(svg(None).contains("height=\"670\""));
+        assert!(svg(Some(2)).contains("height=\"770\""));
+        assert!(svg_string(
+            &chart_data,
+            &ChartOptions {
+                legend_columns: Some(0),
+                ..Default::default()
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn half_test() {
+        let chart_data: ChartData =
+            json5::from_str("{ title: '', items: [{ key: 'a', value: 1 }] }").unwrap();
+        let svg = svg_string(
+            &chart_data,
+            &ChartOptions {
+                half: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // A whole semicircle from the left edge to the right, half the height of a full chart
+        assert!(svg.contains("height=\"320\""));
+        assert!(svg.contains("d=\"M40,240 A200,200,0,0,1,440,240 L240,240 z\""));
+    }
+
+    #[test]
+    fn start_angle_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+        )
+        .unwrap();
+        let chart = |start_angle, counterclockwise| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    start_angle,
+                    counterclockwise: Some(counterclockwise),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        assert!(
+            chart(Some(0.0), false).contains("d=\"M440,240 A200,200,0,0,1,240,440 L240,240 z\"")
+        );
+        assert!(chart(None, true).contains("d=\"M40,240 A200,200,0,0,1,240,40 L240,240 z\""));
+    }
+
+    #[test]
+    fn rose_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 4 }, { key: 'b', value: 0 }, { key: 'c', value: 2 }] }",
+        )
+        .unwrap();
+        let svg = svg_string(
+            &chart_data,
+            &ChartOptions {
+                rose: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // Each wedge takes a third of the circle, with the empty one left out
+        assert!(svg.contains("d=\"M240,40 A200,200,0,0,1,413.20508,340 L240,240 z\""));
+        assert!(svg.contains("A100,100,0,0,1,240,140 L240,240 z"));
+        assert_eq!(svg.matches("<path").count(), 2);
+    }
+
+    #[test]
+    fn sunburst_test() {
+        let chart = |items: &str, sunburst| {
+            let chart_data: ChartData =
+                json5::from_str(&format!("{{ title: '', items: {} }}", items)).unwrap();
+
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    sunburst: Some(sunburst),
+                    ..Default::default()
+                },
+            )
+        };
+        let items = "[{ key: 'a', children: [{ key: 'b', value: 1 }, { key: 'c', value: 3 }] }, { key: 'd', value: 4 }]";
+        let svg = chart(items, true).unwrap();
+
+        // The parent takes the total of its children
+        assert!(svg.contains("<title>a: 4 (50%)</title>"));
+        assert!(svg.contains("class=\"wedge-0-1\""));
+        assert_eq!(svg.matches("<path").count(), 4);
+        assert_eq!(chart(items, false).unwrap().matches("<path").count(), 2);
+        assert!(matches!(
+            chart("[{ key: 'a', children: [{ key: 'b', value: -1 }] }]", true),
+            Err(PieChartError::NegativeValue { .. })
+        ));
+    }
+
+    #[test]
+    fn merge_duplicates_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 2 }, { key: 'a', value: 1 }] }",
+        )
+        .unwrap();
+        let svg = svg_string(
+            &chart_data,
+            &ChartOptions {
+                merge_duplicates: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(svg.contains("<title>a: 2 (50%)</title>"));
+        assert_eq!(svg.matches("<path").count(), 2);
+        assert_eq!(
+            svg_string(&chart_data, &ChartOptions::default())
+                .unwrap()
+                .matches("<path")
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn top_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 6 }, { key: 'c', value: 1 }, { key: 'd', value: 2 }] }",
+        )
+        .unwrap();
+        let chart = |top_other| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    top: Some(2),
+                    top_other: Some(top_other),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let svg = chart(false);
+
+        assert!(svg.contains("<title>b: 6 (75%)</title>"));
+        assert!(svg.contains("<title>d: 2 (25%)</title>"));
+        assert_eq!(svg.matches("<path").count(), 2);
+        assert!(chart(true).contains("<title>Other: 2 (20%)</title>"));
+    }
 
     #[test]
-    fn basic_test() {
-        struct TestLogger;
+    fn saturation_brightness_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 2 }, { key: 'c', value: 3 }] }",
+        )
+        .unwrap();
+        let chart = |saturation, brightness| {
+            PieChartTool::new(&SilentLog).process_chart_data(
+                &chart_data,
+                &ChartOptions {
+                    saturation: Some(saturation),
+                    brightness: Some(brightness),
+                    ..Default::default()
+                },
+            )
+        };
+        let colors: Vec<String> = chart(0.0, 0.8)
+            .unwrap()
+            .wedges
+            .into_iter()
+            .map(|wedge| wedge.color)
+            .collect();
 
-        impl TestLogger {
-            fn new() -> TestLogger {
-                TestLogger {}
-            }
+        assert_eq!(colors, ["#cccccc", "#cccccc", "#cccccc"]);
+        assert!(matches!(
+            chart(1.5, 0.5),
+            Err(PieChartError::Validation(message)) if message.contains("saturation")
+        ));
+    }
+
+    #[test]
+    fn units_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1500 }], options: { units: 'GB', thousands_separator: true } }",
+        )
+        .unwrap();
+        let svg = svg_string(
+            &chart_data,
+            &ChartOptions {
+                legend_values: Some(LegendValues::Value),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(svg.contains("a (1,500\u{a0}GB)"));
+    }
+
+    #[test]
+    fn breakout_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 6 }, { key: 'b', value: 2 }, { key: 'c', value: 1 }, { key: 'd', value: 1 }] }",
+        )
+        .unwrap();
+        let tool = PieChartTool::new(&SilentLog);
+        let options = ChartOptions {
+            min_percent: Some(15.0),
+            breakout: Some("Other".to_string()),
+            ..Default::default()
+        };
+        let rd = tool.process_chart_data(&chart_data, &options).unwrap();
+        let labels: Vec<&str> = rd
+            .breakout_wedges()
+            .iter()
+            .filter_map(|(wedge, _, _)| wedge.label.as_deref())
+            .collect();
+
+        // The grouped items are broken out, with the wedge they are grouped into facing them
+        assert_eq!(labels, ["c", "d"]);
+        assert!((rd.start_angle + 2.0 * PI * 0.9).abs() < 1e-9);
+
+        let ((x, y), _, radius) = rd.breakout_circle();
+        let connectors = rd.connectors();
+
+        assert_eq!(rd.size().0, x + radius + rd.gutter.left);
+        assert_eq!(connectors[0].1, (x, y - radius));
+        assert!(connectors[0].0 .1 < rd.center().1);
+
+        let svg = tool.render(&chart_data, &options).unwrap().to_string();
+
+        assert_eq!(svg.matches("class=\"connector\"").count(), 2);
+        assert!(svg.contains("<title>d: 1 (50%)</title>"));
+
+        // The other item of the largest items breaks out in the same way
+        let rd = tool
+            .process_chart_data(
+                &chart_data,
+                &ChartOptions {
+                    top: Some(2),
+                    top_other: Some(true),
+                    breakout: Some("Other".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let labels: Vec<&str> = rd
+            .breakout_wedges()
+            .iter()
+            .filter_map(|(wedge, _, _)| wedge.label.as_deref())
+            .collect();
+
+        assert_eq!(labels, ["c", "d"]);
+        assert!(matches!(
+            tool.process_chart_data(
+                &chart_data,
+                &ChartOptions {
+                    breakout: Some("a".to_string()),
+                    ..Default::default()
+                }
+            ),
+            Err(PieChartError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn waffle_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 2 }, { key: 'b', value: 1 }, { key: 'c', value: 0.02 }] }",
+        )
+        .unwrap();
+        let tool = PieChartTool::new(&SilentLog);
+        let options = ChartOptions {
+            waffle: Some(true),
+            ..Default::default()
+        };
+        let rd = tool.process_chart_data(&chart_data, &options).unwrap();
+        let cells = rd.waffle_cells();
+        let count = |index: usize| cells.iter().filter(|cell| cell.0 == index).count();
+
+        // The squares add up to 100 and fill the rows from the top left
+        assert_eq!([count(0), count(1), count(2)], [66, 33, 1]);
+        assert_eq!(cells[0], (0, rd.gutter.left, rd.gutter.top));
+        assert_eq!(cells[67].2, cells[60].2);
+        assert!(cells[70].2 > cells[69].2);
+
+        let svg = tool.render(&chart_data, &options).unwrap().to_string();
+
+        assert_eq!(svg.matches("<rect").count(), 100 + 3);
+        assert!(svg.contains("<g aria-label=\"c: 0.02 (0.6622516556291391%)\" class=\"wedge-2\">"));
+        assert!(tool
+            .process_chart_data(
+                &chart_data,
+                &ChartOptions {
+                    donut: Some(true),
+                    ..options
+                }
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn treemap_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 2 }, { key: 'b', value: 6 }, { key: 'c', value: 1 }, { key: 'd', value: 6 }, { key: 'e', value: 3 }, { key: 'ffffffffffffffff', value: 2 }, { key: 'g', value: 4 }] }",
+        )
+        .unwrap();
+        let tool = PieChartTool::new(&SilentLog);
+        let options = ChartOptions {
+            treemap: Some(true),
+            labels: Some(WedgeLabels::Key),
+            ..Default::default()
+        };
+        let rd = tool.process_chart_data(&chart_data, &options).unwrap();
+        let rects = rd.treemap();
+        let d = rd.pie_diameter;
+
+        // The largest item is at the top left, and the rectangles fill the square of the pie
+        assert_eq!(rects.len(), 7);
+        assert_eq!(
+            (rects[0].0, rects[0].1, rects[0].2),
+            (1, rd.gutter.left, rd.gutter.top)
+        );
+        assert!((rects.iter().map(|r| r.3 * r.4).sum::<f64>() - d * d).abs() < 1e-6);
+        assert!((rects[6].3 * rects[6].4 - d * d / 24.0).abs() < 1e-6);
+
+        for &(_, x, y, width, height) in &rects {
+            assert!(x + width <= rd.gutter.left + d + 1e-6);
+            assert!(y + height <= rd.gutter.top + d + 1e-6);
         }
 
-        impl PieChartLog for TestLogger {
-            fn output(self: &Self, _args: Arguments) {}
-            fn warning(self: &Self, _args: Arguments) {}
-            fn error(self: &Self, _args: Arguments) {}
+        let svg = tool.render(&chart_data, &options).unwrap().to_string();
+
+        // Labels too wide for their rectangles are left out
+        assert!(svg.contains("<g class=\"treemap\">"));
+        assert_eq!(svg.matches("<rect class=\"wedge").count(), 7);
+        assert!(!svg.contains(">ffffffffffffffff</text>"));
+        assert!(tool
+            .process_chart_data(
+                &chart_data,
+                &ChartOptions {
+                    half: Some(true),
+                    ..options
+                }
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn bars_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 0 }, { key: 'c', value: 4 }] }",
+        )
+        .unwrap();
+        let tool = PieChartTool::new(&SilentLog);
+        let options = ChartOptions {
+            as_bars: Some(true),
+            labels: Some(WedgeLabels::Key),
+            ..Default::default()
+        };
+        let rd = tool.process_chart_data(&chart_data, &options).unwrap();
+        let bars = rd.bars();
+
+        // Empty items keep their row, and the longest bar leaves room for its label
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[1].2 - bars[0].2, 2.0 * MAX_BAR_SPACING);
+        assert_eq!(bars[0].3 * 4.0, bars[1].3);
+        assert!(bars[1].3 < rd.pie_diameter);
+        assert_eq!(bars[1].4, MAX_BAR_SPACING * (1.0 - BAR_GAP));
+
+        let svg = tool.render(&chart_data, &options).unwrap().to_string();
+
+        assert!(svg.contains("<g class=\"bars\">"));
+        assert_eq!(svg.matches("class=\"bar-label\"").count(), 2);
+        assert!(tool
+            .process_chart_data(
+                &chart_data,
+                &ChartOptions {
+                    waffle: Some(true),
+                    ..options
+                }
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn series_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', series: [{ name: '2023', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }, { name: '2024', items: [{ key: 'b', value: 2 }, { key: 'c', value: 2, color: 'red' }] }] }",
+        )
+        .unwrap();
+        let tool = PieChartTool::new(&SilentLog);
+        let rd = tool
+            .process_chart_data(&chart_data, &ChartOptions::default())
+            .unwrap();
+        let keys: Vec<&str> = rd.wedges.iter().map(|wedge| wedge.key.as_str()).collect();
+        let classes: Vec<&str> = rd.series_wedges[0]
+            .iter()
+            .map(|wedge| wedge.class.as_str())
+            .collect();
+
+        // The legend has every key, and the outer ring the classes of the inner one
+        assert_eq!(keys, ["a", "b", "c"]);
+        assert_eq!(rd.wedges[2].value, 0.0);
+        assert_eq!(rd.wedges[2].color, "red");
+        assert_eq!(rd.wedges[1].legend, "b");
+        assert_eq!(classes, ["wedge-1", "wedge-2"]);
+        assert_eq!(rd.ring_radii(1).1, rd.pie_diameter / 2.0);
+
+        let svg = tool
+            .render(&chart_data, &ChartOptions::default())
+            .unwrap()
+            .to_string();
+
+        assert!(svg.contains("<title>2024, c: 2 (50%)</title>"));
+        assert!(matches!(
+            tool.process_chart_data(
+                &chart_data,
+                &ChartOptions {
+                    sunburst: Some(true),
+                    ..Default::default()
+                }
+            ),
+            Err(PieChartError::Validation(_))
+        ));
+        assert!(matches!(
+            ChartData {
+                items: vec![ItemData::new("a", 1.0)],
+                ..chart_data.clone()
+            }
+            .validate(),
+            Err(PieChartError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn rtl_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: 'הוצאות', items: [{ key: 'שכירות', value: 10 }, { key: 'מזון', value: 5 }, { key: 'a', value: 1 }], caption: 'מקור' }",
+        )
+        .unwrap();
+        let tool = PieChartTool::new(&SilentLog);
+        let rd = tool
+            .process_chart_data(&chart_data, &ChartOptions::default())
+            .unwrap();
+        let (width, _) = rd.size();
+        let entries = rd.legend_entries();
+
+        assert!(rd.rtl);
+        // The first legend entry is on the right
+        assert_eq!(
+            entries[0].0,
+            width - rd.legend_gutter.left - rd.legend_height
+        );
+        assert!(entries[1].0 < entries[0].0);
+
+        let svg = tool
+            .render(&chart_data, &ChartOptions::default())
+            .unwrap()
+            .to_string();
+
+        assert!(svg.contains("direction=\"rtl\""));
+        assert!(svg.contains(&format!(
+            "class=\"caption\" x=\"{}\"",
+            width - LEGEND_GUTTER
+        )));
+
+        let rd = tool
+            .process_chart_data(
+                &chart_data,
+                &ChartOptions {
+                    locale: Some("he".to_string()),
+                    top: Some(1),
+                    top_other: Some(true),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(rd.wedges.last().unwrap().key, "אחר");
+        assert!(
+            !tool
+                .process_chart_data(
+                    &ChartData {
+                        items: chart_data.items[2..].to_vec(),
+                        ..chart_data.clone()
+                    },
+                    &ChartOptions::default()
+                )
+                .unwrap()
+                .rtl
+        );
+    }
+
+    #[test]
+    fn format_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: '{value}', value: 3 }] }",
+        )
+        .unwrap();
+        let chart = |label_format: &str, legend_format: &str| {
+            PieChartTool::new(&SilentLog).process_chart_data(
+                &chart_data,
+                &ChartOptions {
+                    label_format: Some(label_format.to_string()),
+                    legend_format: Some(legend_format.to_string()),
+                    ..Default::default()
+                },
+            )
+        };
+        let rd = chart("{percent}", "{key}: {value} ({percent}%)").unwrap();
+
+        assert_eq!(rd.wedges[0].label.as_deref(), Some("25"));
+        assert_eq!(rd.wedges[0].legend, "a: 1 (25%)");
+        assert_eq!(rd.wedges[1].legend, "{value}: 3 (75%)");
+        assert!(matches!(
+            chart("{count}", "{key}"),
+            Err(PieChartError::Validation(message)) if message.contains("label format")
+        ));
+    }
+
+    #[test]
+    fn legend_sort_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'b', value: 1 }, { key: 'c', value: 3 }, { key: 'a', value: 2 }] }",
+        )
+        .unwrap();
+        let rd = PieChartTool::new(&SilentLog)
+            .process_chart_data(
+                &chart_data,
+                &ChartOptions {
+                    sort: Some(SortOrder::ValueDesc),
+                    legend_sort: Some(SortOrder::Key),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let keys = |wedges: Vec<&WedgeData>| -> Vec<String> {
+            wedges.iter().map(|wedge| wedge.key.clone()).collect()
+        };
+
+        assert_eq!(keys(rd.wedges.iter().collect()), ["c", "a", "b"]);
+        assert_eq!(keys(rd.legend_wedges()), ["a", "b", "c"]);
+
+        let svg = PieChartTool::legend(&rd).to_string();
+
+        // Legend blocks keep the class, and so the color, of their wedge
+        assert!(svg.find("class=\"wedge-1\"") < svg.find("class=\"wedge-2\""));
+        assert!(svg.find("class=\"wedge-2\"") < svg.find("class=\"wedge-0\""));
+    }
+
+    #[test]
+    fn percent_input_test() {
+        let chart = |items: &str| {
+            let chart_data: ChartData =
+                json5::from_str(&format!("{{ title: '', items: {} }}", items)).unwrap();
+
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    percentages: Some(true),
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert!(
+            chart("[{ key: 'a', value: 33.3 }, { key: 'b', value: 66.6 }]")
+                .unwrap()
+                .contains("<title>a: 33.3 (33.3%)</title>")
+        );
+        assert!(matches!(
+            chart("[{ key: 'a', value: 30 }, { key: 'b', value: 60 }]"),
+            Err(PieChartError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn value_policy_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: -1 }, { key: 'c', value: 0 }] }",
+        )
+        .unwrap();
+        let chart = |negatives, zeros| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    negatives: Some(negatives),
+                    zeros: Some(zeros),
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert!(matches!(
+            chart(NegativeValues::Error, ZeroValues::Keep),
+            Err(PieChartError::NegativeValue { .. })
+        ));
+
+        let svg = chart(NegativeValues::Abs, ZeroValues::Keep).unwrap();
+
+        assert!(svg.contains("<title>b: 1 (50%)</title>"));
+        assert!(svg.contains("<title>c: 0 (0%)</title>"));
+
+        let svg = chart(NegativeValues::Skip, ZeroValues::Skip).unwrap();
+
+        assert!(svg.contains("<title>a: 1 (100%)</title>"));
+        assert!(!svg.contains("<title>b"));
+        assert!(!svg.contains("<title>c"));
+    }
+
+    #[test]
+    fn item_validation_test() {
+        let chart = |items: &str| {
+            let chart_data: ChartData =
+                json5::from_str(&format!("{{ title: '', items: {} }}", items)).unwrap();
+
+            render(&chart_data, &ChartOptions::default())
+        };
+
+        assert!(matches!(chart("[]"), Err(PieChartError::NoItems)));
+        assert!(matches!(
+            chart("[{ key: 'a', value: 0 }, { key: 'b', value: 0 }]"),
+            Err(PieChartError::ZeroTotal)
+        ));
+        assert!(matches!(
+            chart("[{ key: 'a', value: 1 }, { key: 'b', value: -1 }]"),
+            Err(PieChartError::NegativeValue { key, .. }) if key == "b"
+        ));
+        assert!(matches!(
+            chart("[{ key: 'a', value: Infinity }]"),
+            Err(PieChartError::NonFiniteValue { .. })
+        ));
+    }
+
+    #[test]
+    fn run_with_options_test() {
+        let dir = TempDir::new("options");
+        let input = dir.join("costs.json5");
+        let output = dir.join("costs.svg");
+        let chart = "{title: 'Costs', items: [{key: 'a', value: 1}, {key: 'b', value: 2}]}";
+
+        fs::write(&input, chart).unwrap();
+
+        let result = dir.run(
+            &SilentLog,
+            [
+                OsString::from("--donut"),
+                "--palette".into(),
+                "tableau10".into(),
+                input.into(),
+                output.clone().into(),
+            ],
+        );
+        let written = fs::read_to_string(&output);
+
+        assert!(result.is_ok());
+
+        // Options set in code render the same SVG as the same options given as arguments, when
+        // there is no config file
+        let svg = PieChartTool::new(&SilentLog)
+            .run_with_options(
+                json5::from_str(chart).unwrap(),
+                ChartOptions {
+                    donut: Some(true),
+                    palette: Some(Palette::Tableau10),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(svg, written.unwrap());
+    }
+
+    #[test]
+    fn option_precedence_test() {
+        let dir = TempDir::new("precedence");
+        let config = dir.join("config.toml");
+        let render = |name: &str, chart: &str, args: &[&str]| {
+            let input = dir.join(format!("{}.json5", name));
+            let output = dir.join(format!("{}.svg", name));
+
+            fs::write(&input, chart).unwrap();
+            dir.run(
+                &SilentLog,
+                ["--palette", "tableau10"]
+                    .iter()
+                    .chain(args)
+                    .map(OsString::from)
+                    .chain([input.into(), output.clone().into()]),
+            )
+            .unwrap();
+            fs::read_to_string(output).unwrap()
+        };
+        let chart = "{title: 'Costs', items: [{key: 'a', value: 1}, {key: 'b', value: 2}]}";
+        let off = "{title: 'Costs', items: [{key: 'a', value: 1}, {key: 'b', value: 2}], options: {donut: false}}";
+
+        let pie = render("pie", chart, &[]);
+
+        fs::write(&config, "donut = true\n").unwrap();
+
+        let donut = render("donut", chart, &[]);
+        // The command line turns off what the config file turns on, as does the chart file
+        let cli_off = render("cli_off", chart, &["--no-donut"]);
+        let chart_off = render("chart_off", off, &[]);
+        let last_wins = render("last_wins", chart, &["--no-donut", "--donut"]);
+
+        assert_ne!(donut, pie);
+        assert_eq!(cli_off, pie);
+        assert_eq!(chart_off, pie);
+        assert_eq!(last_wins, donut);
+    }
+
+    #[test]
+    fn donut_test() {
+        let chart = |chart: &str, donut| {
+            svg_string(
+                &json5::from_str(chart).unwrap(),
+                &ChartOptions {
+                    donut: Some(donut),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let items = "items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }]";
+
+        // The default hole is half the radius, and the wedges are cut back to it with an arc
+        assert!(chart(&format!("{{ title: '', {} }}", items), true)
+            .contains("d=\"M240,40 A200,200,0,0,1,440,240 L340,240 A100,100,0,0,0,240,140 z\""));
+        assert!(chart(
+            &format!("{{ title: '', hole_ratio: 0.3, {} }}", items),
+            false
+        )
+        .contains("d=\"M240,40 A200,200,0,0,1,440,240 L300,240 A60,60,0,0,0,240,180 z\""));
+        assert!(chart(&format!("{{ title: '', {} }}", items), false)
+            .contains("d=\"M240,40 A200,200,0,0,1,440,240 L240,240 z\""));
+    }
+
+    #[test]
+    fn seed_test() {
+        let colors = |chart: &str, seed| {
+            PieChartTool::new(&SilentLog)
+                .process_chart_data(
+                    &json5::from_str(chart).unwrap(),
+                    &ChartOptions {
+                        seed,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .wedges
+                .into_iter()
+                .map(|wedge| wedge.color)
+                .collect::<Vec<_>>()
+        };
+        let chart = "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }";
+
+        // The same seed gives the same colors on every run, from the options or the chart
+        assert_eq!(colors(chart, Some(7)), colors(chart, Some(7)));
+        assert_ne!(colors(chart, Some(7)), colors(chart, Some(8)));
+        assert_eq!(
+            colors(
+                "{ title: '', seed: 7, items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+                None
+            ),
+            colors(chart, Some(7))
+        );
+    }
+
+    #[test]
+    fn dimensions_test() {
+        let chart = |chart: &str, options: ChartOptions| {
+            svg_string(&json5::from_str(chart).unwrap(), &options).unwrap()
+        };
+        let items = "items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }]";
+        let default = format!("{{ title: '', {} }}", items);
+
+        assert!(chart(&default, ChartOptions::default()).contains("viewBox=\"0 0 480 520\""));
+
+        let small = chart(
+            &default,
+            ChartOptions {
+                size: Some(200.0),
+                ..Default::default()
+            },
+        );
+
+        assert!(small.contains("viewBox=\"0 0 280 320\""));
+        assert!(small.contains("d=\"M140,40 A100,100,0,0,1,240,140 L140,140 z\""));
+        // The gutters and legend shrink and grow the document around the pie
+        assert!(chart(
+            &default,
+            ChartOptions {
+                size: Some(200.0),
+                gutter: Some(10.0),
+                ..Default::default()
+            }
+        )
+        .contains("viewBox=\"0 0 220 274\""));
+        assert!(chart(
+            &default,
+            ChartOptions {
+                size: Some(200.0),
+                legend_height: Some(30.0),
+                ..Default::default()
+            }
+        )
+        .contains("height=\"30\" rx=\"3\" ry=\"3\" width=\"30\""));
+        // The options block of the chart sets them too
+        assert!(chart(
+            &format!("{{ title: '', options: {{ size: 200 }}, {} }}", items),
+            ChartOptions::default()
+        )
+        .contains("viewBox=\"0 0 280 320\""));
+    }
+
+    #[test]
+    fn wedge_labels_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1, color: '#000000' }, { key: 'b', value: 3, color: '#ffff00' }] }",
+        )
+        .unwrap();
+        let chart = |labels| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    labels: Some(labels),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let svg = chart(WedgeLabels::Both);
+
+        // Labels sit halfway out along the middle of their wedge, in white on dark wedges
+        assert!(svg.contains(
+            "<text class=\"labels inverse\" x=\"310.71067811865476\" y=\"169.28932188134524\">\na 25%\n</text>"
+        ));
+        assert!(svg.contains("<text class=\"labels\" x=\"169.28932188134524\" y=\"310.71067811865476\">\nb 75%\n</text>"));
+        assert!(chart(WedgeLabels::Key).contains(">\na\n</text>"));
+        assert!(chart(WedgeLabels::Percent).contains(">\n25%\n</text>"));
+        assert!(!chart(WedgeLabels::None).contains("class=\"labels"));
+    }
+
+    #[test]
+    fn min_percent_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 90 }, { key: 'b', value: 6 }, { key: 'c', value: 3 }, { key: 'd', value: 1 }] }",
+        )
+        .unwrap();
+        let wedges = |options: ChartOptions| {
+            PieChartTool::new(&SilentLog)
+                .process_chart_data(&chart_data, &options)
+                .unwrap()
+                .wedges
+                .into_iter()
+                .map(|wedge| (wedge.key, wedge.value))
+                .collect::<Vec<_>>()
+        };
+
+        // The items below 5% are added together into one wedge
+        assert_eq!(
+            wedges(ChartOptions {
+                min_percent: Some(5.0),
+                ..Default::default()
+            }),
+            [
+                ("a".to_string(), 90.0),
+                ("b".to_string(), 6.0),
+                ("Other".to_string(), 4.0)
+            ]
+        );
+        assert_eq!(
+            wedges(ChartOptions {
+                min_percent: Some(5.0),
+                other_label: Some("Rest".to_string()),
+                ..Default::default()
+            })[2],
+            ("Rest".to_string(), 4.0)
+        );
+        assert_eq!(wedges(ChartOptions::default()).len(), 4);
+    }
+
+    #[test]
+    fn sort_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'c', value: 2 }, { key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+        )
+        .unwrap();
+        let keys = |sort| {
+            PieChartTool::new(&SilentLog)
+                .process_chart_data(
+                    &chart_data,
+                    &ChartOptions {
+                        sort,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .wedges
+                .into_iter()
+                .map(|wedge| wedge.key)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(keys(Some(SortOrder::ValueDesc)), ["b", "c", "a"]);
+        assert_eq!(keys(Some(SortOrder::ValueAsc)), ["a", "c", "b"]);
+        assert_eq!(keys(Some(SortOrder::Key)), ["a", "b", "c"]);
+        // Without sorting the wedges are in the order of the file
+        assert_eq!(keys(Some(SortOrder::None)), ["c", "a", "b"]);
+        assert_eq!(keys(None), ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn legend_values_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'Disk', value: 120 }, { key: 'RAM', value: 360 }] }",
+        )
+        .unwrap();
+        let legend = |legend_values| {
+            PieChartTool::new(&SilentLog)
+                .process_chart_data(
+                    &chart_data,
+                    &ChartOptions {
+                        legend_values,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .wedges
+                .into_iter()
+                .map(|wedge| wedge.legend)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(legend(None), ["Disk (25%)", "RAM (75%)"]);
+        assert_eq!(
+            legend(Some(LegendValues::Value)),
+            ["Disk (120)", "RAM (360)"]
+        );
+        assert_eq!(
+            legend(Some(LegendValues::Both)),
+            ["Disk (120, 25%)", "RAM (360, 75%)"]
+        );
+        assert_eq!(legend(Some(LegendValues::None)), ["Disk", "RAM"]);
+    }
+
+    #[test]
+    fn title_style_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: 'T', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+        )
+        .unwrap();
+        let chart = |title_style: TitleStyle| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    title_style,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let styled = chart(TitleStyle {
+            font_family: Some("Georgia".to_string()),
+            font_size: Some(30.0),
+            font_weight: Some("bold".to_string()),
+            color: Some("red".to_string()),
+            position: None,
+        });
+
+        assert!(styled.contains(
+            ".title{font-family:Georgia;font-size:30;font-weight:bold;fill:red;text-anchor:middle;}"
+        ));
+        // The larger font widens the title gutter
+        assert!(styled.contains("viewBox=\"0 0 480 540\""));
+        assert!(styled.contains("<text class=\"title\" x=\"240\" y=\"30\">"));
+
+        let bottom = chart(TitleStyle {
+            position: Some(TitlePosition::Bottom),
+            ..Default::default()
+        });
+
+        assert!(bottom.contains("<text class=\"title\" x=\"240\" y=\"510\">"));
+
+        let hidden = chart(TitleStyle {
+            position: Some(TitlePosition::Hidden),
+            ..Default::default()
+        });
+
+        assert!(!hidden.contains("class=\"title\""));
+        // The pie moves up into the space the title gutter took
+        assert!(hidden.contains("viewBox=\"0 0 480 490\""));
+        assert!(hidden.contains("d=\"M240,10 A200,200,0,0,1,440,210 L240,210 z\""));
+    }
+
+    #[test]
+    fn custom_styles_test() {
+        let dir = TempDir::new("custom_styles");
+        let css = dir.join("extra.css");
+
+        fs::write(&css, ".labels{fill:blue}").unwrap();
+
+        let chart_data: ChartData = json5::from_str(
+            "{ title: 'T', items: [{ key: 'a', value: 1 }], styles: ['.title{fill:green}'] }",
+        )
+        .unwrap();
+        let chart = |replace_styles| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    css: Some(css.clone()),
+                    replace_styles,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let appended = chart(None);
+        let generated = appended.find(".title{font-family:").unwrap();
+        let styles = appended.find(".title{fill:green}").unwrap();
+        let file = appended.find(".labels{fill:blue}").unwrap();
+
+        // The chart styles and then the file come after the generated styles, so they win
+        assert!(generated < styles && styles < file);
+
+        let replaced = chart(Some(true));
+
+        assert!(!replaced.contains(".title{font-family:"));
+        assert!(replaced.contains(".title{fill:green}"));
+        assert!(replaced.contains(".labels{fill:blue}"));
+        assert!(matches!(
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    css: Some(dir.join("missing.css")),
+                    ..Default::default()
+                }
+            ),
+            Err(PieChartError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn theme_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: 'T', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+        )
+        .unwrap();
+        let chart = |theme| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    theme,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let light = chart(Some(Theme::Light));
+
+        assert!(light.contains("style=\"background-color: white;\""));
+        assert!(light.contains(".legend{font-family:Arial;font-size:12pt;fill:#000000;"));
+        assert!(light.contains("stroke-width:0}"));
+
+        let dark = chart(Some(Theme::Dark));
+
+        assert!(dark.contains("style=\"background-color: #1e1e1e;\""));
+        assert!(dark.contains(".title{font-family:Arial;font-size:12;fill:#e0e0e0;"));
+        assert!(dark.contains(".legend{font-family:Arial;font-size:12pt;fill:#e0e0e0;"));
+        // The dark theme defaults to the Tableau 10 palette with separated wedges
+        assert!(dark.contains(".wedge-0{fill:#4e79a7;stroke:#1e1e1e;stroke-width:1}"));
+        assert!(dark.contains(".wedge-1{fill:#f28e2b;stroke:#1e1e1e;stroke-width:1}"));
+    }
+
+    #[test]
+    fn out_dir_test() {
+        let dir = TempDir::new("out_dir");
+        let out_dir = dir.join("charts");
+
+        fs::write(
+            dir.join("first.json5"),
+            "{ title: 'First', items: [{ key: 'a', value: 1 }] }",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("second.json"),
+            r#"{ "title": "Second", "items": [{ "key": "b", "value": 2 }] }"#,
+        )
+        .unwrap();
+
+        let args = |files: &[&str]| {
+            let mut args: Vec<OsString> = vec!["--out-dir".into(), out_dir.clone().into()];
+
+            args.extend(files.iter().map(|file| dir.join(file).into()));
+            args
+        };
+
+        dir.run(&SilentLog, args(&["first.json5", "second.json"]))
+            .unwrap();
+
+        // The directory is created and each output is named after its input
+        assert!(fs::read_to_string(out_dir.join("first.svg"))
+            .unwrap()
+            .contains("aria-label=\"First\""));
+        assert!(fs::read_to_string(out_dir.join("second.svg"))
+            .unwrap()
+            .contains("aria-label=\"Second\""));
+
+        let log = RecordingLog::default();
+
+        assert!(matches!(
+            dir.run(&log, args(&["missing.json5", "second.json"])),
+            Err(PieChartError::Io { .. })
+        ));
+        assert!(log
+            .0
+            .into_inner()
+            .iter()
+            .any(|line| line.starts_with("error: Unable to render")
+                && line.contains("missing.json5")));
+        assert!(matches!(
+            dir.run(&SilentLog, args(&[])),
+            Err(PieChartError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn watch_test() {
+        let dir = TempDir::new("watch");
+        let (input, output) = (dir.join("chart.json5"), dir.join("chart.svg"));
+        let write = |content: &str, seconds: u64| {
+            fs::write(&input, content).unwrap();
+            // Set the time rather than hoping the clock has moved on since the last write
+            fs::File::options()
+                .write(true)
+                .open(&input)
+                .unwrap()
+                .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds))
+                .unwrap();
+        };
+
+        write("{ title: 'First', items: [{ key: 'a', value: 1 }] }", 1);
+
+        let cli = Cli::try_parse_from([
+            OsString::new(),
+            "--watch".into(),
+            "--force".into(),
+            input.clone().into(),
+            output.clone().into(),
+        ])
+        .unwrap();
+        let jobs = cli.render.get_jobs().unwrap();
+        let log = RecordingLog::default();
+        let tool = PieChartTool::new(&log);
+        let mut last_modified = vec![None; jobs.len()];
+        let mut render = || {
+            tool.render_modified(
+                &cli.render,
+                &ChartOptions::default(),
+                &jobs,
+                &mut last_modified,
+            );
+            log.0.take()
+        };
+
+        assert_eq!(render().len(), 1);
+        assert!(fs::read_to_string(&output)
+            .unwrap()
+            .contains("aria-label=\"First\""));
+        // Nothing is rendered until the input changes
+        assert!(render().is_empty());
+
+        write("{ title: 'Second', items: [{ key: 'a', value: 1 }] }", 2);
+
+        assert!(render()[0].starts_with("Rendered"));
+        assert!(fs::read_to_string(&output)
+            .unwrap()
+            .contains("aria-label=\"Second\""));
+
+        // A broken input is reported without stopping the watch or touching the output
+        write("{ title: ", 3);
+
+        assert!(render()[0].starts_with("error: "));
+        assert!(fs::read_to_string(&output)
+            .unwrap()
+            .contains("aria-label=\"Second\""));
+
+        // Watching starts only with files to read and write, and without overwriting by accident
+        assert!(matches!(
+            dir.run(&SilentLog, ["--watch"]),
+            Err(PieChartError::Usage(_))
+        ));
+        assert!(matches!(
+            dir.run(
+                &SilentLog,
+                [OsString::from("--watch"), input.into(), output.into()]
+            ),
+            Err(PieChartError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn tooltips_test() {
+        let svg = svg_string(
+            &json5::from_str(
+                "{ title: 'T', items: [{ key: '<a>', value: 1 }, { key: 'b', value: 2 }] }",
+            )
+            .unwrap(),
+            &ChartOptions::default(),
+        )
+        .unwrap();
+        let tooltip = "<title>&lt;a&gt;: 1 (33.33333333333333%)</title>";
+
+        // Each wedge and its legend block has the exact value and percentage
+        assert_eq!(svg.matches(tooltip).count(), 2);
+        assert!(svg.contains(&format!(
+            "class=\"wedge-0\" d=\"M240,40 A200,200,0,0,1,413.20508,340 L240,240 z\">\n{}",
+            tooltip
+        )));
+        assert!(svg.contains(&format!("class=\"wedge-0\" height=\"20\" rx=\"3\" ry=\"3\" width=\"20\" x=\"10\" y=\"450\">\n{}", tooltip)));
+        assert_eq!(
+            svg.matches("<title>b: 2 (66.66666666666666%)</title>")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn accessibility_test() {
+        let svg = svg_string(
+            &json5::from_str(
+                "{ title: 'A & B', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+            )
+            .unwrap(),
+            &ChartOptions::default(),
+        )
+        .unwrap();
+
+        assert!(svg.starts_with("<svg aria-label=\"A &amp; B\""));
+        assert!(svg.contains(" role=\"img\" "));
+        assert!(svg.contains(
+            "<title>A &amp; B</title>\n<desc>Pie chart with 2 wedges: a (25%), b (75%)</desc>"
+        ));
+        assert!(svg.contains("<path aria-label=\"a: 1 (25%)\" class=\"wedge-0\""));
+        assert!(svg.contains("<path aria-label=\"b: 3 (75%)\" class=\"wedge-1\""));
+    }
+
+    #[test]
+    fn wedge_stroke_test() {
+        let chart_data: ChartData =
+            json5::from_str("{ title: '', items: [{ key: 'a', value: 1 }] }").unwrap();
+        let stroke = |options: ChartOptions| {
+            svg_string(&chart_data, &options).map(|svg| {
+                let start = svg.find(".wedge-0{").unwrap();
+                let end = start + svg[start..].find('}').unwrap();
+
+                svg[start..end].split_once(';').unwrap().1.to_string()
+            })
+        };
+
+        assert_eq!(stroke(ChartOptions::default()).unwrap(), "stroke-width:0");
+        assert_eq!(
+            stroke(ChartOptions {
+                stroke_color: Some("red".to_string()),
+                stroke_width: Some(2.0),
+                ..Default::default()
+            })
+            .unwrap(),
+            "stroke:red;stroke-width:2"
+        );
+        // Either option alone draws a separator in the background color, one unit wide by default
+        assert_eq!(
+            stroke(ChartOptions {
+                stroke_width: Some(3.0),
+                background: Some("transparent".to_string()),
+                ..Default::default()
+            })
+            .unwrap(),
+            "stroke:white;stroke-width:3"
+        );
+        assert_eq!(
+            stroke(ChartOptions {
+                stroke_color: Some("#123456".to_string()),
+                ..Default::default()
+            })
+            .unwrap(),
+            "stroke:#123456;stroke-width:1"
+        );
+        assert!(matches!(
+            stroke(ChartOptions {
+                stroke_width: Some(-1.0),
+                ..Default::default()
+            }),
+            Err(PieChartError::Validation(_))
+        ));
+        assert!(stroke(ChartOptions {
+            stroke_color: Some("not a color".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn pattern_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+        )
+        .unwrap();
+        let chart = |pattern| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    pattern,
+                    seed: Some(1),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        assert!(!chart(Some(PatternMode::None)).contains("<pattern"));
+
+        let overlay = chart(Some(PatternMode::Overlay));
+
+        assert!(overlay.contains(".wedge-0{fill:url(#pattern-0);stroke-width:0}"));
+        assert!(overlay.contains(".wedge-1{fill:url(#pattern-1);stroke-width:0}"));
+        // The hatching is drawn over the wedge color, and differs between wedges
+        assert!(overlay.contains(
+            "<pattern height=\"8\" id=\"pattern-0\" patternUnits=\"userSpaceOnUse\" width=\"8\">\n\
+             <rect fill=\"#7c4080\" height=\"8\" width=\"8\"/>\n\
+             <path d=\"M0,8 L8,0 M-2,2 L2,-2 M6,10 L10,6\" stroke=\"#ffffff\" stroke-width=\"1.5\"/>"
+        ));
+        assert!(
+            overlay.contains("<path d=\"M0,0 L8,8 M-2,6 L2,10 M6,-2 L10,2\" stroke=\"#ffffff\"")
+        );
+
+        let only = chart(Some(PatternMode::Only));
+
+        // Black on white, so the wedges differ only by their patterns
+        assert_eq!(
+            only.matches("<rect fill=\"#ffffff\" height=\"8\" width=\"8\"/>")
+                .count(),
+            2
+        );
+        assert_eq!(
+            only.matches("stroke=\"#000000\" stroke-width=\"1.5\"")
+                .count(),
+            2
+        );
+        assert!(!only.contains("#7c4080"));
+    }
+
+    #[test]
+    fn animate_test() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+        )
+        .unwrap();
+        let chart = |animate| {
+            svg_string(
+                &chart_data,
+                &ChartOptions {
+                    animate,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        assert!(!chart(None).contains("@keyframes"));
+
+        let animated = chart(Some(true));
+
+        // The wedges grow from the center of the pie, one after the other
+        assert!(animated.contains(
+            ".pie path{transform-origin:240px 240px;animation:pie-grow 0.6s ease-out both}"
+        ));
+        assert!(animated.contains(".pie .wedge-0{animation-delay:0.000s}"));
+        assert!(animated.contains(".pie .wedge-1{animation-delay:0.300s}"));
+        assert!(animated
+            .contains("@keyframes pie-grow{from{transform:scale(0)}to{transform:scale(1)}}"));
+        assert!(animated.contains("@keyframes pie-fade{"));
+        assert!(animated.contains("@media (prefers-reduced-motion:reduce)"));
+    }
+
+    #[test]
+    fn exit_code_test() {
+        let dir = TempDir::new("exit_code");
+        let exit_code = |content: &str| {
+            let input = dir.join("chart.json5");
+
+            fs::write(&input, content).unwrap();
+            dir.run(&SilentLog, [OsString::from("--check"), input.into()])
+                .unwrap_err()
+                .exit_code()
+        };
+
+        assert_eq!(
+            dir.run(&SilentLog, ["--no-such-option"])
+                .unwrap_err()
+                .exit_code(),
+            2
+        );
+        assert_eq!(exit_code("{ title: "), 3);
+        // Every problem with the chart itself has the same code
+        assert_eq!(exit_code("{ title: '', items: [] }"), 4);
+        assert_eq!(
+            exit_code("{ title: '', items: [{ key: 'a', value: -1 }] }"),
+            4
+        );
+        assert_eq!(
+            exit_code("{ title: '', items: [{ key: 'a', value: 1, color: 'nope' }] }"),
+            4
+        );
+        assert_eq!(
+            dir.run(&SilentLog, [dir.join("missing.json5")])
+                .unwrap_err()
+                .exit_code(),
+            5
+        );
+
+        let template = dir.join("chart.txt");
+
+        fs::write(
+            dir.join("chart.json5"),
+            "{ title: '', items: [{ key: 'a', value: 1 }] }",
+        )
+        .unwrap();
+        fs::write(&template, "{nope}").unwrap();
+        assert_eq!(
+            dir.run(
+                &SilentLog,
+                [
+                    OsString::from("--template"),
+                    template.into(),
+                    dir.join("chart.json5").into(),
+                    dir.join("out.txt").into(),
+                ]
+            )
+            .unwrap_err()
+            .exit_code(),
+            6
+        );
+    }
+
+    #[test]
+    fn open_test() {
+        let command = PieChartTool::open_command(Path::new("my chart.svg"));
+        let args: Vec<_> = command.get_args().collect();
+
+        // The path is passed as the one last argument, so spaces in it need no quoting
+        assert_eq!(args.last().unwrap(), &OsStr::new("my chart.svg"));
+
+        if cfg!(target_os = "macos") {
+            assert_eq!(command.get_program(), "open");
+        } else if cfg!(windows) {
+            assert_eq!(command.get_program(), "cmd");
+            assert_eq!(args[..3], ["/C", "start", ""]);
+        } else {
+            assert_eq!(command.get_program(), "xdg-open");
+            assert_eq!(args.len(), 1);
         }
+    }
 
-        let logger = TestLogger::new();
-        let mut tool = PieChartTool::new(&logger);
-        let args: Vec<std::ffi::OsString> = vec!["".into(), "--help".into()];
+    #[test]
+    fn check_out_dir_test() {
+        let dir = TempDir::new("check_out_dir");
+        let (input, out_dir) = (dir.join("chart.json5"), dir.join("charts"));
 
-        tool.run(args).unwrap();
+        fs::write(&input, "{ title: '', items: [{ key: 'a', value: 1 }] }").unwrap();
+        dir.run(
+            &SilentLog,
+            [
+                OsString::from("--check"),
+                "--out-dir".into(),
+                out_dir.clone().into(),
+                input.into(),
+            ],
+        )
+        .unwrap();
+
+        assert!(!out_dir.exists());
     }
 }
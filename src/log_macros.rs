@@ -26,3 +26,23 @@ macro_rules! error {
     $log.error(format_args!($fmt, $($args)+))
   };
 }
+
+#[macro_export]
+macro_rules! debug {
+  ($log: expr, $fmt: expr) => {
+    $log.debug(format_args!($fmt))
+  };
+  ($log: expr, $fmt: expr, $($args: tt)+) => {
+    $log.debug(format_args!($fmt, $($args)+))
+  };
+}
+
+#[macro_export]
+macro_rules! trace {
+  ($log: expr, $fmt: expr) => {
+    $log.trace(format_args!($fmt))
+  };
+  ($log: expr, $fmt: expr, $($args: tt)+) => {
+    $log.trace(format_args!($fmt, $($args)+))
+  };
+}
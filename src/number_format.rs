@@ -0,0 +1,315 @@
+const SI_PREFIXES: [(f64, &str); 4] = [(1e12, "T"), (1e9, "G"), (1e6, "M"), (1e3, "k")];
+const NBSP: char = '\u{a0}';
+const NARROW_NBSP: char = '\u{202f}';
+/// Symbols that make units a currency, which is placed as the locale places it
+const CURRENCY_SYMBOLS: &str = "$€£¥₹₩₽₺₪₫₴₦฿₱₲₵₡¢";
+/// Units written straight after the number, without a space
+const UNSPACED_UNITS: [&str; 3] = ["%", "‰", "°"];
+
+/// The separators and percent sign and currency placement for a language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Locale {
+    decimal_point: char,
+    group_separator: char,
+    percent_first: bool,
+    percent_space: Option<char>,
+    currency_first: bool,
+    currency_space: Option<char>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            decimal_point: '.',
+            group_separator: ',',
+            percent_first: false,
+            percent_space: None,
+            currency_first: true,
+            currency_space: None,
+        }
+    }
+}
+
+impl Locale {
+    /// Look up a locale from a tag such as `de`, `fr-CA` or `pt_BR`. Only the language is used.
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let language = tag
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let (decimal_point, group_separator, percent_first, percent_space) = match language.as_str()
+        {
+            "en" | "ja" | "zh" | "ko" | "he" | "ar" => ('.', ',', false, None),
+            "de" | "es" => (',', '.', false, Some(NBSP)),
+            "it" | "nl" | "pt" | "id" => (',', '.', false, None),
+            "fr" => (',', NARROW_NBSP, false, Some(NARROW_NBSP)),
+            "sv" | "nb" | "fi" | "ru" | "pl" | "cs" => (',', NBSP, false, Some(NBSP)),
+            "tr" => (',', '.', true, None),
+            _ => return None,
+        };
+        let (currency_first, currency_space) = match language.as_str() {
+            "en" | "ja" | "zh" | "ko" | "tr" | "id" => (true, None),
+            "nl" | "pt" => (true, Some(NBSP)),
+            _ => (false, Some(NBSP)),
+        };
+
+        Some(Locale {
+            decimal_point,
+            group_separator,
+            percent_first,
+            percent_space,
+            currency_first,
+            currency_space,
+        })
+    }
+}
+
+/// Formats item values and percentages for legends and labels
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NumberFormatter {
+    /// Decimal places for values, or `None` for as many as needed
+    pub value_decimals: Option<usize>,
+    /// Decimal places for percentages
+    pub percent_decimals: usize,
+    /// Group the thousands in the integer part
+    pub thousands_separator: bool,
+    /// Scale large values with a k/M/G/T suffix
+    pub si_prefix: bool,
+    /// Separators and percent sign and currency placement
+    pub locale: Locale,
+    /// The units of values, such as `GB` or `€`
+    pub units: Option<String>,
+}
+
+impl NumberFormatter {
+    /// Format an item value, with its units if it has any. Currencies are placed before or after
+    /// the number as the locale places them, and other units after it with a space unless they
+    /// are a sign like `%`.
+    pub fn value(self: &Self, value: f64) -> String {
+        let text = self.unitless_value(value);
+        let Some(units) = self.units.as_deref().filter(|units| !units.is_empty()) else {
+            return text;
+        };
+        let is_currency = units.ends_with(|c| CURRENCY_SYMBOLS.contains(c));
+
+        if !is_currency {
+            let space = if UNSPACED_UNITS.contains(&units) {
+                ""
+            } else {
+                "\u{a0}"
+            };
+
+            return format!("{}{}{}", text, space, units);
+        }
+
+        let space = self
+            .locale
+            .currency_space
+            .map(String::from)
+            .unwrap_or_default();
+
+        match text.strip_prefix('-') {
+            Some(number) if self.locale.currency_first => {
+                format!("-{}{}{}", units, space, number)
+            }
+            _ if self.locale.currency_first => format!("{}{}{}", units, space, text),
+            _ => format!("{}{}{}", text, space, units),
+        }
+    }
+
+    fn unitless_value(self: &Self, value: f64) -> String {
+        if self.si_prefix {
+            if let Some((scale, prefix)) =
+                SI_PREFIXES.iter().find(|(scale, _)| value.abs() >= *scale)
+            {
+                let scaled = value / scale;
+                let text = match self.value_decimals {
+                    Some(decimals) => self.number(scaled, decimals),
+                    None => self.trim_zeros(self.number(scaled, 1)),
+                };
+
+                return format!("{}{}", text, prefix);
+            }
+        }
+
+        match self.value_decimals {
+            Some(decimals) => self.number(value, decimals),
+            None => self.group(&value.to_string()),
+        }
+    }
+
+    /// Format a fraction of the whole as a percentage, including the percent sign
+    pub fn percent(self: &Self, fraction: f64) -> String {
+        let number = self.percent_number(fraction);
+        let space = self
+            .locale
+            .percent_space
+            .map(String::from)
+            .unwrap_or_default();
+
+        if self.locale.percent_first {
+            format!("%{}{}", space, number)
+        } else {
+            format!("{}{}%", number, space)
+        }
+    }
+
+    /// Format a fraction of the whole as the number of a percentage, without the percent sign
+    pub fn percent_number(self: &Self, fraction: f64) -> String {
+        self.number(fraction * 100.0, self.percent_decimals)
+    }
+
+    /// Round fractions of the whole to the percentage decimal places by the largest remainder
+    /// method, so that the displayed percentages add up to exactly 100
+    pub fn round_percentages(self: &Self, fractions: &[f64]) -> Vec<f64> {
+        let scale = 100.0 * 10f64.powi(self.percent_decimals as i32);
+        let scaled: Vec<f64> = fractions.iter().map(|fraction| fraction * scale).collect();
+        // Allow for values like 28.999999999999996 that should be whole
+        let mut units: Vec<f64> = scaled.iter().map(|s| (s + 1e-9).floor()).collect();
+        let missing = (scale - units.iter().sum::<f64>()).round().max(0.0) as usize;
+        let mut order: Vec<usize> = (0..units.len()).collect();
+
+        order.sort_by(|&a, &b| (scaled[b] - units[b]).total_cmp(&(scaled[a] - units[a])));
+
+        for &i in order.iter().take(missing) {
+            units[i] += 1.0;
+        }
+
+        units.iter().map(|units| units / scale).collect()
+    }
+
+    fn number(self: &Self, value: f64, decimals: usize) -> String {
+        self.group(&format!("{:.*}", decimals, value))
+    }
+
+    fn group(self: &Self, text: &str) -> String {
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text),
+        };
+        let (integer, fraction) = match digits.find('.') {
+            Some(index) => digits.split_at(index),
+            None => (digits, ""),
+        };
+        let mut grouped = String::new();
+
+        for (i, c) in integer.chars().enumerate() {
+            if self.thousands_separator && i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(self.locale.group_separator);
+            }
+
+            grouped.push(c);
+        }
+
+        if let Some(fraction) = fraction.strip_prefix('.') {
+            grouped.push(self.locale.decimal_point);
+            grouped.push_str(fraction);
+        }
+
+        format!("{}{}", sign, grouped)
+    }
+
+    fn trim_zeros(self: &Self, text: String) -> String {
+        if text.contains(self.locale.decimal_point) {
+            text.trim_end_matches('0')
+                .trim_end_matches(self.locale.decimal_point)
+                .to_string()
+        } else {
+            text
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_values_and_percentages() {
+        let mut formatter = NumberFormatter::default();
+
+        assert_eq!(formatter.value(1234.5), "1234.5");
+        assert_eq!(formatter.percent(0.004), "0%");
+
+        formatter.percent_decimals = 1;
+        formatter.thousands_separator = true;
+
+        assert_eq!(formatter.value(-1234567.25), "-1,234,567.25");
+        assert_eq!(formatter.percent(0.004), "0.4%");
+
+        formatter.si_prefix = true;
+
+        assert_eq!(formatter.value(1234.0), "1.2k");
+        assert_eq!(formatter.value(3_000_000.0), "3M");
+        assert_eq!(formatter.value(999.0), "999");
+
+        formatter.value_decimals = Some(2);
+
+        assert_eq!(formatter.value(3_000_000.0), "3.00M");
+    }
+
+    #[test]
+    fn rounds_percentages_to_100() {
+        let mut formatter = NumberFormatter::default();
+        let thirds = formatter.round_percentages(&[1.0 / 3.0; 3]);
+
+        assert_eq!(thirds, vec![0.34, 0.33, 0.33]);
+
+        formatter.percent_decimals = 1;
+
+        let percentages: Vec<String> = formatter
+            .round_percentages(&[0.1234, 0.2345, 0.6421])
+            .into_iter()
+            .map(|fraction| formatter.percent(fraction))
+            .collect();
+
+        assert_eq!(percentages, vec!["12.3%", "23.5%", "64.2%"]);
+    }
+
+    #[test]
+    fn formats_for_locales() {
+        let mut formatter = NumberFormatter {
+            percent_decimals: 1,
+            thousands_separator: true,
+            locale: Locale::from_tag("de-DE").unwrap(),
+            ..Default::default()
+        };
+
+        assert_eq!(formatter.value(1234.5), "1.234,5");
+        assert_eq!(formatter.percent(0.125), "12,5\u{a0}%");
+
+        formatter.locale = Locale::from_tag("fr").unwrap();
+
+        assert_eq!(formatter.value(1234.5), "1\u{202f}234,5");
+
+        formatter.locale = Locale::from_tag("tr_TR").unwrap();
+
+        assert_eq!(formatter.percent(0.5), "%50,0");
+        assert_eq!(Locale::from_tag("xx"), None);
+    }
+
+    #[test]
+    fn formats_units() {
+        let mut formatter = NumberFormatter {
+            thousands_separator: true,
+            units: Some("GB".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(formatter.value(1234.0), "1,234\u{a0}GB");
+
+        formatter.units = Some("%".to_string());
+
+        assert_eq!(formatter.value(12.0), "12%");
+
+        formatter.units = Some("$".to_string());
+
+        assert_eq!(formatter.value(-1234.0), "-$1,234");
+
+        formatter.units = Some("€".to_string());
+        formatter.locale = Locale::from_tag("de").unwrap();
+
+        assert_eq!(formatter.value(1234.0), "1.234\u{a0}€");
+    }
+}
@@ -0,0 +1,161 @@
+pub(crate) mod animated;
+pub(crate) mod compact;
+pub(crate) mod embed;
+pub(crate) mod graphics;
+pub(crate) mod html;
+pub(crate) mod pdf;
+pub(crate) mod pretty;
+pub(crate) mod raster;
+pub(crate) mod svg;
+pub(crate) mod template;
+pub(crate) mod term;
+
+use crate::{PieChartError, RenderData};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Draws charts that are already laid out in an output format. The layout is all worked out by
+/// `RenderData`, so a format can be added without changing it.
+pub(crate) trait Renderer {
+    type Output;
+
+    /// Draw the charts into one document, or one page each for formats with pages
+    fn render(self: &Self, charts: &mut [RenderData]) -> Result<Self::Output, PieChartError>;
+}
+
+/// The color as a 24 bit RGB value, for formats without CSS colors. Anything that is not a
+/// color, such as `transparent`, is drawn in gray.
+pub(crate) fn rgb(color: &str) -> u32 {
+    crate::color::rgb(color).unwrap_or(0x808080)
+}
+
+/// The format of the rendered chart
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// SVG, with several charts laid out in a grid
+    #[default]
+    Svg,
+    /// PDF, with one page per chart
+    Pdf,
+    /// An HTML page holding the SVG, with tooltips showing the exact values
+    Html,
+    /// Text drawn with block characters and colors for a terminal
+    Term,
+    /// Text for a terminal with the pie drawn as a Sixel image
+    Sixel,
+    /// Text for a terminal with the pie drawn as an image in the Kitty graphics protocol
+    Kitty,
+    /// The SVG as a base64 `data:image/svg+xml` URI, for HTML attributes
+    DataUri,
+    /// A Markdown image of the SVG as a data URI, titled with the chart title
+    Markdown,
+}
+
+impl OutputFormat {
+    /// Guess the format from the output file extension, falling back to SVG
+    pub fn detect(path: Option<&Path>) -> OutputFormat {
+        let extension = path
+            .and_then(|path| path.extension())
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("pdf") => OutputFormat::Pdf,
+            Some("html") | Some("htm") => OutputFormat::Html,
+            Some("md") => OutputFormat::Markdown,
+            _ => OutputFormat::Svg,
+        }
+    }
+
+    /// The file extension used for the format
+    pub fn extension(self: &Self) -> &'static str {
+        match self {
+            OutputFormat::Svg => "svg",
+            OutputFormat::Pdf => "pdf",
+            OutputFormat::Html => "html",
+            OutputFormat::Term | OutputFormat::Kitty | OutputFormat::DataUri => "txt",
+            OutputFormat::Sixel => "six",
+            OutputFormat::Markdown => "md",
+        }
+    }
+}
+
+/// The format of an animation of several charts
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnimationFormat {
+    /// SVG, with the wedges moving smoothly and the frames titled
+    #[default]
+    Svg,
+    /// An animated GIF of the pie alone
+    Gif,
+    /// An animated PNG of the pie alone, which shows as its first frame where APNG is not
+    /// understood
+    Apng,
+}
+
+impl AnimationFormat {
+    /// Guess the format from the output file extension, falling back to SVG
+    pub fn detect(path: Option<&Path>) -> AnimationFormat {
+        let extension = path
+            .and_then(|path| path.extension())
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("gif") => AnimationFormat::Gif,
+            Some("png") | Some("apng") => AnimationFormat::Apng,
+            _ => AnimationFormat::Svg,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChartData, ChartOptions, PieChartTool, SilentLog};
+
+    /// Draws only the angles of the wedges, to check the layout without any output format
+    struct Angles;
+
+    impl Renderer for Angles {
+        type Output = Vec<(f64, f64)>;
+
+        fn render(self: &Self, charts: &mut [RenderData]) -> Result<Self::Output, PieChartError> {
+            let rd = &charts[0];
+            let (mut a, _) = rd.angles();
+
+            Ok(rd
+                .wedges
+                .iter()
+                .map(|wedge| {
+                    let b = a + rd.span(wedge);
+                    let angles = (a.to_degrees().round(), b.to_degrees().round());
+
+                    a = b;
+                    angles
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn renders_laid_out_charts() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+        )
+        .unwrap();
+        let options = ChartOptions {
+            half: Some(true),
+            ..Default::default()
+        };
+        let rd = PieChartTool::new(&SilentLog)
+            .process_chart_data(&chart_data, &options)
+            .unwrap();
+
+        assert_eq!(
+            Angles.render(&mut [rd]).unwrap(),
+            [(-180.0, -135.0), (-135.0, 0.0)]
+        );
+    }
+}
@@ -0,0 +1,447 @@
+//! Animations drawn as a sequence of images, in the GIF and APNG formats, for slides and chat
+//! apps that show images but not animated SVG. Only the pie is drawn, in the colors of a
+//! palette, with the pixels outside it left transparent, so any titles and legend have to be
+//! given beside the image.
+
+use super::raster::{self, Raster};
+use crate::{
+    animation::Animation,
+    input::zip::{DISTANCE_BASE, DISTANCE_EXTRA, LENGTH_BASE, LENGTH_EXTRA},
+    PieChartError, RenderData,
+};
+use std::collections::HashMap;
+
+/// The most codes that GIF compression can have
+const MAX_CODES: usize = 4096;
+/// The farthest back that DEFLATE can repeat bytes from
+const MAX_DISTANCE: usize = 32768;
+
+/// The images of an animation, each with the palette index of every pixel
+pub(crate) struct Frames {
+    width: usize,
+    height: usize,
+    /// The colors of the wedges, after the transparent background at index 0
+    palette: Vec<u32>,
+    /// The pixels of each image and the seconds it is shown for
+    images: Vec<(Vec<u8>, f64)>,
+}
+
+impl Frames {
+    /// Draw the pie with the wedges at the percentages of each sample of the animation, joining
+    /// any samples that are drawn the same
+    pub(crate) fn new(rd: &mut RenderData, animation: &Animation) -> Result<Frames, PieChartError> {
+        let diameter = rd.pie_diameter.round() as usize;
+        let mut palette = vec![0xffffff];
+        let indexes: Vec<usize> = raster::colors(rd)
+            .into_iter()
+            .map(|rgb| {
+                palette.iter().position(|c| *c == rgb).unwrap_or_else(|| {
+                    palette.push(rgb);
+                    palette.len() - 1
+                })
+            })
+            .collect();
+
+        if palette.len() > 256 {
+            return Err(PieChartError::Validation(format!(
+                "Animated images can have at most 255 colors (found {})",
+                palette.len() - 1
+            )));
+        }
+
+        let mut images: Vec<(Vec<u8>, f64)> = vec![];
+        let mut size = (diameter, diameter);
+
+        for (percentages, seconds) in animation.frames() {
+            for (wedge, percentage) in rd.wedges.iter_mut().zip(percentages) {
+                wedge.percentage = *percentage;
+            }
+
+            let raster = Raster::new(rd, diameter);
+            let mut pixels = Vec::with_capacity(raster.width * raster.height);
+
+            for y in 0..raster.height {
+                for x in 0..raster.width {
+                    pixels.push(raster.get(x, y).map_or(0, |index| indexes[index] as u8));
+                }
+            }
+
+            size = (raster.width, raster.height);
+
+            match images.last_mut() {
+                Some((last, shown)) if *last == pixels => *shown += seconds,
+                _ => images.push((pixels, seconds)),
+            }
+        }
+
+        Ok(Frames {
+            width: size.0,
+            height: size.1,
+            palette,
+            images,
+        })
+    }
+
+    /// An animated GIF that repeats forever
+    pub(crate) fn gif(self: &Self) -> Vec<u8> {
+        // The color table has a power of two entries, of which at least four for compression
+        let bits = (2..8)
+            .find(|bits| 1 << bits >= self.palette.len())
+            .unwrap_or(8);
+        let mut out = b"GIF89a".to_vec();
+
+        out.extend((self.width as u16).to_le_bytes());
+        out.extend((self.height as u16).to_le_bytes());
+        out.extend([0xf0 | (bits - 1) as u8, 0, 0]);
+
+        for index in 0..1 << bits {
+            let rgb = self.palette.get(index).copied().unwrap_or_default();
+
+            out.extend([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]);
+        }
+
+        out.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        for (pixels, seconds) in &self.images {
+            let delay = (seconds * 100.0).round().min(u16::MAX as f64) as u16;
+
+            // Each image is cleared before the next, so that the background shows through it
+            out.extend([0x21, 0xf9, 4, 0x09]);
+            out.extend(delay.to_le_bytes());
+            out.extend([0, 0, 0x2c, 0, 0, 0, 0]);
+            out.extend((self.width as u16).to_le_bytes());
+            out.extend((self.height as u16).to_le_bytes());
+            out.extend([0, bits as u8]);
+
+            for block in lzw(pixels, bits).chunks(255) {
+                out.push(block.len() as u8);
+                out.extend(block);
+            }
+
+            out.push(0);
+        }
+
+        out.push(0x3b);
+        out
+    }
+
+    /// An animated PNG that repeats forever, which shows as its first image where APNG is not
+    /// understood
+    pub(crate) fn apng(self: &Self) -> Vec<u8> {
+        let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+        let size = [
+            (self.width as u32).to_be_bytes(),
+            (self.height as u32).to_be_bytes(),
+        ]
+        .concat();
+        let palette: Vec<u8> = self
+            .palette
+            .iter()
+            .flat_map(|rgb| [(rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8])
+            .collect();
+        let mut sequence: u32 = 0;
+
+        chunk(
+            &mut out,
+            b"IHDR",
+            &[size.as_slice(), &[8, 3, 0, 0, 0]].concat(),
+        );
+        chunk(&mut out, b"PLTE", &palette);
+        // Only the background is transparent
+        chunk(&mut out, b"tRNS", &[0]);
+        chunk(
+            &mut out,
+            b"acTL",
+            &[(self.images.len() as u32).to_be_bytes(), [0; 4]].concat(),
+        );
+
+        for (index, (pixels, seconds)) in self.images.iter().enumerate() {
+            let delay = (seconds * 1000.0).round().min(u16::MAX as f64) as u16;
+            let mut control = sequence.to_be_bytes().to_vec();
+
+            control.extend(&size);
+            control.extend([0; 8]);
+            control.extend(delay.to_be_bytes());
+            control.extend(1000u16.to_be_bytes());
+            // Each image replaces the last entirely
+            control.extend([0, 0]);
+            chunk(&mut out, b"fcTL", &control);
+            sequence += 1;
+
+            let mut scanlines = Vec::with_capacity((self.width + 1) * self.height);
+
+            for row in pixels.chunks(self.width) {
+                scanlines.push(0);
+                scanlines.extend(row);
+            }
+
+            let data = zlib(&scanlines, self.width + 1);
+
+            if index == 0 {
+                chunk(&mut out, b"IDAT", &data);
+            } else {
+                chunk(
+                    &mut out,
+                    b"fdAT",
+                    &[&sequence.to_be_bytes(), data.as_slice()].concat(),
+                );
+                sequence += 1;
+            }
+        }
+
+        chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
+
+/// Bits packed into bytes from the least significant bit up, as both GIF and DEFLATE do
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    value: u32,
+    count: u32,
+}
+
+impl Bits {
+    /// Append the low `count` bits of the value, least significant first
+    fn write(self: &mut Self, value: usize, count: u32) {
+        self.value |= (value as u32) << self.count;
+        self.count += count;
+
+        while self.count >= 8 {
+            self.bytes.push(self.value as u8);
+            self.value >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Append a Huffman code, which is written most significant bit first
+    fn code(self: &mut Self, code: usize, length: u32) {
+        for bit in (0..length).rev() {
+            self.write(code >> bit & 1, 1);
+        }
+    }
+
+    fn finish(mut self: Self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.value as u8);
+        }
+
+        self.bytes
+    }
+}
+
+/// Compress the pixels of a GIF image, whose palette indices take `bits` bits, with codes for
+/// each new run of pixels that grow a bit wider whenever they run out
+fn lzw(pixels: &[u8], bits: usize) -> Vec<u8> {
+    let clear = 1 << bits;
+    let first = clear + 2;
+    let mut codes: HashMap<(usize, u8), usize> = HashMap::new();
+    let mut next = first;
+    let mut width = bits as u32 + 1;
+    let mut out = Bits::default();
+    let mut prefix = None;
+
+    out.write(clear, width);
+
+    for &pixel in pixels {
+        let Some(code) = prefix else {
+            prefix = Some(pixel as usize);
+            continue;
+        };
+
+        if let Some(&longer) = codes.get(&(code, pixel)) {
+            prefix = Some(longer);
+            continue;
+        }
+
+        out.write(code, width);
+
+        if next < MAX_CODES {
+            codes.insert((code, pixel), next);
+
+            if next == 1 << width {
+                width += 1;
+            }
+
+            next += 1;
+        } else {
+            // Start again once every code is used
+            out.write(clear, width);
+            codes.clear();
+            next = first;
+            width = bits as u32 + 1;
+        }
+
+        prefix = Some(pixel as usize);
+    }
+
+    if let Some(code) = prefix {
+        out.write(code, width);
+
+        // The decoder adds a code for the last run too, which may widen the end code
+        if next < MAX_CODES && next == 1 << width {
+            width += 1;
+        }
+    }
+
+    out.write(clear + 1, width);
+    out.finish()
+}
+
+/// A PNG chunk, with its length and checksum
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    out.extend(kind);
+    out.extend(data);
+    out.extend(crc32(kind.iter().chain(data)).to_be_bytes());
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    !bytes.fold(!0, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+/// Compress PNG scanlines of `stride` bytes into a zlib stream
+fn zlib(data: &[u8], stride: usize) -> Vec<u8> {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+
+        (a, (b + a) % 65521)
+    });
+
+    [
+        vec![0x78, 0x01],
+        deflate(data, stride),
+        (b << 16 | a).to_be_bytes().to_vec(),
+    ]
+    .concat()
+}
+
+/// Compress data with the fixed Huffman codes of DEFLATE, repeating runs of the byte before or
+/// of the row above, which is most of the pixels of a pie
+fn deflate(data: &[u8], stride: usize) -> Vec<u8> {
+    let mut out = Bits::default();
+    let mut pos = 0;
+
+    // One last block with fixed codes
+    out.write(1, 1);
+    out.write(1, 2);
+
+    while pos < data.len() {
+        let (length, distance) = [1, stride]
+            .into_iter()
+            .filter(|distance| *distance <= pos.min(MAX_DISTANCE))
+            .map(|distance| {
+                let end = data.len().min(pos + 258);
+
+                (
+                    (pos..end)
+                        .take_while(|i| data[*i] == data[i - distance])
+                        .count(),
+                    distance,
+                )
+            })
+            .max()
+            .unwrap_or_default();
+
+        if length < 3 {
+            literal(&mut out, data[pos] as usize);
+            pos += 1;
+            continue;
+        }
+
+        let code = LENGTH_BASE
+            .iter()
+            .rposition(|base| *base <= length)
+            .unwrap_or_default();
+
+        literal(&mut out, 257 + code);
+        out.write(length - LENGTH_BASE[code], LENGTH_EXTRA[code]);
+
+        let code = DISTANCE_BASE
+            .iter()
+            .rposition(|base| *base <= distance)
+            .unwrap_or_default();
+
+        out.code(code, 5);
+        out.write(distance - DISTANCE_BASE[code], DISTANCE_EXTRA[code]);
+        pos += length;
+    }
+
+    literal(&mut out, 256);
+    out.finish()
+}
+
+/// A literal byte, the end of a block or the start of a length, in the fixed Huffman codes
+fn literal(out: &mut Bits, symbol: usize) {
+    match symbol {
+        0..=143 => out.code(0x30 + symbol, 8),
+        144..=255 => out.code(0x190 + symbol - 144, 9),
+        256..=279 => out.code(symbol - 256, 7),
+        _ => out.code(0xc0 + symbol - 280, 8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::zip;
+
+    #[test]
+    fn encodes_images() {
+        let frames = Frames {
+            width: 3,
+            height: 2,
+            palette: vec![0xffffff, 0xff0000, 0x0000ff],
+            images: vec![(vec![0, 1, 1, 2, 2, 2], 0.5), (vec![1; 6], 1.25)],
+        };
+        let gif = frames.gif();
+
+        assert!(gif.starts_with(b"GIF89a\x03\x00\x02\x00\xf1"));
+        assert_eq!(&gif[13..19], [0xff, 0xff, 0xff, 0xff, 0, 0]);
+        assert_eq!(gif.last(), Some(&0x3b));
+
+        let apng = frames.apng();
+        let mut chunks = vec![];
+        let mut pos = 8;
+
+        while pos < apng.len() {
+            let length = u32::from_be_bytes(apng[pos..pos + 4].try_into().unwrap()) as usize;
+            let data = &apng[pos + 8..pos + 8 + length];
+
+            assert_eq!(
+                apng[pos + 8 + length..pos + 12 + length],
+                crc32(apng[pos + 4..pos + 8 + length].iter()).to_be_bytes()
+            );
+            chunks.push((&apng[pos + 4..pos + 8], data));
+            pos += 12 + length;
+        }
+
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| *kind).collect();
+
+        assert_eq!(
+            kinds,
+            [b"IHDR", b"PLTE", b"tRNS", b"acTL", b"fcTL", b"IDAT", b"fcTL", b"fdAT", b"IEND"]
+        );
+        // The second image is shown for 1250 of 1000ths of a second
+        assert_eq!(&chunks[6].1[20..24], [0x04, 0xe2, 0x03, 0xe8]);
+        assert_eq!(
+            zip::inflate(&chunks[5].1[2..]).unwrap(),
+            [0, 0, 1, 1, 0, 2, 2, 2]
+        );
+        assert_eq!(crc32(b"IEND".iter()), 0xae426082);
+    }
+
+    #[test]
+    fn compresses_runs() {
+        let data: Vec<u8> = (0..5000).map(|i| (i / 700 % 3) as u8).collect();
+        let compressed = deflate(&data, 100);
+
+        assert!(compressed.len() < 100);
+        assert_eq!(zip::inflate(&compressed).unwrap(), data);
+    }
+}
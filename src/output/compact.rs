@@ -0,0 +1,119 @@
+//! Shrinks serialized SVG, by rounding coordinates and by removing the line breaks between
+//! elements. Text and styles are left alone, other than the line breaks around them.
+
+/// Attributes holding coordinates or lengths, whose numbers are rounded
+const COORDINATE_ATTRIBUTES: [&str; 16] = [
+    "cx", "cy", "d", "height", "points", "r", "rx", "ry", "viewBox", "width", "x", "x1", "x2", "y",
+    "y1", "y2",
+];
+
+/// Round the numbers in coordinate attributes to at most `digits` decimal places
+pub(crate) fn round_coordinates(svg: &str, digits: usize) -> String {
+    let mut result = String::with_capacity(svg.len());
+    let mut rest = svg;
+
+    // Attribute values are escaped, so tags end at the first '>' and values at the next quote
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..]
+            .find('>')
+            .map_or(rest.len(), |end| start + end + 1);
+
+        result.push_str(&rest[..start]);
+        result.push_str(&round_tag(&rest[start..end], digits));
+        rest = &rest[end..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn round_tag(tag: &str, digits: usize) -> String {
+    let mut result = String::with_capacity(tag.len());
+    let mut rest = tag;
+
+    while let Some(equals) = rest.find("=\"") {
+        let name = rest[..equals]
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or_default();
+        let value_start = equals + 2;
+        let value_end = rest[value_start..]
+            .find('"')
+            .map_or(rest.len(), |end| value_start + end);
+        let value = &rest[value_start..value_end];
+
+        result.push_str(&rest[..value_start]);
+
+        if COORDINATE_ATTRIBUTES.contains(&name) {
+            result.push_str(&round_numbers(value, digits));
+        } else {
+            result.push_str(value);
+        }
+
+        rest = &rest[value_end..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn round_numbers(value: &str, digits: usize) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut number = String::new();
+
+    for c in value.chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_digit() || c == '.' || (c == '-' && number.is_empty()) {
+            number.push(c);
+            continue;
+        }
+
+        if !number.is_empty() {
+            result.push_str(&round_number(&number, digits));
+            number.clear();
+        }
+
+        result.push(c);
+    }
+
+    result.pop();
+    result
+}
+
+fn round_number(number: &str, digits: usize) -> String {
+    match number.parse::<f64>() {
+        Ok(n) if number.contains('.') => {
+            let rounded = format!("{:.*}", digits, n);
+            let rounded = if rounded.contains('.') {
+                rounded.trim_end_matches('0').trim_end_matches('.')
+            } else {
+                &rounded
+            };
+
+            match rounded {
+                "-0" => "0".to_string(),
+                rounded => rounded.to_string(),
+            }
+        }
+        _ => number.to_string(),
+    }
+}
+
+/// Remove the line breaks and indentation between elements
+pub(crate) fn minify(svg: &str) -> String {
+    svg.lines().map(str::trim).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_coordinates_and_minifies() {
+        let svg = "<svg width=\"480.126\">\n<path aria-label=\"a: 33.33333%\" d=\"M240,40 A-0.0001,1.25,0,0,1,413.20508,340\"/>\n<text x=\"10.5\">\n1.23456\n</text>\n</svg>";
+
+        assert_eq!(
+            minify(&round_coordinates(svg, 2)),
+            "<svg width=\"480.13\"><path aria-label=\"a: 33.33333%\" d=\"M240,40 A0,1.25,0,0,1,413.21,340\"/><text x=\"10.5\">1.23456</text></svg>"
+        );
+    }
+}
@@ -0,0 +1,42 @@
+//! SVG as a data URI, and as a Markdown image of one, so that a chart can be pasted straight into
+//! a Markdown document or an HTML attribute without a separate file.
+
+use crate::base64;
+
+/// The SVG as a base64 `data:` URI
+pub(crate) fn data_uri(svg: &str) -> String {
+    format!(
+        "data:image/svg+xml;base64,{}",
+        base64::encode(svg.as_bytes())
+    )
+}
+
+/// A Markdown image of the SVG, with the title as its alternative text
+pub(crate) fn markdown(title: &str, svg: &str) -> String {
+    let alt: String = title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .flat_map(|c| match c {
+            '\\' | '[' | ']' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect();
+
+    format!("![{}]({})", alt, data_uri(svg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embeds_svg() {
+        assert_eq!(data_uri("<svg/>"), "data:image/svg+xml;base64,PHN2Zy8+");
+        assert_eq!(
+            markdown("Costs [2024]\nby team", "<svg/>"),
+            "![Costs \\[2024\\] by team](data:image/svg+xml;base64,PHN2Zy8+)"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! The pie as an image in the escape sequences of a terminal image protocol. Pixels outside the
+//! pie are left transparent, so that the image sits on the terminal background.
+
+use super::raster::Raster;
+use crate::base64;
+use std::fmt::Write;
+
+/// The most base64 bytes the Kitty protocol takes in one escape sequence
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// A protocol for drawing images in a terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Graphics {
+    /// DEC Sixel, understood by xterm, foot, WezTerm, mlterm and others
+    Sixel,
+    /// The Kitty graphics protocol, understood by Kitty, WezTerm, Ghostty and Konsole
+    Kitty,
+}
+
+/// The escape sequences that draw the raster, with `colors` giving the color of each wedge
+pub(crate) fn encode(protocol: Graphics, raster: &Raster, colors: &[u32]) -> String {
+    match protocol {
+        Graphics::Sixel => sixel(raster, colors),
+        Graphics::Kitty => kitty(raster, colors),
+    }
+}
+
+/// Sixel images are drawn in bands six pixels high, one color at a time, with each column of a
+/// band written as a character whose bits are the pixels of that color
+fn sixel(raster: &Raster, colors: &[u32]) -> String {
+    // Pixels that are not drawn keep the background
+    let mut s = format!("\x1bP0;1q\"1;1;{};{}", raster.width, raster.height);
+
+    for (index, rgb) in colors.iter().enumerate() {
+        let percent = |shift: u32| ((rgb >> shift) & 0xff) * 100 / 255;
+        let _ = write!(
+            s,
+            "#{};2;{};{};{}",
+            index,
+            percent(16),
+            percent(8),
+            percent(0)
+        );
+    }
+
+    for top in (0..raster.height).step_by(6) {
+        for index in 0..colors.len() {
+            let sixels: Vec<u8> = (0..raster.width)
+                .map(|x| {
+                    (0..6)
+                        .filter(|row| raster.get(x, top + row) == Some(index))
+                        .fold(0, |bits, row| bits | 1 << row)
+                })
+                .collect();
+
+            // Nothing needs to be written after the last pixel of the color
+            let Some(last) = sixels.iter().rposition(|bits| *bits != 0) else {
+                continue;
+            };
+            let _ = write!(s, "#{}", index);
+            let mut x = 0;
+
+            // Runs of the same character are repeated with a count
+            while x <= last {
+                let run = sixels[x..]
+                    .iter()
+                    .take_while(|bits| **bits == sixels[x])
+                    .count();
+                let c = (b'?' + sixels[x]) as char;
+
+                if run > 3 {
+                    let _ = write!(s, "!{}{}", run, c);
+                } else {
+                    s.extend(std::iter::repeat_n(c, run));
+                }
+
+                x += run;
+            }
+
+            // Go back to the start of the band for the next color
+            s.push('$');
+        }
+
+        s.push('-');
+    }
+
+    s.push_str("\x1b\\");
+    s
+}
+
+/// Kitty images are sent as base64 RGBA pixels, split over as many escape sequences as needed
+fn kitty(raster: &Raster, colors: &[u32]) -> String {
+    let mut pixels = Vec::with_capacity(raster.width * raster.height * 4);
+
+    for y in 0..raster.height {
+        for x in 0..raster.width {
+            pixels.extend(match raster.get(x, y) {
+                Some(index) => {
+                    let rgb = colors[index];
+
+                    [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 0xff]
+                }
+                None => [0; 4],
+            });
+        }
+    }
+
+    let data = base64::encode(&pixels);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+    let mut s = String::new();
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = (index + 1 < chunks.len()) as u8;
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+
+        let _ = if index == 0 {
+            write!(
+                s,
+                "\x1b_Ga=T,f=32,s={},v={},m={};{}\x1b\\",
+                raster.width, raster.height, more, chunk
+            )
+        } else {
+            write!(s, "\x1b_Gm={};{}\x1b\\", more, chunk)
+        };
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChartData, ChartOptions, PieChartTool, SilentLog};
+
+    #[test]
+    fn encodes_images() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1, color: '#ff0000' }, { key: 'b', value: 1, color: '#0000ff' }] }",
+        )
+        .unwrap();
+        let rd = PieChartTool::new(&SilentLog)
+            .process_chart_data(&chart_data, &ChartOptions::default())
+            .unwrap();
+        let raster = Raster::new(&rd, 60);
+        let colors = [0xff0000, 0x0000ff];
+        let sixel = encode(Graphics::Sixel, &raster, &colors);
+
+        assert!(sixel.starts_with("\x1bP0;1q\"1;1;60;60#0;2;100;0;0#1;2;0;0;100"));
+        assert_eq!(sixel.matches('-').count(), 10);
+        assert!(sixel.ends_with("-\x1b\\"));
+
+        let kitty = encode(Graphics::Kitty, &raster, &colors);
+        let sequences: Vec<&str> = kitty.split("\x1b\\").filter(|s| !s.is_empty()).collect();
+
+        // 60 by 60 RGBA pixels are 19200 base64 characters, in chunks of at most 4096
+        assert_eq!(sequences.len(), 5);
+        assert!(sequences[0].starts_with("\x1b_Ga=T,f=32,s=60,v=60,m=1;"));
+        assert!(sequences[4].starts_with("\x1b_Gm=0;"));
+    }
+}
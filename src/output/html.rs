@@ -0,0 +1,143 @@
+//! Wraps a rendered chart in a standalone HTML page. The tooltip text for each chart is kept in
+//! a script rather than in attributes, and matched to the wedges and legend blocks by their
+//! class, which is `wedge-N`, or `wedge-N-M` and so on for sunburst rings, unless the item has
+//! its own.
+
+use crate::{RenderData, WedgeData};
+use std::collections::BTreeMap;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html dir="{dir}">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body{margin:0;padding:16px;background-color:{background}}
+#tooltip{position:fixed;display:none;white-space:pre-line;padding:4px 8px;border-radius:3px;background:rgba(0,0,0,0.8);color:#fff;font:12px Arial,sans-serif;pointer-events:none}
+</style>
+</head>
+<body>
+{svg}
+<div id="tooltip"></div>
+<script>
+const tooltips = {tooltips};
+const tooltip = document.getElementById("tooltip");
+const root = document.querySelector("svg");
+const charts = root.querySelectorAll(":scope > svg");
+
+(charts.length ? [...charts] : [root]).forEach((chart, index) => {
+  Object.entries(tooltips[index]).forEach(([name, text]) => chart.querySelectorAll("." + name).forEach((element) => {
+    // Replace the native SVG tooltip
+    element.querySelector("title")?.remove();
+
+    element.addEventListener("mousemove", (event) => {
+      tooltip.textContent = text;
+      tooltip.style.left = event.clientX + 12 + "px";
+      tooltip.style.top = event.clientY + 12 + "px";
+      tooltip.style.display = "block";
+    });
+    element.addEventListener("mouseleave", () => {
+      tooltip.style.display = "none";
+    });
+  }));
+});
+</script>
+</body>
+</html>
+"#;
+
+pub(crate) fn render(svg: &str, render_data: &[RenderData]) -> String {
+    let tooltips: Vec<BTreeMap<String, String>> = render_data
+        .iter()
+        .map(|rd| {
+            let mut tooltips = BTreeMap::new();
+
+            for (level, wedges) in rd.rings() {
+                add_tooltips(&mut tooltips, rd, wedges, level);
+            }
+
+            tooltips
+        })
+        .collect();
+    // Keep the JSON from closing the script element early
+    let tooltips = serde_json::to_string(&tooltips)
+        .unwrap()
+        .replace("</", "<\\/");
+
+    fill(
+        PAGE,
+        &[
+            ("{dir}", if render_data[0].rtl { "rtl" } else { "ltr" }),
+            ("{title}", &escape(&render_data[0].title)),
+            ("{background}", &render_data[0].background),
+            ("{svg}", svg),
+            ("{tooltips}", &tooltips),
+        ],
+    )
+}
+
+/// Add the tooltips of the wedges and those outside them, keyed by class. The wedges of each
+/// series share the class of their key, so their tooltips are on lines of their own.
+fn add_tooltips(
+    tooltips: &mut BTreeMap<String, String>,
+    rd: &RenderData,
+    wedges: &[WedgeData],
+    level: usize,
+) {
+    for wedge in wedges {
+        add_tooltips(tooltips, rd, &wedge.children, level + 1);
+
+        let tooltip = rd.wedge_tooltip(wedge, level);
+
+        tooltips
+            .entry(wedge.class.clone())
+            .and_modify(|text| {
+                text.push('\n');
+                text.push_str(&tooltip);
+            })
+            .or_insert(tooltip);
+    }
+}
+
+/// Replace each placeholder in a single pass, so that values are never searched for them
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut page = String::new();
+    let mut rest = template;
+
+    for (placeholder, value) in values {
+        let (before, after) = rest.split_once(placeholder).unwrap();
+
+        page.push_str(before);
+        page.push_str(value);
+        rest = after;
+    }
+
+    page.push_str(rest);
+    page
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{render_html, ChartData, ChartOptions};
+
+    #[test]
+    fn embeds_the_svg_and_tooltips() {
+        let chart_data: ChartData = json5::from_str(
+            r#"{ title: "<Test>", items: [{ key: "a</script>", value: 1 }, { key: "b", value: 2 }] }"#,
+        )
+        .unwrap();
+        let html = render_html(&[chart_data], &ChartOptions::default()).unwrap();
+
+        assert!(html.contains("<title>&lt;Test&gt;</title>"));
+        assert!(html.contains("<svg"));
+        assert!(html.contains(
+            r#"const tooltips = [{"wedge-0":"a<\/script>: 1 (33.33333333333333%)","wedge-1":"b: 2 (66.66666666666666%)"}];"#
+        ));
+    }
+}
@@ -0,0 +1,597 @@
+//! A minimal PDF writer that draws charts with the standard Helvetica fonts, one chart per page.
+//! Styles from the chart data or a CSS file and pattern fills only apply to SVG output, and text
+//! is limited to the Latin-1 characters of the standard encoding.
+
+use super::Renderer;
+use crate::{
+    callout, text, LabelPosition, PieChartError, RenderData, WedgeData, CAPTION_FONT_SIZE,
+    FULL_PERCENTAGE, LABEL_FONT_SIZE, LEGEND_FONT_SIZE, LEGEND_LINE_HEIGHT,
+};
+use std::f64::consts::FRAC_PI_2;
+use std::fmt::Write;
+
+/// SVG user units are CSS pixels, 96 to the inch, while PDF uses points, 72 to the inch
+const POINTS_PER_PIXEL: f64 = 0.75;
+/// The curve control point distance for a quarter circle of radius 1
+const KAPPA: f64 = 0.552_284_75;
+
+pub(crate) struct PdfRenderer;
+
+impl Renderer for PdfRenderer {
+    type Output = Vec<u8>;
+
+    fn render(self: &Self, charts: &mut [RenderData]) -> Result<Vec<u8>, PieChartError> {
+        Ok(render(charts))
+    }
+}
+
+/// Render each chart to a page of a PDF document
+fn render(pages: &[RenderData]) -> Vec<u8> {
+    // Objects are numbered from 1 in this order: the catalog, the page tree, the regular and
+    // bold fonts, then a page and its content stream for each chart
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|i| format!("{} 0 R", 5 + i * 2))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+
+    for (index, rd) in pages.iter().enumerate() {
+        let (width, height) = rd.size();
+        let content = content(rd);
+
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+             /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            width * POINTS_PER_PIXEL,
+            height * POINTS_PER_PIXEL,
+            6 + index * 2
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = vec![];
+
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n{}\nendobj\n", index + 1, object).bytes());
+    }
+
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+
+    for offset in offsets {
+        // Each entry must be exactly 20 bytes
+        let _ = writeln!(trailer, "{:010} 00000 n ", offset);
+    }
+
+    let _ = write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.extend(trailer.bytes());
+
+    pdf
+}
+
+/// The content stream for a chart, drawn with the same geometry as the SVG
+fn content(rd: &RenderData) -> String {
+    let mut s = String::new();
+    let (width, height) = rd.size();
+    let (x_center, y_center) = rd.center();
+
+    // Flip the page so that the origin is at the top left, as in SVG
+    let _ = writeln!(
+        s,
+        "{} 0 0 {} 0 {:.2} cm",
+        POINTS_PER_PIXEL,
+        -POINTS_PER_PIXEL,
+        height * POINTS_PER_PIXEL
+    );
+    if rd.has_background() {
+        fill_color(&mut s, &rd.background);
+        let _ = writeln!(s, "0 0 {:.2} {:.2} re f", width, height);
+    }
+
+    if let Some((ref color, stroke_width)) = rd.wedge_stroke {
+        stroke_color(&mut s, color);
+        let _ = writeln!(s, "{} w", stroke_width);
+    }
+
+    let paint = if rd.wedge_stroke.is_some() { "b" } else { "f" };
+    let (start, _) = rd.angles();
+
+    if rd.waffle {
+        let size = rd.waffle_cell_size();
+
+        for (index, x, y) in rd.waffle_cells() {
+            fill_color(&mut s, &rd.wedges[index].color);
+            rounded_rect(&mut s, x, y, size, rd.legend_rect_corner_radius);
+            let _ = writeln!(s, "{}", paint);
+        }
+    } else if rd.bars {
+        for (index, x, y, width, height) in rd.bars() {
+            let wedge = &rd.wedges[index];
+
+            fill_color(&mut s, &wedge.color);
+            let _ = writeln!(
+                s,
+                "{:.2} {:.2} {:.2} {:.2} re {}",
+                x, y, width, height, paint
+            );
+
+            if let Some(ref label) = wedge.label {
+                fill_color(&mut s, &rd.text_color);
+                text(
+                    &mut s,
+                    label,
+                    x + width + callout::TEXT_GAP,
+                    y + height / 2.0 + LABEL_FONT_SIZE * 0.35,
+                    LABEL_FONT_SIZE,
+                    false,
+                );
+            }
+        }
+    } else if rd.treemap {
+        for (index, x, y, width, height) in rd.treemap() {
+            let wedge = &rd.wedges[index];
+
+            fill_color(&mut s, &wedge.color);
+            let _ = writeln!(
+                s,
+                "{:.2} {:.2} {:.2} {:.2} re {}",
+                x, y, width, height, paint
+            );
+
+            if let Some(label) = wedge
+                .label
+                .as_ref()
+                .filter(|label| rd.fits_in(label, width, height))
+            {
+                fill_color(
+                    &mut s,
+                    if wedge.label_inverse {
+                        "#ffffff"
+                    } else {
+                        "#000000"
+                    },
+                );
+                text(
+                    &mut s,
+                    label,
+                    x + (width - text::width(label, LABEL_FONT_SIZE)) / 2.0,
+                    y + height / 2.0 + LABEL_FONT_SIZE * 0.35,
+                    LABEL_FONT_SIZE,
+                    false,
+                );
+            }
+        }
+    } else {
+        for (level, wedges) in rd.rings() {
+            ring(&mut s, rd, wedges, start, level, paint);
+        }
+    }
+
+    for (level, wedges) in rd.rings().filter(|_| !rd.waffle && !rd.bars && !rd.treemap) {
+        let mut a = start;
+
+        for wedge in wedges {
+            let b = a + rd.span(wedge);
+            let (inner_radius, radius) = rd.wedge_radii(wedge, level);
+
+            if let Some(label) = wedge
+                .label
+                .as_ref()
+                .filter(|_| wedge.percentage > 0.0 && rd.label_position == LabelPosition::Inside)
+            {
+                let label_radius = if rd.is_whole(wedge) && inner_radius == 0.0 {
+                    0.0
+                } else {
+                    (radius + inner_radius) / 2.0
+                };
+                let mid = (a + b) / 2.0;
+
+                fill_color(
+                    &mut s,
+                    if wedge.label_inverse {
+                        "#ffffff"
+                    } else {
+                        "#000000"
+                    },
+                );
+                // Centered both ways, like the SVG labels
+                text(
+                    &mut s,
+                    label,
+                    x_center + label_radius * mid.cos() - text::width(label, LABEL_FONT_SIZE) / 2.0,
+                    y_center + label_radius * mid.sin() + LABEL_FONT_SIZE * 0.35,
+                    LABEL_FONT_SIZE,
+                    false,
+                );
+            }
+
+            a = b;
+        }
+    }
+
+    if rd.breakout.is_some() {
+        breakout(&mut s, rd, paint);
+    }
+
+    let callouts = rd.callouts();
+
+    if !callouts.is_empty() {
+        stroke_color(&mut s, &rd.text_color);
+        let _ = writeln!(s, "1 w");
+    }
+
+    for (index, callout) in callouts {
+        let label = rd.wedges[index].label.as_deref().unwrap_or_default();
+        let (x, y) = callout.end;
+
+        let _ = writeln!(
+            s,
+            "{:.2} {:.2} m {:.2} {:.2} l {:.2} {:.2} l S",
+            callout.anchor.0, callout.anchor.1, callout.elbow.0, callout.elbow.1, x, y
+        );
+        fill_color(&mut s, &rd.text_color);
+        text(
+            &mut s,
+            label,
+            if callout.left {
+                x - callout::TEXT_GAP - text::width(label, LABEL_FONT_SIZE)
+            } else {
+                x + callout::TEXT_GAP
+            },
+            y + LABEL_FONT_SIZE * 0.35,
+            LABEL_FONT_SIZE,
+            false,
+        );
+    }
+
+    let bold = rd.title_font_weight.as_deref().is_some_and(|weight| {
+        matches!(weight, "bold" | "bolder") || weight.parse::<u32>().is_ok_and(|w| w >= 600)
+    });
+
+    for (line, y, is_subtitle) in rd.title_lines() {
+        let size = if is_subtitle {
+            rd.subtitle_font_size
+        } else {
+            rd.title_font_size
+        };
+
+        fill_color(
+            &mut s,
+            if is_subtitle {
+                &rd.text_color
+            } else {
+                &rd.title_color
+            },
+        );
+        text(
+            &mut s,
+            line,
+            (width - text::width(line, size)) / 2.0,
+            y,
+            size,
+            bold && !is_subtitle,
+        );
+    }
+
+    let entries = rd.legend_entries();
+
+    for (wedge, &(x, y)) in rd.legend_wedges().into_iter().zip(&entries) {
+        fill_color(&mut s, &wedge.color);
+        rounded_rect(&mut s, x, y, rd.legend_height, rd.legend_rect_corner_radius);
+        let _ = writeln!(s, "{}", paint);
+        fill_color(&mut s, &rd.text_color);
+
+        for (line_index, line) in wedge.legend_lines.iter().enumerate() {
+            // Right to left lines end at the right edge of the block
+            let x = if rd.rtl {
+                x + rd.legend_height - text::width(line, LEGEND_FONT_SIZE)
+            } else {
+                x
+            };
+
+            text(
+                &mut s,
+                line,
+                x,
+                y + rd.legend_height * 2.0 + line_index as f64 * LEGEND_LINE_HEIGHT,
+                LEGEND_FONT_SIZE,
+                false,
+            );
+        }
+    }
+
+    fill_color(&mut s, &rd.text_color);
+
+    for (line, y) in rd.caption_lines() {
+        let x = if rd.rtl {
+            width - rd.legend_gutter.right - text::width(line, CAPTION_FONT_SIZE)
+        } else {
+            rd.legend_gutter.left
+        };
+
+        text(&mut s, line, x, y, CAPTION_FONT_SIZE, false);
+    }
+
+    s
+}
+
+/// Draw the wedges of a sunburst ring, each followed by the wedges outside it. The wedges of the
+/// pie itself are the ring at level 0.
+fn ring(
+    s: &mut String,
+    rd: &RenderData,
+    wedges: &[WedgeData],
+    start: f64,
+    level: usize,
+    paint: &str,
+) {
+    let (x_center, y_center) = rd.center();
+    let mut a = start;
+
+    for wedge in wedges.iter() {
+        let b = a + rd.span(wedge);
+        let (inner_radius, radius) = rd.wedge_radii(wedge, level);
+
+        // Empty wedges are only shown in the legend
+        if wedge.percentage == 0.0 {
+            a = b;
+            continue;
+        }
+
+        fill_color(s, &wedge.color);
+        sector(s, (x_center, y_center), inner_radius, radius, a, b);
+        let _ = writeln!(s, "{}", paint);
+        ring(s, rd, &wedge.children, a, level + 1, paint);
+        a = b;
+    }
+}
+
+/// The outline of a wedge from angle `a` to `b`, leaving out the hole if it has one
+fn sector(s: &mut String, center: (f64, f64), inner_radius: f64, radius: f64, a: f64, b: f64) {
+    let (x_center, y_center) = center;
+
+    let _ = writeln!(
+        s,
+        "{:.2} {:.2} m",
+        x_center + radius * a.cos(),
+        y_center + radius * a.sin()
+    );
+    arc(s, x_center, y_center, radius, a, b);
+
+    if inner_radius > 0.0 {
+        let _ = writeln!(
+            s,
+            "{:.2} {:.2} l",
+            x_center + inner_radius * b.cos(),
+            y_center + inner_radius * b.sin()
+        );
+        arc(s, x_center, y_center, inner_radius, b, a);
+    } else {
+        let _ = writeln!(s, "{:.2} {:.2} l", x_center, y_center);
+    }
+}
+
+/// The breakout pie with its labels, and the lines joining it to the broken out wedge
+fn breakout(s: &mut String, rd: &RenderData, paint: &str) {
+    let ((x_center, y_center), inner_radius, radius) = rd.breakout_circle();
+
+    for (wedge, a, b) in rd.breakout_wedges() {
+        if wedge.percentage > 0.0 {
+            fill_color(s, &wedge.color);
+            sector(s, (x_center, y_center), inner_radius, radius, a, b);
+            let _ = writeln!(s, "{}", paint);
+        }
+    }
+
+    for (wedge, a, b) in rd.breakout_wedges() {
+        if let Some(label) = wedge.label.as_ref().filter(|_| wedge.percentage > 0.0) {
+            let mid = (a + b) / 2.0;
+            let distance = if wedge.percentage >= FULL_PERCENTAGE && inner_radius == 0.0 {
+                0.0
+            } else {
+                (radius + inner_radius) / 2.0
+            };
+
+            fill_color(
+                s,
+                if wedge.label_inverse {
+                    "#ffffff"
+                } else {
+                    "#000000"
+                },
+            );
+            text(
+                s,
+                label,
+                x_center + distance * mid.cos() - text::width(label, LABEL_FONT_SIZE) / 2.0,
+                y_center + distance * mid.sin() + LABEL_FONT_SIZE * 0.35,
+                LABEL_FONT_SIZE,
+                false,
+            );
+        }
+    }
+
+    stroke_color(s, &rd.text_color);
+    let _ = writeln!(s, "1 w");
+
+    for (from, to) in rd.connectors() {
+        let _ = writeln!(
+            s,
+            "{:.2} {:.2} m {:.2} {:.2} l S",
+            from.0, from.1, to.0, to.1
+        );
+    }
+}
+
+/// Curves along a circle from angle `a` to `b`, split into segments of at most a quarter turn
+fn arc(s: &mut String, x_center: f64, y_center: f64, radius: f64, a: f64, b: f64) {
+    let segments = ((b - a).abs() / FRAC_PI_2).ceil().max(1.0);
+    let step = (b - a) / segments;
+    let k = 4.0 / 3.0 * (step / 4.0).tan() * radius;
+
+    for i in 0..segments as usize {
+        let t0 = a + step * i as f64;
+        let t1 = t0 + step;
+        let (x0, y0) = (x_center + radius * t0.cos(), y_center + radius * t0.sin());
+        let (x1, y1) = (x_center + radius * t1.cos(), y_center + radius * t1.sin());
+
+        let _ = writeln!(
+            s,
+            "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c",
+            x0 - k * t0.sin(),
+            y0 + k * t0.cos(),
+            x1 + k * t1.sin(),
+            y1 - k * t1.cos(),
+            x1,
+            y1
+        );
+    }
+}
+
+fn rounded_rect(s: &mut String, x: f64, y: f64, size: f64, corner: f64) {
+    let r = corner.min(size / 2.0);
+    let k = r * (1.0 - KAPPA);
+    let (right, bottom) = (x + size, y + size);
+
+    let _ = writeln!(s, "{:.2} {:.2} m", x + r, y);
+    let _ = writeln!(s, "{:.2} {:.2} l", right - r, y);
+    let _ = writeln!(
+        s,
+        "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c",
+        right - k,
+        y,
+        right,
+        y + k,
+        right,
+        y + r
+    );
+    let _ = writeln!(s, "{:.2} {:.2} l", right, bottom - r);
+    let _ = writeln!(
+        s,
+        "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c",
+        right,
+        bottom - k,
+        right - k,
+        bottom,
+        right - r,
+        bottom
+    );
+    let _ = writeln!(s, "{:.2} {:.2} l", x + r, bottom);
+    let _ = writeln!(
+        s,
+        "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c",
+        x + k,
+        bottom,
+        x,
+        bottom - k,
+        x,
+        bottom - r
+    );
+    let _ = writeln!(s, "{:.2} {:.2} l", x, y + r);
+    let _ = writeln!(
+        s,
+        "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c h",
+        x,
+        y + k,
+        x + k,
+        y,
+        x + r,
+        y
+    );
+}
+
+fn text(s: &mut String, text: &str, x: f64, y: f64, size: f64, bold: bool) {
+    let mut escaped = String::new();
+
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            // The ellipsis of truncated legend labels
+            '…' => escaped.push_str("\\205"),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(escaped, "\\{:03o}", c as u32);
+            }
+            _ => escaped.push('?'),
+        }
+    }
+
+    // The text matrix flips the glyphs back upright
+    let _ = writeln!(
+        s,
+        "BT /{} {} Tf 1 0 0 -1 {:.2} {:.2} Tm ({}) Tj ET",
+        if bold { "F2" } else { "F1" },
+        size,
+        x,
+        y,
+        escaped
+    );
+}
+
+/// The color as fractions of red, green and blue
+fn rgb(color: &str) -> (f64, f64, f64) {
+    let rgb = super::rgb(color);
+    let component = |shift: u32| ((rgb >> shift) & 0xff) as f64 / 255.0;
+
+    (component(16), component(8), component(0))
+}
+
+fn fill_color(s: &mut String, color: &str) {
+    let (r, g, b) = rgb(color);
+
+    let _ = writeln!(s, "{:.3} {:.3} {:.3} rg", r, g, b);
+}
+
+fn stroke_color(s: &mut String, color: &str) {
+    let (r, g, b) = rgb(color);
+
+    let _ = writeln!(s, "{:.3} {:.3} {:.3} RG", r, g, b);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChartData, ChartOptions, PieChartTool, SilentLog};
+
+    #[test]
+    fn writes_a_page_per_chart() {
+        let chart_data: ChartData =
+            json5::from_str(r#"{ title: "Test (1)", items: [{ key: "a", value: 1 }] }"#).unwrap();
+        let tool = PieChartTool::new(&SilentLog);
+        let rd = || {
+            tool.process_chart_data(&chart_data, &ChartOptions::default())
+                .unwrap()
+        };
+        let pdf = String::from_utf8_lossy(&render(&[rd(), rd()])).to_string();
+
+        assert!(pdf.starts_with("%PDF-1.4"));
+        assert!(pdf.contains("/Count 2"));
+        assert!(pdf.contains("/MediaBox [0 0 360.00 390.00]"));
+        assert!(pdf.contains("(Test \\(1\\)) Tj"));
+        assert!(pdf.ends_with("%%EOF\n"));
+    }
+}
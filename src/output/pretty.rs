@@ -0,0 +1,50 @@
+//! Indents serialized SVG, which already has each element on its own line, by how deeply the
+//! elements are nested.
+
+const INDENT: &str = "  ";
+
+/// Indent each line by the number of elements it is inside
+pub(crate) fn indent(svg: &str) -> String {
+    let mut result = String::with_capacity(svg.len() * 2);
+    let mut depth = 0usize;
+
+    for line in svg.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let closes = line.starts_with("</");
+        let opens = line.starts_with('<')
+            && !closes
+            && !line.ends_with("/>")
+            && !line.starts_with("<?")
+            && !line.starts_with("<!")
+            && !line.contains("</");
+
+        if closes {
+            depth = depth.saturating_sub(1);
+        }
+
+        for _ in 0..depth {
+            result.push_str(INDENT);
+        }
+
+        result.push_str(line);
+        result.push('\n');
+
+        if opens {
+            depth += 1;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indents_nested_elements() {
+        assert_eq!(
+            indent("<svg>\n<g>\n<path d=\"M0,0\"/>\n<text>\nHi\n</text>\n</g>\n</svg>"),
+            "<svg>\n  <g>\n    <path d=\"M0,0\"/>\n    <text>\n      Hi\n    </text>\n  </g>\n</svg>\n"
+        );
+    }
+}
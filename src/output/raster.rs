@@ -0,0 +1,139 @@
+//! The pie as a grid of pixels, each of which is the wedge it falls in, for formats that draw
+//! the pie as characters or as an image. Wedges are numbered in the order they are drawn, with
+//! the wedges of each sunburst ring straight after the wedge they are outside of, and the rings
+//! of any further series after all of those.
+
+use super::rgb;
+use crate::{RenderData, WedgeData, WAFFLE_SIZE};
+use std::f64::consts::PI;
+
+pub(crate) struct Raster {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Option<usize>>,
+}
+
+impl Raster {
+    /// Sample the pie at the given diameter in pixels, or the squares of a waffle chart or the
+    /// rectangles of a bar chart or treemap across that width. Half charts are only as high as the top half of the pie.
+    pub(crate) fn new(rd: &RenderData, diameter: usize) -> Raster {
+        let height = if rd.half { diameter / 2 } else { diameter };
+        let radius = diameter as f64 / 2.0;
+        let scale = rd.pie_diameter / diameter as f64;
+        let (start, _) = rd.angles();
+        let cells = if rd.waffle { rd.waffle_cells() } else { vec![] };
+        let rects = rd.rects();
+        let pixels = (0..height)
+            .flat_map(|y| (0..diameter).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                // The gaps between the squares are too thin to draw at these sizes
+                if rd.waffle {
+                    let cell = |i: usize| i * WAFFLE_SIZE / diameter;
+
+                    return cells
+                        .get(cell(y) * WAFFLE_SIZE + cell(x))
+                        .map(|&(index, _, _)| index);
+                }
+
+                if rd.bars || rd.treemap {
+                    let (x, y) = (
+                        rd.gutter.left + (x as f64 + 0.5) * scale,
+                        rd.gutter.top + (y as f64 + 0.5) * scale,
+                    );
+
+                    return rects
+                        .iter()
+                        .find(|&&(_, left, top, width, height)| {
+                            (left..left + width).contains(&x) && (top..top + height).contains(&y)
+                        })
+                        .map(|&(index, ..)| index);
+                }
+
+                let (dx, dy) = (x as f64 + 0.5 - radius, y as f64 + 0.5 - radius);
+                let mut offset = 0;
+
+                rd.rings().find_map(|(level, wedges)| {
+                    let index = wedge_at(
+                        rd,
+                        wedges,
+                        start,
+                        level,
+                        dy.atan2(dx),
+                        (dx * dx + dy * dy).sqrt() * scale,
+                    );
+
+                    let found = index.map(|index| offset + index);
+                    offset += count(wedges);
+                    found
+                })
+            })
+            .collect();
+
+        Raster {
+            width: diameter,
+            height,
+            pixels,
+        }
+    }
+
+    /// The wedge at a pixel, or `None` outside the pie or the raster
+    pub(crate) fn get(self: &Self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x]
+        } else {
+            None
+        }
+    }
+}
+
+/// The color of each wedge as a 24 bit RGB value, numbered as in the raster
+pub(crate) fn colors(rd: &RenderData) -> Vec<u32> {
+    rd.rings()
+        .flat_map(|(_, wedges)| wedge_colors(wedges))
+        .collect()
+}
+
+fn wedge_colors(wedges: &[WedgeData]) -> Vec<u32> {
+    wedges
+        .iter()
+        .flat_map(|wedge| [vec![rgb(&wedge.color)], wedge_colors(&wedge.children)].concat())
+        .collect()
+}
+
+fn wedge_at(
+    rd: &RenderData,
+    wedges: &[WedgeData],
+    start: f64,
+    level: usize,
+    angle: f64,
+    distance: f64,
+) -> Option<usize> {
+    let mut a = start;
+    let mut index = 0;
+
+    for wedge in wedges {
+        let span = rd.span(wedge);
+        let (inner_radius, radius) = rd.wedge_radii(wedge, level);
+        // Wedges may run either way around, and whole wedges all the way
+        let from = a.min(a + span);
+
+        if wedge.percentage > 0.0 && (angle - from).rem_euclid(2.0 * PI) < span.abs() {
+            if (inner_radius..radius).contains(&distance) {
+                return Some(index);
+            }
+
+            if let Some(child) = wedge_at(rd, &wedge.children, a, level + 1, angle, distance) {
+                return Some(index + 1 + child);
+            }
+        }
+
+        index += 1 + count(&wedge.children);
+        a += span;
+    }
+
+    None
+}
+
+fn count(wedges: &[WedgeData]) -> usize {
+    wedges.iter().map(|wedge| 1 + count(&wedge.children)).sum()
+}
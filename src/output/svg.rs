@@ -0,0 +1,23 @@
+//! The SVG renderer, which draws one chart as it is and several in a grid of nested SVG elements
+
+use super::Renderer;
+use crate::{PieChartError, PieChartTool, RenderData};
+use svg::Document;
+
+pub(crate) struct SvgRenderer<'a> {
+    /// The tool to log to while drawing
+    pub tool: &'a PieChartTool<'a>,
+    /// The number of grid columns, instead of one more than fits in a square
+    pub columns: Option<usize>,
+}
+
+impl Renderer for SvgRenderer<'_> {
+    type Output = Document;
+
+    fn render(self: &Self, charts: &mut [RenderData]) -> Result<Document, PieChartError> {
+        match charts {
+            [rd] => self.tool.render_chart(rd),
+            charts => self.tool.render_grid_chart(charts, self.columns),
+        }
+    }
+}
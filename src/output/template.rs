@@ -0,0 +1,76 @@
+//! Renders a user's own template with the laid out chart, for layouts the renderer cannot make.
+//! Templates use the [TinyTemplate](https://docs.rs/tinytemplate) syntax, with the fields of
+//! the first chart at the top level and every chart in `charts`. Values are HTML escaped unless
+//! given the `unescaped` formatter, as in `{svg | unescaped}`.
+
+use crate::PieChartError;
+use serde::Serialize;
+use tinytemplate::TinyTemplate;
+
+/// A chart as it is laid out, in the same units as the SVG
+#[derive(Serialize, Debug)]
+pub(crate) struct Chart {
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub caption: Option<String>,
+    pub width: f64,
+    pub height: f64,
+    pub center_x: f64,
+    pub center_y: f64,
+    pub background: String,
+    pub text_color: String,
+    /// The styles of the rendered chart, as CSS
+    pub styles: String,
+    /// The chart rendered as usual, to use alongside or instead of the wedges
+    pub svg: String,
+    pub wedges: Vec<Wedge>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct Wedge {
+    pub key: String,
+    pub value: f64,
+    /// The share of the total, from 0 to 100
+    pub percent: f64,
+    pub legend: String,
+    pub color: String,
+    /// The class of the wedge in the rendered chart, such as `wedge-0` or the item's own class
+    pub class: String,
+    pub href: Option<String>,
+    /// The angles the wedge starts and ends at, in degrees clockwise from 12 o'clock
+    pub start_angle: f64,
+    pub end_angle: f64,
+    pub inner_radius: f64,
+    pub outer_radius: f64,
+    /// The SVG path data drawing the wedge
+    pub path: String,
+    /// The middle of the wedge, where its label would be drawn
+    pub label_x: f64,
+    pub label_y: f64,
+    /// The wedges of the next sunburst ring out
+    pub children: Vec<Wedge>,
+}
+
+#[derive(Serialize)]
+struct Context<'a> {
+    #[serde(flatten)]
+    chart: &'a Chart,
+    charts: &'a [Chart],
+}
+
+pub(crate) fn render(template: &str, charts: &[Chart]) -> Result<String, PieChartError> {
+    let mut tt = TinyTemplate::new();
+    let error = |e: tinytemplate::error::Error| {
+        PieChartError::Render(format!("Unable to render template: {}", e))
+    };
+
+    tt.add_template("chart", template).map_err(error)?;
+    tt.render(
+        "chart",
+        &Context {
+            chart: &charts[0],
+            charts,
+        },
+    )
+    .map_err(error)
+}
@@ -0,0 +1,167 @@
+//! Text for a terminal that draws the pie with half block characters, so that each character
+//! cell holds two roughly square pixels, in 24 bit ANSI colors. Without colors each wedge is
+//! drawn with its own shade of block instead. Terminals that can show images are sent the pie
+//! as an image instead of the blocks.
+
+use super::{
+    graphics::{self, Graphics},
+    raster::{self, Raster},
+    rgb, Renderer,
+};
+use crate::{PieChartError, RenderData, WedgeData};
+use std::fmt::Write;
+
+/// The diameter of the pie in pixels, which is as many characters across as it is
+const DIAMETER: usize = 32;
+/// The characters that tell wedges apart without colors, repeating after the last
+const SHADES: [char; 8] = ['█', '▓', '▒', '░', '#', '+', '*', '='];
+
+pub(crate) struct TermRenderer {
+    pub color: bool,
+    /// The image protocol to draw the pie with, instead of block characters
+    pub graphics: Option<Graphics>,
+}
+
+impl Renderer for TermRenderer {
+    type Output = String;
+
+    fn render(self: &Self, charts: &mut [RenderData]) -> Result<String, PieChartError> {
+        Ok(charts
+            .iter()
+            .map(|rd| self.chart(rd))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+impl TermRenderer {
+    fn chart(self: &Self, rd: &RenderData) -> String {
+        let mut s = String::new();
+
+        for (line, _, is_subtitle) in rd.title_lines() {
+            let _ = if is_subtitle || !self.color {
+                writeln!(s, "{}", line)
+            } else {
+                writeln!(s, "\x1b[1m{}\x1b[0m", line)
+            };
+        }
+
+        let colors = raster::colors(rd);
+
+        match self.graphics {
+            Some(protocol) => {
+                let raster = Raster::new(rd, rd.pie_diameter.round() as usize);
+
+                s.push_str(&graphics::encode(protocol, &raster, &colors));
+                s.push('\n');
+            }
+            None => {
+                let raster = Raster::new(rd, DIAMETER);
+
+                for y in (0..raster.height).step_by(2) {
+                    for x in 0..raster.width {
+                        s.push_str(&self.cell(&colors, raster.get(x, y), raster.get(x, y + 1)));
+                    }
+
+                    s.truncate(s.trim_end().len());
+                    s.push('\n');
+                }
+            }
+        }
+
+        for &index in &rd.legend_order {
+            let wedge = &rd.wedges[index];
+
+            let _ = writeln!(s, "{} {}", self.swatch(wedge, index), wedge.legend);
+        }
+
+        if let Some(ref caption) = rd.caption {
+            let _ = writeln!(s, "{}", caption);
+        }
+
+        s
+    }
+
+    /// A character cell holding a pixel at the top and one at the bottom, each of which is the
+    /// wedge there if there is one
+    fn cell(self: &Self, colors: &[u32], top: Option<usize>, bottom: Option<usize>) -> String {
+        let color = |index: usize| {
+            let rgb = colors[index];
+
+            format!("{};{};{}", rgb >> 16, (rgb >> 8) & 0xff, rgb & 0xff)
+        };
+
+        if !self.color {
+            return top
+                .or(bottom)
+                .map_or(' ', |index| SHADES[index % SHADES.len()])
+                .to_string();
+        }
+
+        match (top, bottom) {
+            (None, None) => " ".to_string(),
+            (Some(top), None) => format!("\x1b[38;2;{}m▀\x1b[0m", color(top)),
+            (None, Some(bottom)) => format!("\x1b[38;2;{}m▄\x1b[0m", color(bottom)),
+            (Some(top), Some(bottom)) => format!(
+                "\x1b[38;2;{}m\x1b[48;2;{}m▀\x1b[0m",
+                color(top),
+                color(bottom)
+            ),
+        }
+    }
+
+    fn swatch(self: &Self, wedge: &WedgeData, index: usize) -> String {
+        if self.color {
+            let rgb = rgb(&wedge.color);
+
+            format!(
+                "\x1b[38;2;{};{};{}m██\x1b[0m",
+                rgb >> 16,
+                (rgb >> 8) & 0xff,
+                rgb & 0xff
+            )
+        } else {
+            SHADES[index % SHADES.len()].to_string().repeat(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChartData, ChartOptions, PieChartTool, SilentLog};
+
+    #[test]
+    fn draws_blocks_and_legend() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: 'Costs', items: [{ key: 'a', value: 1, color: '#ff0000' }, { key: 'b', value: 3, color: '#0000ff' }] }",
+        )
+        .unwrap();
+        let mut rd = PieChartTool::new(&SilentLog)
+            .process_chart_data(&chart_data, &ChartOptions::default())
+            .unwrap();
+        let plain = TermRenderer {
+            color: false,
+            graphics: None,
+        }
+        .render(std::slice::from_mut(&mut rd))
+        .unwrap();
+        let lines: Vec<&str> = plain.lines().collect();
+
+        assert_eq!(lines[0], "Costs");
+        assert_eq!(lines.len(), 1 + DIAMETER / 2 + 2);
+        assert!(lines[1..=DIAMETER / 2].concat().contains('█'));
+        assert!(lines[1..=DIAMETER / 2].concat().contains('▓'));
+        assert_eq!(lines[DIAMETER / 2 + 1], "██ a (25%)");
+
+        let colored = TermRenderer {
+            color: true,
+            graphics: None,
+        }
+        .render(std::slice::from_mut(&mut rd))
+        .unwrap();
+
+        assert!(colored.contains("\x1b[38;2;255;0;0m"));
+        assert!(colored.contains("\x1b[48;2;0;0;255m▀"));
+    }
+}
@@ -0,0 +1,168 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+const TABLEAU10: [u32; 10] = [
+    0x4e79a7, 0xf28e2b, 0xe15759, 0x76b7b2, 0x59a14f, 0xedc948, 0xb07aa1, 0xff9da7, 0x9c755f,
+    0xbab0ac,
+];
+const SET1: [u32; 9] = [
+    0xe41a1c, 0x377eb8, 0x4daf4a, 0x984ea3, 0xff7f00, 0xffff33, 0xa65628, 0xf781bf, 0x999999,
+];
+const SET2: [u32; 8] = [
+    0x66c2a5, 0xfc8d62, 0x8da0cb, 0xe78ac3, 0xa6d854, 0xffd92f, 0xe5c494, 0xb3b3b3,
+];
+const SET3: [u32; 12] = [
+    0x8dd3c7, 0xffffb3, 0xbebada, 0xfb8072, 0x80b1d3, 0xfdb462, 0xb3de69, 0xfccde5, 0xd9d9d9,
+    0xbc80bd, 0xccebc5, 0xffed6f,
+];
+const DARK2: [u32; 8] = [
+    0x1b9e77, 0xd95f02, 0x7570b3, 0xe7298a, 0x66a61e, 0xe6ab02, 0xa6761d, 0x666666,
+];
+const PAIRED: [u32; 12] = [
+    0xa6cee3, 0x1f78b4, 0xb2df8a, 0x33a02c, 0xfb9a99, 0xe31a1c, 0xfdbf6f, 0xff7f00, 0xcab2d6,
+    0x6a3d9a, 0xffff99, 0xb15928,
+];
+const PASTEL1: [u32; 9] = [
+    0xfbb4ae, 0xb3cde3, 0xccebc5, 0xdecbe4, 0xfed9a6, 0xffffcc, 0xe5d8bd, 0xfddaec, 0xf2f2f2,
+];
+const PASTEL2: [u32; 8] = [
+    0xb3e2cd, 0xfdcdac, 0xcbd5e8, 0xf4cae4, 0xe6f5c9, 0xfff2ae, 0xf1e2cc, 0xcccccc,
+];
+const ACCENT: [u32; 8] = [
+    0x7fc97f, 0xbeaed4, 0xfdc086, 0xffff99, 0x386cb0, 0xf0027f, 0xbf5b17, 0x666666,
+];
+// Okabe and Ito, distinguishable with the common color vision deficiencies
+const COLORBLIND: [u32; 8] = [
+    0xe69f00, 0x56b4e9, 0x009e73, 0xf0e442, 0x0072b2, 0xd55e00, 0xcc79a7, 0x000000,
+];
+// Viridis sampled at eleven evenly spaced points
+const VIRIDIS: [u32; 11] = [
+    0x440154, 0x482475, 0x414487, 0x355f8d, 0x2a788e, 0x21918c, 0x22a884, 0x44bf70, 0x7ad151,
+    0xbddf26, 0xfde725,
+];
+
+/// The set of colors used for the chart wedges
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// Random hues spaced by the golden ratio
+    #[default]
+    Random,
+    /// Tableau 10
+    Tableau10,
+    /// ColorBrewer Set1
+    Set1,
+    /// ColorBrewer Set2
+    Set2,
+    /// ColorBrewer Set3
+    Set3,
+    /// ColorBrewer Dark2
+    Dark2,
+    /// ColorBrewer Paired
+    Paired,
+    /// ColorBrewer Pastel1
+    Pastel1,
+    /// ColorBrewer Pastel2
+    Pastel2,
+    /// ColorBrewer Accent
+    Accent,
+    /// Viridis, sampled evenly across the wedges
+    Viridis,
+    /// Okabe-Ito, safe for the common color vision deficiencies
+    Colorblind,
+}
+
+/// The color space that the random palette spaces its hues out in
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorSpace {
+    /// HSV, whose colors vary in how light they look from one hue to the next
+    #[default]
+    Hsv,
+    /// OKLCH, the polar form of Oklab, whose colors all look equally light
+    Oklch,
+}
+
+impl Palette {
+    fn qualitative_colors(self: &Self) -> Option<&'static [u32]> {
+        match self {
+            Palette::Random | Palette::Viridis => None,
+            Palette::Tableau10 => Some(&TABLEAU10),
+            Palette::Set1 => Some(&SET1),
+            Palette::Set2 => Some(&SET2),
+            Palette::Set3 => Some(&SET3),
+            Palette::Dark2 => Some(&DARK2),
+            Palette::Paired => Some(&PAIRED),
+            Palette::Pastel1 => Some(&PASTEL1),
+            Palette::Pastel2 => Some(&PASTEL2),
+            Palette::Accent => Some(&ACCENT),
+            Palette::Colorblind => Some(&COLORBLIND),
+        }
+    }
+
+    /// The number of distinct colors in the palette, or `None` if it has no fixed size
+    pub fn len(self: &Self) -> Option<usize> {
+        self.qualitative_colors().map(|colors| colors.len())
+    }
+
+    /// Returns `count` RGB colors from the palette, or `None` for the random palette.
+    /// Fixed size palettes repeat once their colors run out.
+    pub fn colors(self: &Self, count: usize) -> Option<Vec<u32>> {
+        if let Some(colors) = self.qualitative_colors() {
+            return Some(colors.iter().cycle().take(count).copied().collect());
+        }
+
+        match self {
+            Palette::Viridis => Some(
+                (0..count)
+                    .map(|i| {
+                        let t = if count > 1 {
+                            i as f64 / (count - 1) as f64
+                        } else {
+                            0.0
+                        };
+
+                        Self::sample(&VIRIDIS, t)
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    fn sample(stops: &[u32], t: f64) -> u32 {
+        let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let index = (position.floor() as usize).min(stops.len() - 2);
+        let fraction = position - index as f64;
+        let (from, to) = (stops[index], stops[index + 1]);
+
+        [16, 8, 0].iter().fold(0, |rgb, shift| {
+            let a = ((from >> shift) & 0xff) as f64;
+            let b = ((to >> shift) & 0xff) as f64;
+
+            rgb | (((a + (b - a) * fraction).round() as u32) << shift)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qualitative_palette_repeats() {
+        let colors = Palette::Set2.colors(10).unwrap();
+
+        assert_eq!(colors.len(), 10);
+        assert_eq!(colors[8], colors[0]);
+        assert_eq!(Palette::Random.colors(3), None);
+    }
+
+    #[test]
+    fn viridis_spans_the_whole_map() {
+        let colors = Palette::Viridis.colors(3).unwrap();
+
+        assert_eq!(colors, vec![0x440154, 0x21918c, 0xfde725]);
+        assert_eq!(Palette::Viridis.colors(1).unwrap(), vec![0x440154]);
+    }
+}
@@ -0,0 +1,318 @@
+//! A description of the charts as they are drawn, for tools that work with the rendered output,
+//! such as to find the wedge under the pointer or to write captions. Positions are in the units of
+//! the SVG, with the origin at the top left of the document, and angles are in degrees clockwise
+//! from 12 o'clock.
+
+use crate::{RenderData, WedgeData};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::FRAC_PI_2;
+
+/// The size of the document and each chart in it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Report {
+    pub width: f64,
+    pub height: f64,
+    pub charts: Vec<ChartReport>,
+}
+
+impl Report {
+    /// The wedge drawn at a point of the document, if there is one. Wedges of a sunburst are
+    /// found in whichever ring the point is in.
+    pub fn wedge_at(self: &Self, x: f64, y: f64) -> Option<&WedgeReport> {
+        self.charts.iter().find_map(|chart| chart.wedge_at(x, y))
+    }
+}
+
+/// A chart of the document as it is laid out
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChartReport {
+    pub title: String,
+    /// The top left corner of the chart in the document, which is not at the origin in a grid
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub center_x: f64,
+    pub center_y: f64,
+    pub wedges: Vec<WedgeReport>,
+    pub legend: Vec<LegendEntry>,
+}
+
+impl ChartReport {
+    /// The wedge drawn at a point of the document, if there is one in this chart
+    pub fn wedge_at(self: &Self, x: f64, y: f64) -> Option<&WedgeReport> {
+        let (dx, dy) = (x - self.center_x, y - self.center_y);
+        let radius = dx.hypot(dy);
+        let angle = dx.atan2(-dy).to_degrees();
+
+        fn find(
+            wedges: &[WedgeReport],
+            x: f64,
+            y: f64,
+            radius: f64,
+            angle: f64,
+        ) -> Option<&WedgeReport> {
+            wedges.iter().find_map(|wedge| {
+                let is_in = if wedge.rects.is_empty() {
+                    let sweep = wedge.end_angle - wedge.start_angle;
+
+                    (wedge.inner_radius..=wedge.outer_radius).contains(&radius)
+                        && (sweep >= 360.0 || (angle - wedge.start_angle).rem_euclid(360.0) < sweep)
+                } else {
+                    wedge.rects.iter().any(|&[left, top, width, height]| {
+                        (left..left + width).contains(&x) && (top..top + height).contains(&y)
+                    })
+                };
+
+                if is_in {
+                    Some(wedge)
+                } else {
+                    find(&wedge.children, x, y, radius, angle)
+                }
+            })
+        }
+
+        find(&self.wedges, x, y, radius, angle)
+    }
+}
+
+/// A wedge as it is drawn, or the shapes drawn for it in charts without a pie
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WedgeReport {
+    pub key: String,
+    pub value: f64,
+    /// The share of the total, from 0 to 100
+    pub percent: f64,
+    pub color: String,
+    pub class: String,
+    pub start_angle: f64,
+    pub end_angle: f64,
+    pub inner_radius: f64,
+    pub outer_radius: f64,
+    /// The middle of the wedge, halfway between its radii at the middle of its angles, or the
+    /// center of its shapes in charts without a pie
+    pub centroid_x: f64,
+    pub centroid_y: f64,
+    /// The squares, bars or rectangles the wedge is drawn as in place of the pie, as the left,
+    /// top, width and height of each
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rects: Vec<[f64; 4]>,
+    /// The wedges of the next sunburst ring out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<WedgeReport>,
+}
+
+/// The color block of a legend entry, with the start of its text
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LegendEntry {
+    pub key: String,
+    pub x: f64,
+    pub y: f64,
+    pub size: f64,
+    pub text_x: f64,
+    pub text_y: f64,
+}
+
+/// The report of charts laid out at the given positions in a document of the given size
+pub(crate) fn report(charts: &[(&RenderData, (f64, f64))], (width, height): (f64, f64)) -> Report {
+    Report {
+        width,
+        height,
+        charts: charts
+            .iter()
+            .map(|&(rd, offset)| chart(rd, offset))
+            .collect(),
+    }
+}
+
+fn chart(rd: &RenderData, (x, y): (f64, f64)) -> ChartReport {
+    let (width, height) = rd.size();
+    let (center_x, center_y) = rd.center();
+    let (start, _) = rd.angles();
+    let mut wedges = wedges(rd, &rd.wedges, start, 0, (x + center_x, y + center_y));
+    let mut rects: Vec<(usize, [f64; 4])> = rd
+        .rects()
+        .into_iter()
+        .map(|(index, left, top, w, h)| (index, [x + left, y + top, w, h]))
+        .collect();
+
+    if rd.waffle {
+        let size = rd.waffle_cell_size();
+
+        rects.extend(
+            rd.waffle_cells()
+                .into_iter()
+                .map(|(index, left, top)| (index, [x + left, y + top, size, size])),
+        );
+    }
+
+    for (index, rect) in rects {
+        wedges[index].rects.push(rect);
+    }
+
+    // The shapes of a wedge all have the same area, so their centroid is the mean of their centers
+    for wedge in wedges.iter_mut().filter(|wedge| !wedge.rects.is_empty()) {
+        let count = wedge.rects.len() as f64;
+
+        wedge.centroid_x = wedge.rects.iter().map(|r| r[0] + r[2] / 2.0).sum::<f64>() / count;
+        wedge.centroid_y = wedge.rects.iter().map(|r| r[1] + r[3] / 2.0).sum::<f64>() / count;
+    }
+
+    ChartReport {
+        title: rd.title.clone(),
+        x,
+        y,
+        width,
+        height,
+        center_x: x + center_x,
+        center_y: y + center_y,
+        wedges,
+        legend: rd
+            .legend_wedges()
+            .into_iter()
+            .zip(rd.legend_entries())
+            .map(|(wedge, (left, top))| LegendEntry {
+                key: wedge.key.clone(),
+                x: x + left,
+                y: y + top,
+                size: rd.legend_height,
+                text_x: x + if rd.rtl {
+                    left + rd.legend_height
+                } else {
+                    left
+                },
+                text_y: y + top + rd.legend_height * 2.0,
+            })
+            .collect(),
+    }
+}
+
+fn wedges(
+    rd: &RenderData,
+    wedges: &[WedgeData],
+    start: f64,
+    level: usize,
+    center: (f64, f64),
+) -> Vec<WedgeReport> {
+    let mut a = start;
+
+    wedges
+        .iter()
+        .map(|wedge| {
+            let b = a + rd.span(wedge);
+            let (inner_radius, outer_radius) = rd.wedge_radii(wedge, level);
+            let (centroid_x, centroid_y) = middle(center, inner_radius, outer_radius, a, b);
+            let report = WedgeReport {
+                key: wedge.key.clone(),
+                value: wedge.value,
+                percent: wedge.percentage * 100.0,
+                color: wedge.color.clone(),
+                class: wedge.class.clone(),
+                start_angle: (a + FRAC_PI_2).to_degrees(),
+                end_angle: (b + FRAC_PI_2).to_degrees(),
+                inner_radius,
+                outer_radius,
+                centroid_x,
+                centroid_y,
+                rects: vec![],
+                children: self::wedges(rd, &wedge.children, a, level + 1, center),
+            };
+
+            a = b;
+            report
+        })
+        .collect()
+}
+
+/// The middle of a ring sector between two angles in radians, which unlike the center of its area
+/// is always inside it, even for sectors wider than a half
+fn middle(
+    (x_center, y_center): (f64, f64),
+    inner_radius: f64,
+    radius: f64,
+    a: f64,
+    b: f64,
+) -> (f64, f64) {
+    let distance = (inner_radius + radius) / 2.0;
+    let mid = (a + b) / 2.0;
+
+    (
+        x_center + distance * mid.cos(),
+        y_center + distance * mid.sin(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChartData, ChartOptions, PieChartTool, SilentLog};
+
+    #[test]
+    fn reports_geometry() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: 'Costs', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }] }",
+        )
+        .unwrap();
+        let tool = PieChartTool::new(&SilentLog);
+        let rd = tool
+            .process_chart_data(&chart_data, &ChartOptions::default())
+            .unwrap();
+        let report = report(&[(&rd, (10.0, 20.0))], (500.0, 600.0));
+        let chart = &report.charts[0];
+
+        assert_eq!(chart.wedges[0].start_angle, 0.0);
+        assert_eq!(chart.wedges[0].end_angle, 90.0);
+        assert_eq!(chart.wedges[1].end_angle, 360.0);
+        assert_eq!(chart.center_x, 10.0 + rd.center().0);
+        assert_eq!(chart.legend.len(), 2);
+        assert_eq!(chart.legend[0].y, 20.0 + rd.legend_entries()[0].1);
+        assert!(chart.wedges[0].rects.is_empty());
+    }
+
+    #[test]
+    fn finds_wedge_at_point() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'a', value: 1 }, { key: 'b', value: 3 }], options: { donut: true } }",
+        )
+        .unwrap();
+        let tool = PieChartTool::new(&SilentLog);
+        let options = ChartOptions::default();
+        let report = tool
+            .report(std::slice::from_ref(&chart_data), &options)
+            .unwrap();
+        let chart = &report.charts[0];
+        let (a, b) = (&chart.wedges[0], &chart.wedges[1]);
+        let key_at = |x, y| report.wedge_at(x, y).map(|wedge| wedge.key.as_str());
+
+        // The first wedge is the quarter from 12 to 3 o'clock
+        assert_eq!(key_at(a.centroid_x, a.centroid_y), Some("a"));
+        assert!(a.centroid_x > chart.center_x && a.centroid_y < chart.center_y);
+        assert_eq!(key_at(b.centroid_x, b.centroid_y), Some("b"));
+        assert!(b.centroid_x < chart.center_x);
+        // Nothing is drawn in the hole of the donut or outside the pie
+        assert_eq!(key_at(chart.center_x, chart.center_y), None);
+        assert_eq!(
+            key_at(chart.center_x, chart.center_y - a.outer_radius - 1.0),
+            None
+        );
+
+        let mut chart_data = chart_data;
+
+        chart_data.options = ChartOptions {
+            treemap: Some(true),
+            ..Default::default()
+        };
+
+        let report = tool.report(&[chart_data], &options).unwrap();
+        let b = &report.charts[0].wedges[1];
+        let [left, top, ..] = b.rects[0];
+
+        // The wedges of a treemap are found by their rectangles
+        assert_eq!(
+            report
+                .wedge_at(left + 1.0, top + 1.0)
+                .map(|wedge| wedge.key.as_str()),
+            Some("b")
+        );
+    }
+}
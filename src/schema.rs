@@ -0,0 +1,334 @@
+//! A JSON Schema for chart files, so that editors can complete and check them, and a checker for
+//! the parts of JSON Schema it uses. The options are described from their command line arguments,
+//! so that the two cannot drift apart.
+
+use crate::{ChartOptions, TitleStyle};
+use clap::{Args, Command};
+use serde_json::{json, Map, Value};
+use std::{any::TypeId, path::PathBuf};
+
+pub(crate) fn schema() -> Value {
+    let charts = json!({ "type": "array", "items": { "$ref": "#/$defs/chart" } });
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Pie chart file",
+        "description": "A chart, an array of charts or a `charts` list of them",
+        "anyOf": [
+            { "$ref": "#/$defs/chart" },
+            charts,
+            { "type": "object", "properties": { "charts": charts }, "required": ["charts"] },
+        ],
+        "$defs": {
+            "chart": {
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "Broken onto several lines at each `\\n`",
+                    },
+                    "subtitle": {
+                        "type": "string",
+                        "description": "Drawn below the title in a smaller font",
+                    },
+                    "caption": {
+                        "type": "string",
+                        "description": "Drawn below the legend in a smaller font, such as the source of the data",
+                    },
+                    "source": { "type": "string", "description": "The same as `caption`" },
+                    "items": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/item" },
+                        "description": "Left empty for charts of series, which have items of their own",
+                    },
+                    "series": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "name": { "type": "string" },
+                                "items": { "type": "array", "items": { "$ref": "#/$defs/item" } },
+                            },
+                            "required": ["name", "items"],
+                        },
+                        "description": "Several sets of items drawn as rings around each other, the first innermost",
+                    },
+                    "hole_ratio": {
+                        "type": "number",
+                        "description": "Kept for older chart files, as `hole_ratio` in `options` takes precedence",
+                    },
+                    "seed": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Kept for older chart files, as `seed` in `options` takes precedence",
+                    },
+                    "options": options(),
+                    "styles": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "CSS rules added to the generated styles",
+                    },
+                },
+                "required": ["title"],
+            },
+            "item": {
+                "type": "object",
+                "properties": {
+                    "key": { "type": "string" },
+                    "value": {
+                        "type": "number",
+                        "minimum": 0,
+                        "description": "Ignored for items with children, which take the total of theirs",
+                    },
+                    "color": {
+                        "type": "string",
+                        "description": "Wedge color as hex, `rgb()`, `hsl()` or a named CSS color",
+                    },
+                    "href": {
+                        "type": "string",
+                        "description": "Link followed when the wedge or its legend entry is clicked",
+                    },
+                    "class": {
+                        "type": "string",
+                        "pattern": "^[A-Za-z_][A-Za-z0-9_-]*$",
+                        "description": "CSS class of the wedge and its legend block instead of the generated `wedge-N`",
+                    },
+                    "children": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/item" },
+                        "description": "Items drawn in a ring outside this one by a sunburst chart",
+                    },
+                },
+                "required": ["key"],
+            },
+        },
+    })
+}
+
+/// The options as they are in chart files, each described from the argument of the same id
+fn options() -> Value {
+    let command = ChartOptions::augment_args(Command::new(""));
+    let title_command = TitleStyle::augment_args(Command::new(""));
+    let keys = |value: Value| match value {
+        Value::Object(map) => map.into_iter().map(|(key, _)| key).collect(),
+        _ => vec![],
+    };
+    let mut title_properties = Map::new();
+
+    for key in keys(serde_json::to_value(TitleStyle::default()).unwrap()) {
+        // The title style ids are prefixed to keep them apart from the chart options
+        let id = format!("title_{}", key);
+
+        title_properties.insert(key, argument(&title_command, &id));
+    }
+
+    let mut properties = Map::new();
+
+    for key in keys(serde_json::to_value(ChartOptions::default()).unwrap()) {
+        let property = if key == "title_style" {
+            json!({ "type": "object", "properties": title_properties })
+        } else {
+            argument(&command, &key)
+        };
+
+        properties.insert(key, property);
+    }
+
+    json!({ "type": "object", "properties": properties })
+}
+
+fn argument(command: &Command, id: &str) -> Value {
+    let Some(arg) = command.get_arguments().find(|arg| arg.get_id() == id) else {
+        return json!({});
+    };
+    let type_id = arg.get_value_parser().type_id();
+    // Options can also be null, which is the same as leaving them out
+    let mut schema = if type_id == TypeId::of::<bool>() {
+        json!({ "type": ["boolean", "null"] })
+    } else if !arg.get_possible_values().is_empty() {
+        let mut values: Vec<Value> = arg
+            .get_possible_values()
+            .iter()
+            .map(|value| value.get_name().into())
+            .collect();
+
+        values.push(Value::Null);
+        json!({ "enum": values })
+    } else if type_id == TypeId::of::<f64>() {
+        json!({ "type": ["number", "null"] })
+    } else if type_id == TypeId::of::<u64>() || type_id == TypeId::of::<usize>() {
+        json!({ "type": ["integer", "null"], "minimum": 0 })
+    } else if type_id == TypeId::of::<String>() || type_id == TypeId::of::<PathBuf>() {
+        json!({ "type": ["string", "null"] })
+    } else {
+        json!({})
+    };
+
+    if let Some(help) = arg.get_help() {
+        schema["description"] = Value::String(help.to_string());
+    }
+
+    schema
+}
+
+/// Check a chart file against the schema, returning a message for each place it does not match
+pub(crate) fn validate(value: &Value) -> Vec<String> {
+    let schema = schema();
+    let mut errors = vec![];
+
+    check(&schema, &schema, value, "", &mut errors);
+    errors
+}
+
+fn check(root: &Value, schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let describe =
+        |message: String| format!("{}: {}", if path.is_empty() { "/" } else { path }, message);
+
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/$defs/");
+
+        return check(root, &root["$defs"][name], value, path, errors);
+    }
+
+    if let Some(choices) = schema["anyOf"].as_array() {
+        let mut choice_errors = vec![];
+
+        for choice in choices {
+            let mut errors = vec![];
+
+            check(root, choice, value, path, &mut errors);
+
+            if errors.is_empty() {
+                return;
+            }
+
+            choice_errors.push(errors);
+        }
+
+        // Report the choice that came closest to matching, which is one that at least matched
+        // at this level, if any did
+        let here = describe(String::new());
+
+        if let Some(closest) = choice_errors.into_iter().min_by_key(|errors| {
+            (
+                errors.iter().any(|error| error.starts_with(&here)),
+                errors.len(),
+            )
+        }) {
+            errors.extend(closest);
+        }
+
+        return;
+    }
+
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(value) {
+            let names: Vec<String> = allowed.iter().map(Value::to_string).collect();
+
+            errors.push(describe(format!(
+                "{} is not one of {}",
+                value,
+                names.join(", ")
+            )));
+        }
+
+        return;
+    }
+
+    // A type is a name, or a list of names of which any will do
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(name) => vec![name],
+        Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    let matches_type = |name: &str| match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_u64() || value.is_i64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    };
+
+    if !types.is_empty() && !types.iter().any(|name| matches_type(name)) {
+        errors.push(describe(format!(
+            "expected {} but found {}",
+            types.join(" or "),
+            value
+        )));
+        return;
+    }
+
+    if let (Some(minimum), Some(number)) = (schema["minimum"].as_f64(), value.as_f64()) {
+        if number < minimum {
+            errors.push(describe(format!("{} is less than {}", number, minimum)));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for name in schema["required"].as_array().into_iter().flatten() {
+            if let Some(name) = name.as_str().filter(|name| !object.contains_key(*name)) {
+                errors.push(describe(format!("'{}' is missing", name)));
+            }
+        }
+
+        for (name, property) in schema["properties"].as_object().into_iter().flatten() {
+            if let Some(value) = object.get(name) {
+                check(root, property, value, &format!("{}/{}", path, name), errors);
+            }
+        }
+    }
+
+    if let Some(array) = value.as_array() {
+        for (index, value) in array.iter().enumerate() {
+            check(
+                root,
+                &schema["items"],
+                value,
+                &format!("{}/{}", path, index),
+                errors,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_and_checks_options() {
+        let schema = schema();
+        let options = &schema["$defs"]["chart"]["properties"]["options"]["properties"];
+
+        assert_eq!(options["donut"]["type"], json!(["boolean", "null"]));
+        assert_eq!(options["size"]["type"][0], "number");
+        assert_eq!(options["palette"]["enum"][0], "random");
+        assert_eq!(
+            options["title_style"]["properties"]["font_family"]["type"][0],
+            "string"
+        );
+        assert!(options
+            .as_object()
+            .unwrap()
+            .values()
+            .all(|option| option.as_object().is_some_and(|option| !option.is_empty())));
+
+        assert!(
+            validate(&json!({ "title": "a", "items": [{ "key": "b", "value": 1 }] })).is_empty()
+        );
+        assert_eq!(
+            validate(&json!({
+                "title": "a",
+                "items": [{ "key": "b", "value": "1" }],
+                "options": { "theme": "blue" },
+            })),
+            vec![
+                "/items/0/value: expected number but found \"1\"",
+                "/options/theme: \"blue\" is not one of \"light\", \"dark\", null",
+            ]
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! A small HTTP server for rendering charts from other programs without starting the tool for
+//! each one. The body of each `POST` request is read as a chart file and the reply is the SVG of
+//! its charts, or the error as text. Each connection takes one request. Options that name files
+//! on the disk of the server can only be given to the server itself, not in the charts sent to it.
+
+use crate::{input, ChartOptions, PieChartError, PieChartTool};
+use std::io::{self, BufRead, Write};
+
+/// Chart files larger than this are refused rather than read into memory
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+/// The status, content type and body of a reply
+pub(crate) struct Response {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn text(status: &'static str, text: impl Into<String>) -> Response {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: (text.into() + "\n").into_bytes(),
+        }
+    }
+
+    pub fn write(self: &Self, writer: &mut impl Write) -> io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        )?;
+
+        if self.status.starts_with("405") {
+            write!(writer, "Allow: POST\r\n")?;
+        }
+
+        write!(writer, "\r\n")?;
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
+
+/// Read a request and render the charts in its body, with the options given to the server taking
+/// precedence over those in the chart file, which in turn take precedence over the config file.
+/// Charts that set options naming files are refused, as the client could read any file with them.
+pub(crate) fn respond(
+    tool: &PieChartTool,
+    reader: &mut impl BufRead,
+    options: &ChartOptions,
+    config: &ChartOptions,
+) -> Response {
+    let body = match read_request(reader) {
+        Ok(Some(body)) => body,
+        Ok(None) => {
+            return Response::text(
+                "405 Method Not Allowed",
+                "Send a chart file as the body of a POST request",
+            )
+        }
+        Err(message) => return Response::text("400 Bad Request", message),
+    };
+    let render = || -> Result<String, PieChartError> {
+        let mut charts = input::read(&body, None, None, "", &input::Table::default())?;
+
+        for chart_data in charts.iter_mut() {
+            if chart_data.options.css.is_some() || chart_data.options.embed_font.is_some() {
+                return Err(PieChartError::Validation(
+                    "Charts sent to the server cannot set 'css' or 'embed_font', which name files on its disk"
+                        .to_string(),
+                ));
+            }
+
+            chart_data.options = chart_data.options.or(config);
+        }
+
+        tool.svg_text(&charts, options)
+    };
+
+    match render() {
+        Ok(svg) => Response {
+            status: "200 OK",
+            content_type: "image/svg+xml",
+            body: svg.into_bytes(),
+        },
+        Err(error) => Response::text("400 Bad Request", error.to_string()),
+    }
+}
+
+/// The body of a `POST` request, or `None` for any other method
+fn read_request(reader: &mut impl BufRead) -> Result<Option<Vec<u8>>, String> {
+    let mut line = String::new();
+    let mut read_line = |line: &mut String| {
+        line.clear();
+        reader
+            .read_line(line)
+            .map_err(|e| format!("Unable to read the request: {}", e))
+    };
+
+    read_line(&mut line)?;
+
+    let method = line
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let mut length = None;
+
+    loop {
+        if read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    if method != "POST" {
+        return Ok(None);
+    }
+
+    let length = match length {
+        Some(length) if length <= MAX_BODY => length,
+        Some(_) => return Err(format!("Chart files can be at most {} bytes", MAX_BODY)),
+        None => return Err("The request has no Content-Length".to_string()),
+    };
+    let mut body = vec![0; length];
+
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| format!("Unable to read the request body: {}", e))?;
+
+    Ok(Some(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SilentLog;
+
+    #[test]
+    fn renders_posted_charts() {
+        let tool = PieChartTool::new(&SilentLog);
+        let chart = "{title: 'Costs', items: [{key: 'a', value: 1}]}";
+        let request = format!(
+            "POST / HTTP/1.1\r\nHost: localhost\r\ncontent-length: {}\r\n\r\n{}",
+            chart.len(),
+            chart
+        );
+        let respond = |request: &str| {
+            respond(
+                &tool,
+                &mut request.as_bytes(),
+                &ChartOptions::default(),
+                &ChartOptions::default(),
+            )
+        };
+        let response = respond(&request);
+        let mut bytes = vec![];
+
+        response.write(&mut bytes).unwrap();
+
+        let text = String::from_utf8(bytes).unwrap();
+
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\nContent-Type: image/svg+xml\r\n"));
+        assert!(text.contains("\r\n\r\n<svg"));
+        assert_eq!(
+            respond("GET / HTTP/1.1\r\n\r\n").status,
+            "405 Method Not Allowed"
+        );
+
+        let broken = respond("POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}");
+
+        assert_eq!(broken.status, "400 Bad Request");
+        assert!(String::from_utf8(broken.body).unwrap().contains("title"));
+
+        // Files on the disk of the server cannot be read by naming them in the chart options
+        let chart = "{title: 'x', items: [{key: 'a', value: 1}], options: {css: 'Cargo.toml'}}";
+        let reading = respond(&format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            chart.len(),
+            chart
+        ));
+        let body = String::from_utf8(reading.body).unwrap();
+
+        assert_eq!(reading.status, "400 Bad Request");
+        assert!(body.contains("css") && !body.contains("[package]"));
+    }
+}
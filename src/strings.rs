@@ -0,0 +1,244 @@
+//! The built-in text of charts and warnings, looked up by the language of the locale. Text that
+//! is not translated into a language is given in English. Warnings name command line options, so
+//! they have no translations yet.
+
+use std::fmt::Display;
+
+/// A piece of built-in text, which may have `{}` placeholders for its arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Message {
+    /// The key of the item holding the items that are grouped together
+    Other,
+    /// The change of an item that is only in the later chart
+    New,
+    /// The heading of the change column of a diff
+    Change,
+    ChangeFrom,
+    /// The description of the chart read by screen readers
+    Description,
+    LabelTooWide,
+    LabelsOverlap,
+    LegendTruncated,
+    NegativeSkipped,
+    NegativeFlipped,
+    ZeroSkipped,
+    DuplicateKey,
+    PaletteRepeats,
+    HardToTellApart,
+    CenterTextNeedsDonut,
+}
+
+/// The translations of each language, after which text falls back to English
+const TRANSLATIONS: [(&str, [&str; 5]); 9] = [
+    (
+        "de",
+        [
+            "Sonstige",
+            "neu",
+            "Änderung",
+            "Änderung von {} zu {}",
+            "Kreisdiagramm mit {} Segmenten: {}",
+        ],
+    ),
+    (
+        "es",
+        [
+            "Otros",
+            "nuevo",
+            "Cambio",
+            "Cambio de {} a {}",
+            "Gráfico circular con {} sectores: {}",
+        ],
+    ),
+    (
+        "fr",
+        [
+            "Autres",
+            "nouveau",
+            "Évolution",
+            "Évolution de {} à {}",
+            "Graphique circulaire de {} secteurs : {}",
+        ],
+    ),
+    (
+        "it",
+        [
+            "Altro",
+            "nuovo",
+            "Variazione",
+            "Variazione da {} a {}",
+            "Grafico a torta con {} spicchi: {}",
+        ],
+    ),
+    (
+        "nl",
+        [
+            "Overig",
+            "nieuw",
+            "Verandering",
+            "Verandering van {} naar {}",
+            "Cirkeldiagram met {} segmenten: {}",
+        ],
+    ),
+    (
+        "pt",
+        [
+            "Outros",
+            "novo",
+            "Variação",
+            "Variação de {} para {}",
+            "Gráfico de pizza com {} fatias: {}",
+        ],
+    ),
+    (
+        "sv",
+        [
+            "Övrigt",
+            "ny",
+            "Förändring",
+            "Förändring från {} till {}",
+            "Cirkeldiagram med {} sektorer: {}",
+        ],
+    ),
+    (
+        "he",
+        [
+            "אחר",
+            "חדש",
+            "שינוי",
+            "שינוי מ־{} ל־{}",
+            "תרשים עוגה עם {} פלחים: {}",
+        ],
+    ),
+    (
+        "ar",
+        [
+            "أخرى",
+            "جديد",
+            "التغير",
+            "التغير من {} إلى {}",
+            "مخطط دائري يضم {} قطاعات: {}",
+        ],
+    ),
+];
+
+/// Languages that are written right to left
+const RTL_LANGUAGES: [&str; 2] = ["he", "ar"];
+
+/// The built-in text in one language
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Strings {
+    translations: Option<&'static [&'static str; 5]>,
+    /// Whether the language is written right to left
+    pub rtl: bool,
+}
+
+impl Strings {
+    /// The text for a locale tag such as `de` or `he-IL`, which is English for languages that have
+    /// no translations
+    pub fn from_tag(tag: &str) -> Strings {
+        let language = tag
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        Strings {
+            translations: TRANSLATIONS
+                .iter()
+                .find(|(code, _)| *code == language)
+                .map(|(_, translations)| translations),
+            rtl: RTL_LANGUAGES.contains(&language.as_str()),
+        }
+    }
+
+    pub fn get(self: &Self, message: Message) -> &'static str {
+        let translated = match message {
+            Message::Other => Some(0),
+            Message::New => Some(1),
+            Message::Change => Some(2),
+            Message::ChangeFrom => Some(3),
+            Message::Description => Some(4),
+            _ => None,
+        };
+
+        match (self.translations, translated) {
+            (Some(translations), Some(index)) => translations[index],
+            _ => english(message),
+        }
+    }
+
+    /// The text with its placeholders replaced by the arguments in turn
+    pub fn format(self: &Self, message: Message, args: &[&dyn Display]) -> String {
+        let mut parts = self.get(message).split("{}");
+        let mut text = parts.next().unwrap_or_default().to_string();
+
+        for (part, arg) in parts.zip(args) {
+            text.push_str(&arg.to_string());
+            text.push_str(part);
+        }
+
+        text
+    }
+}
+
+fn english(message: Message) -> &'static str {
+    match message {
+        Message::Other => "Other",
+        Message::New => "new",
+        Message::Change => "Change",
+        Message::ChangeFrom => "Change from {} to {}",
+        Message::Description => "Pie chart with {} wedges: {}",
+        Message::LabelTooWide => {
+            "Label '{}' is wider than its wedge, so try '--label-position outside'"
+        }
+        Message::LabelsOverlap => {
+            "Labels outside the pie overlap as there is no room for them all, so try '--top'"
+        }
+        Message::LegendTruncated => "Legend entry for '{}' is truncated to '{}'",
+        Message::NegativeSkipped => "Skipping '{}' as its value {} is negative",
+        Message::NegativeFlipped => "Using {} for '{}' in place of its negative value",
+        Message::ZeroSkipped => "Skipping '{}' as its value is zero",
+        Message::DuplicateKey => "Key '{}' is used by {} items, so try '--merge-duplicates'",
+        Message::PaletteRepeats => {
+            "Palette has only {} colors for {} items, so some colors will repeat"
+        }
+        Message::HardToTellApart => "Wedges '{}' and '{}' may be hard to tell apart with {}",
+        Message::CenterTextNeedsDonut => {
+            "Center text is only drawn in the hole of a donut, so try '--donut'"
+        }
+    }
+}
+
+/// Whether text is written right to left, which is when its first letter is Hebrew or Arabic
+pub(crate) fn is_rtl(text: &str) -> bool {
+    text.chars()
+        .find(|c| c.is_alphabetic())
+        .is_some_and(|c| matches!(c as u32, 0x0590..=0x08ff | 0xfb1d..=0xfdff | 0xfe70..=0xfeff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_text() {
+        let hebrew = Strings::from_tag("he-IL");
+
+        assert_eq!(hebrew.get(Message::Other), "אחר");
+        assert!(hebrew.rtl);
+        assert_eq!(
+            Strings::from_tag("de").format(Message::ChangeFrom, &[&"Mai", &"Juni"]),
+            "Änderung von Mai zu Juni"
+        );
+        // Warnings and unknown languages are in English
+        assert_eq!(
+            Strings::from_tag("fr").format(Message::ZeroSkipped, &[&"a"]),
+            "Skipping 'a' as its value is zero"
+        );
+        assert_eq!(Strings::from_tag("ja").get(Message::Other), "Other");
+        assert!(!Strings::default().rtl);
+        assert!(is_rtl("42 שקל"));
+        assert!(!is_rtl("Costs (עלויות)"));
+    }
+}
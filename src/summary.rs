@@ -0,0 +1,93 @@
+//! A table of the numbers behind a chart, for reading alongside the picture: the value of each
+//! wedge, its exact percentage of the total and the running total of the percentages, in the order
+//! the wedges are drawn.
+
+use crate::RenderData;
+
+/// The lines of the table, with the keys left aligned and the numbers right aligned under their
+/// headings, ending with the total
+pub(crate) fn table(rd: &RenderData) -> Vec<String> {
+    let mut cumulative = 0.0;
+    let mut rows = vec![[
+        "Key".to_string(),
+        "Value".to_string(),
+        "Percent".to_string(),
+        "Cumulative".to_string(),
+    ]];
+
+    for wedge in &rd.wedges {
+        cumulative += wedge.percentage;
+        rows.push([
+            wedge.key.clone(),
+            wedge.value.to_string(),
+            format!("{:.2}%", wedge.percentage * 100.0),
+            format!("{:.2}%", cumulative * 100.0),
+        ]);
+    }
+
+    rows.push([
+        "Total".to_string(),
+        rd.wedges
+            .iter()
+            .map(|wedge| wedge.value)
+            .sum::<f64>()
+            .to_string(),
+        format!("{:.2}%", cumulative * 100.0),
+        String::new(),
+    ]);
+
+    let widths: Vec<usize> = (0..4)
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            let line = format!(
+                "{:<key$}  {:>value$}  {:>percent$}  {:>cumulative$}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                key = widths[0],
+                value = widths[1],
+                percent = widths[2],
+                cumulative = widths[3]
+            );
+
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChartData, ChartOptions, PieChartTool, SilentLog};
+
+    #[test]
+    fn totals_wedges() {
+        let chart_data: ChartData = json5::from_str(
+            "{ title: '', items: [{ key: 'Rent', value: 1200 }, { key: 'Food', value: 400 }, { key: 'Fun', value: 0 }] }",
+        )
+        .unwrap();
+        let rd = PieChartTool::new(&SilentLog)
+            .process_chart_data(&chart_data, &ChartOptions::default())
+            .unwrap();
+
+        assert_eq!(
+            table(&rd),
+            [
+                "Key    Value  Percent  Cumulative",
+                "Rent    1200   75.00%      75.00%",
+                "Food     400   25.00%     100.00%",
+                "Fun        0    0.00%     100.00%",
+                "Total   1600  100.00%",
+            ]
+        );
+    }
+}
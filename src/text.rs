@@ -0,0 +1,126 @@
+//! Text measurement for laying out legends. Widths are those of the embedded font when there is
+//! one, and otherwise those of Helvetica, which is close enough to Arial and most other sans-serif
+//! fonts to keep text from running into its neighbors.
+
+mod font;
+
+/// Helvetica widths of the printable ASCII characters, in thousandths of the font size
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Measures text in a font read from a file, or in Helvetica
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    font: Option<font::Font>,
+}
+
+impl Metrics {
+    /// The metrics of a TrueType, OpenType or WOFF font file, or `None` if it cannot be read
+    pub(crate) fn from_font(data: &[u8]) -> Option<Metrics> {
+        Some(Metrics {
+            font: Some(font::Font::parse(data)?),
+        })
+    }
+
+    /// The width of the text at the given font size, in the same units as the size
+    pub(crate) fn width(self: &Self, text: &str, size: f64) -> f64 {
+        match self.font {
+            Some(ref font) => font.width(text, size),
+            None => width(text, size),
+        }
+    }
+
+    /// Break the text into lines no wider than `max_width`, between words where possible
+    pub(crate) fn wrap(self: &Self, text: &str, max_width: f64, size: f64) -> Vec<String> {
+        let mut lines = vec![];
+        let mut line = String::new();
+
+        for word in text.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+
+            if self.width(&candidate, size) <= max_width {
+                line = candidate;
+                continue;
+            }
+
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+
+            // Words too long for a line of their own are broken between characters
+            for c in word.chars() {
+                line.push(c);
+
+                if self.width(&line, size) > max_width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+
+        if !line.is_empty() || lines.is_empty() {
+            lines.push(line);
+        }
+
+        lines
+    }
+}
+
+/// The width of the text in Helvetica at the given font size, in the same units as the size
+pub(crate) fn width(text: &str, size: f64) -> f64 {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => HELVETICA_WIDTHS[c as usize - ' ' as usize],
+            _ => 556,
+        } as f64)
+        .sum::<f64>()
+        * size
+        / 1000.0
+}
+
+/// Shorten text longer than `max_chars` characters, ending it with an ellipsis
+pub(crate) fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+
+    truncated.truncate(truncated.trim_end().len());
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_and_truncates() {
+        let metrics = Metrics::default();
+
+        assert_eq!(width("Hi", 10.0), 9.44);
+        assert_eq!(
+            metrics.wrap("Peak Hour Traffic", 60.0, 10.0),
+            ["Peak Hour", "Traffic"]
+        );
+        assert_eq!(metrics.wrap("Supercalifragilistic", 40.0, 10.0).len(), 3);
+        assert_eq!(metrics.wrap("", 40.0, 10.0), [""]);
+
+        let metrics = Metrics::from_font(&font::tests::font_file()).unwrap();
+
+        assert_eq!(metrics.width("AB", 10.0), 3.0);
+        assert_eq!(metrics.wrap("AB CC", 6.0, 10.0), ["AB", "CC"]);
+        assert_eq!(truncate("Short", 5), "Short");
+        assert_eq!(truncate("Very long key", 6), "Very…");
+    }
+}
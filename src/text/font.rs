@@ -0,0 +1,262 @@
+//! Advance widths from TrueType and OpenType fonts, and WOFF files of them. Only the `head`,
+//! `hhea`, `hmtx` and `cmap` tables are read, which is all that measuring a line of text needs.
+
+use crate::input::zip;
+use std::{borrow::Cow, collections::HashMap};
+
+fn u16_at(data: &[u8], pos: usize) -> Option<usize> {
+    data.get(pos..pos + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Option<usize> {
+    data.get(pos..pos + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+}
+
+#[derive(Debug)]
+pub(crate) struct Font {
+    units_per_em: f64,
+    /// The advance width of each glyph, the last of which also applies to every later glyph
+    advances: Vec<usize>,
+    /// The Unicode character map, as its format and its subtable
+    cmap: (usize, Vec<u8>),
+}
+
+impl Font {
+    /// Read a font file, or return `None` if it is not a font or lacks a table
+    pub(crate) fn parse(data: &[u8]) -> Option<Font> {
+        let tables = tables(data)?;
+        let head = tables.get(b"head")?;
+        let hhea = tables.get(b"hhea")?;
+        let hmtx = tables.get(b"hmtx")?;
+        let units_per_em = u16_at(head, 18).filter(|units| *units > 0)? as f64;
+        let advances = (0..u16_at(hhea, 34)?)
+            .map(|index| u16_at(hmtx, index * 4))
+            .collect::<Option<Vec<usize>>>()
+            .filter(|advances| !advances.is_empty())?;
+
+        Some(Font {
+            units_per_em,
+            advances,
+            cmap: unicode_cmap(tables.get(b"cmap")?)?,
+        })
+    }
+
+    /// The width of the text at the given font size, in the same units as the size. Characters
+    /// the font does not have take the width of its missing glyph.
+    pub(crate) fn width(self: &Self, text: &str, size: f64) -> f64 {
+        let units: usize = text
+            .chars()
+            .map(|c| {
+                let glyph = self.glyph(c as usize).unwrap_or(0);
+
+                self.advances
+                    .get(glyph)
+                    .or(self.advances.last())
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .sum();
+
+        units as f64 * size / self.units_per_em
+    }
+
+    fn glyph(self: &Self, c: usize) -> Option<usize> {
+        let (format, ref table) = self.cmap;
+
+        if format == 12 {
+            // The groups the table claims are limited to those it has room for, so that a broken
+            // or hostile font cannot make every character take billions of steps
+            let groups = u32_at(table, 12)?.min(table.len().saturating_sub(16) / 12);
+
+            return (0..groups).find_map(|group| {
+                let pos = 16 + group * 12;
+                let (start, end) = (u32_at(table, pos)?, u32_at(table, pos + 4)?);
+
+                if (start..=end).contains(&c) {
+                    Some(u32_at(table, pos + 8)? + c - start)
+                } else {
+                    None
+                }
+            });
+        }
+
+        // Format 4 maps the first plane in segments, each given by parallel arrays
+        let segments = u16_at(table, 6)? / 2;
+        let ends = 14;
+        let starts = ends + segments * 2 + 2;
+        let deltas = starts + segments * 2;
+        let range_offsets = deltas + segments * 2;
+        let segment = (0..segments)
+            .find(|segment| u16_at(table, ends + segment * 2).is_some_and(|end| c <= end))?;
+        let start = u16_at(table, starts + segment * 2).filter(|start| c >= *start)?;
+        let delta = u16_at(table, deltas + segment * 2)?;
+        let range_offset = u16_at(table, range_offsets + segment * 2)?;
+
+        if range_offset == 0 {
+            return Some((c + delta) & 0xffff);
+        }
+
+        // The glyphs are then found relative to the range offset itself
+        let glyph = u16_at(
+            table,
+            range_offsets + segment * 2 + range_offset + (c - start) * 2,
+        )?;
+
+        (glyph != 0).then_some((glyph + delta) & 0xffff)
+    }
+}
+
+/// The tables of an sfnt font, or of a WOFF file once they are decompressed
+fn tables(data: &[u8]) -> Option<HashMap<[u8; 4], Cow<'_, [u8]>>> {
+    let mut tables = HashMap::new();
+    let woff = data.starts_with(b"wOFF");
+    let (count, directory, record_len) = if woff {
+        (u16_at(data, 12)?, 44, 20)
+    } else if data.starts_with(&[0, 1, 0, 0]) || data.starts_with(b"OTTO") {
+        (u16_at(data, 4)?, 12, 16)
+    } else {
+        return None;
+    };
+
+    for index in 0..count {
+        let record = directory + index * record_len;
+        let tag: [u8; 4] = data.get(record..record + 4)?.try_into().ok()?;
+        let offset = u32_at(data, record + 8)?;
+        let (length, original_length) = if woff {
+            (u32_at(data, record + 12)?, u32_at(data, record + 16)?)
+        } else {
+            let length = u32_at(data, record + 12)?;
+
+            (length, length)
+        };
+        let table = data.get(offset..offset + length)?;
+
+        // WOFF tables that are smaller than they were are zlib streams, after a two byte header
+        let table = if length < original_length {
+            Cow::Owned(zip::inflate(table.get(2..)?).ok()?)
+        } else {
+            Cow::Borrowed(table)
+        };
+
+        tables.insert(tag, table);
+    }
+
+    Some(tables)
+}
+
+/// The subtable mapping Unicode to glyphs, preferring one that covers all the planes
+fn unicode_cmap(cmap: &[u8]) -> Option<(usize, Vec<u8>)> {
+    let mut found: Option<(usize, Vec<u8>)> = None;
+
+    for index in 0..u16_at(cmap, 2)? {
+        let record = 4 + index * 8;
+        let (platform, encoding) = (u16_at(cmap, record)?, u16_at(cmap, record + 2)?);
+
+        if !(platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10))) {
+            continue;
+        }
+
+        let table = cmap.get(u32_at(cmap, record + 4)?..)?;
+        let format = u16_at(table, 0)?;
+
+        if format == 12 || (format == 4 && found.is_none()) {
+            found = Some((format, table.to_vec()));
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A font with glyphs for 'A' to 'C', each 100 units wider than the last, and a missing glyph
+    /// of 300 units, on a 1000 unit em
+    pub(crate) fn font_file() -> Vec<u8> {
+        let be16 =
+            |values: &[u16]| -> Vec<u8> { values.iter().flat_map(|v| v.to_be_bytes()).collect() };
+        let mut head = vec![0; 54];
+
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+
+        let mut hhea = vec![0; 36];
+
+        hhea[34..36].copy_from_slice(&4u16.to_be_bytes());
+
+        let hmtx = be16(&[300, 0, 100, 0, 200, 0, 300, 0]);
+        // Two segments, 'A' to 'C' mapped to glyphs 1 to 3 and the final 0xffff
+        let subtable = be16(&[
+            4,
+            32,
+            0,
+            4,
+            0,
+            0,
+            0,
+            0x43,
+            0xffff,
+            0,
+            0x41,
+            0xffff,
+            1u16.wrapping_sub(0x41),
+            1,
+            0,
+            0,
+        ]);
+        let cmap = [be16(&[0, 1, 3, 1, 0, 12]), subtable].concat();
+        let tables: [(&[u8; 4], Vec<u8>); 4] = [
+            (b"cmap", cmap),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+        ];
+        let mut data = [vec![0, 1, 0, 0], be16(&[tables.len() as u16, 0, 0, 0])].concat();
+        let mut offset = 12 + tables.len() * 16;
+
+        for (tag, table) in &tables {
+            data.extend(*tag);
+            data.extend([0; 4]);
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += table.len();
+        }
+
+        for (_, table) in tables {
+            data.extend(table);
+        }
+
+        data
+    }
+
+    #[test]
+    fn reads_advance_widths() {
+        let font = Font::parse(&font_file()).unwrap();
+
+        assert_eq!(font.width("A", 10.0), 1.0);
+        assert_eq!(font.width("BC", 10.0), 5.0);
+        assert_eq!(font.width("z", 10.0), 3.0);
+        assert!(Font::parse(b"not a font").is_none());
+    }
+
+    #[test]
+    fn limits_groups_to_the_table() {
+        let be32 =
+            |values: &[u32]| -> Vec<u8> { values.iter().flat_map(|v| v.to_be_bytes()).collect() };
+        // One group of 'A' to 'C' mapped to glyphs 1 to 3, in a table claiming 0xffffffff groups
+        let font = Font {
+            units_per_em: 1000.0,
+            advances: vec![300, 100, 200, 300],
+            cmap: (
+                12,
+                [be32(&[12 << 16, 28, 0, u32::MAX]), be32(&[0x41, 0x43, 1])].concat(),
+            ),
+        };
+
+        assert_eq!(font.glyph('B' as usize), Some(2));
+        assert_eq!(font.glyph('z' as usize), None);
+        assert_eq!(font.width("Az", 10.0), 4.0);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::Palette;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// The overall look of the chart
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Dark text on a white background
+    #[default]
+    Light,
+    /// Light text on a dark background
+    Dark,
+}
+
+impl Theme {
+    /// The document background color
+    pub fn background(self: &Self) -> &'static str {
+        match self {
+            Theme::Light => "white",
+            Theme::Dark => "#1e1e1e",
+        }
+    }
+
+    /// The color of the title and legend text
+    pub fn text_color(self: &Self) -> &'static str {
+        match self {
+            Theme::Light => "#000000",
+            Theme::Dark => "#e0e0e0",
+        }
+    }
+
+    /// The color and width of the line drawn around each wedge, if any
+    pub fn wedge_stroke(self: &Self) -> Option<(&'static str, f64)> {
+        match self {
+            Theme::Light => None,
+            Theme::Dark => Some(("#1e1e1e", 1.0)),
+        }
+    }
+
+    /// The palette used when none is given
+    pub fn palette(self: &Self) -> Palette {
+        match self {
+            Theme::Light => Palette::Random,
+            Theme::Dark => Palette::Tableau10,
+        }
+    }
+}